@@ -1,31 +1,44 @@
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use log::{error, info};
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use tokio::sync::OnceCell;
 use crate::config::CURRENT_DIR;
 use crate::entities::table::create_all_need_table;
 use crate::error::AppResult;
 
-pub static DB: OnceLock<DatabaseConnection> = OnceLock::new();
+pub static DB: OnceCell<DatabaseConnection> = OnceCell::const_new();
 
 pub fn get_db_coon()->&'static DatabaseConnection{
     DB.get().expect("数据库连接不存在")
 }
 
 ///实体类，对应数据库中的行数据
+/// 可以重复调用，只有第一次调用会真正建立连接
 pub async fn init_db_coon() {
+    DB.get_or_init(connect_db).await;
+}
+/// 测试时每个进程使用独立的临时数据库，避免测试之间互相影响
+fn db_path() -> PathBuf {
+    if cfg!(test) {
+        std::env::temp_dir().join(format!("order-test-{}.db", std::process::id()))
+    } else {
+        CURRENT_DIR.join("data").join("data.db")
+    }
+}
+async fn connect_db() -> DatabaseConnection {
     let current_dir = CURRENT_DIR.clone();
-    let db_path = CURRENT_DIR.join("data").join("data.db").to_string_lossy().to_string();
-    let exist = match check_db_file(&db_path, &current_dir) {
-        Ok(flag) => flag,
-        Err(e) => {
-            error!("数据库文件不存在，创建数据库文件{:?}失败:{}", db_path, e);
-            panic!("数据库文件不存在，创建数据库文件{:?}失败:{}", db_path, e)
-        }
-    };
+    let db_path = db_path().to_string_lossy().to_string();
+    if cfg!(test) {
+        //进程id可能被复用，清掉之前遗留的测试数据库
+        let _ = fs::remove_file(&db_path);
+    }
+    if let Err(e) = check_db_file(&db_path, &current_dir) {
+        error!("数据库文件不存在，创建数据库文件{:?}失败:{}", db_path, e);
+        panic!("数据库文件不存在，创建数据库文件{:?}失败:{}", db_path, e)
+    }
     let url = format!("sqlite:{}?mode=rwc", db_path);
     let mut opt = ConnectOptions::new(url);
     opt.max_connections(1000)
@@ -34,10 +47,9 @@ pub async fn init_db_coon() {
         .idle_timeout(Duration::from_secs(8))
         .sqlx_logging(false);
     let db = Database::connect(opt).await.expect("数据库打开失败");
-    if !exist {
-        let _ = create_all_need_table(&db).await;
-    };
-    DB.set(db).expect("数据库创建失败");
+    //已存在的表会跳过，这样新增的表在旧数据库上也能创建
+    create_all_need_table(&db).await;
+    db
 }
 ///打开数据库的日志
 #[allow(dead_code)] //function `open_db_log` is never used 这个只有少数需要查看数据库日志时才使用
@@ -47,14 +59,14 @@ pub async fn open_db_log() {
         .with_test_writer()
         .init();
 }
-pub fn check_db_file(path: &str, current_dir: &PathBuf) -> AppResult<bool> {
+pub fn check_db_file(path: &str, current_dir: &Path) -> AppResult<bool> {
     if PathBuf::from(path).exists() {
         info!("数据库存在");
         Ok(true)
     } else {
         info!("数据库不存在,创建数据库。");
-        let _ = fs::create_dir_all(current_dir.join("data"))?;
-        let _ = File::create(path)?;
+        fs::create_dir_all(current_dir.join("data"))?;
+        File::create(path)?;
         Ok(false)
     }
 }
\ No newline at end of file
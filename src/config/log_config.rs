@@ -62,7 +62,7 @@ pub fn init_logger(log_config: &LogConfig) -> WorkerGuard {
         .with_level(true) //表示在日志中包含日志级别（如 INFO、ERROR 等）。这有助于快速识别日志的严重性。
         .with_thread_names(true)
         .with_timer(local_time.clone())
-        .with_filter(EnvFilter::new(log_config.level.to_string()));
+        .with_filter(EnvFilter::new(&log_config.level));
 
     // 配置控制台日志
     let console_layer = fmt::layer()
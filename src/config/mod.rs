@@ -2,21 +2,23 @@ pub mod db;
 pub mod log_config;
 
 use std::{env, fs};
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, OnceLock};
 use log::info;
 use log::error;
 use rand::distr::Alphanumeric;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+use time::UtcOffset;
 use crate::config::log_config::LogConfig;
 use crate::error::AppResult;
 
 pub static CURRENT_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
-    let current_dir = env::current_dir().expect("无法获取当前目录");
-    current_dir
+    env::current_dir().expect("无法获取当前目录")
 });
 
 pub static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
@@ -28,11 +30,12 @@ pub fn get_config() -> &'static ServerConfig {
 /// 只应该在初始化时调用一次，后续需要配置时请使用[get_config()]
 pub fn load_config()->&'static ServerConfig{
     let config = check_config_file(&CURRENT_DIR.join("data").join("config.toml"), &CURRENT_DIR).expect("无法加载配置文件");
+    config.store.offset().expect("store.utc_offset格式错误，应为形如+08:00的时区偏移");
     CONFIG.set(config.clone()).expect("无法设置config");
     CONFIG.get().expect("config should be set")
 }
 
-fn check_config_file(path: &PathBuf, current_dir: &PathBuf) -> AppResult<ServerConfig> {
+fn check_config_file(path: &Path, current_dir: &Path) -> AppResult<ServerConfig> {
     let mut config_file: File = if path.exists() {
         info!("配置存在");
         if let Ok(config) = toml::from_str::<ServerConfig>(&fs::read_to_string(path)?) {
@@ -65,6 +68,14 @@ pub struct ServerConfig {
     pub listen_addr: String,
     pub jwt: JwtConfig,
     pub log: LogConfig,
+    #[serde(default)]
+    pub store: StoreConfig,
+    #[serde(default)]
+    pub print: PrintConfig,
+    #[serde(default)]
+    pub image: ImageConfig,
+    #[serde(default)]
+    pub member: MemberConfig,
 }
 impl Default for ServerConfig {
     fn default() -> Self {
@@ -72,6 +83,10 @@ impl Default for ServerConfig {
             listen_addr: "127.0.0.1:8008".into(),
             jwt: JwtConfig::default(),
             log: LogConfig::default(),
+            store: StoreConfig::default(),
+            print: PrintConfig::default(),
+            image: ImageConfig::default(),
+            member: MemberConfig::default(),
         }
     }
 }
@@ -79,6 +94,12 @@ impl Default for ServerConfig {
 pub struct JwtConfig {
     pub secret: String,
     pub expiry: i64,
+    // 顾客扫码点餐令牌的有效期(秒)，旧配置文件没有这一项时使用默认值
+    #[serde(default = "default_table_expiry")]
+    pub table_expiry: i64,
+    // 会员令牌的有效期(秒)
+    #[serde(default = "default_member_expiry")]
+    pub member_expiry: i64,
 }
 
 impl Default for JwtConfig {
@@ -86,9 +107,108 @@ impl Default for JwtConfig {
         JwtConfig {
             secret: generate_secret(32),
             expiry: 3600,
+            table_expiry: default_table_expiry(),
+            member_expiry: default_member_expiry(),
         }
     }
 }
+fn default_table_expiry() -> i64 {
+    7200
+}
+fn default_member_expiry() -> i64 {
+    30 * 24 * 3600
+}
+/// 门店配置
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct StoreConfig {
+    /// 门店所在时区相对UTC的偏移，形如+08:00，所有记录的时间都是这个时区的本地时间
+    pub utc_offset: String,
+    /// 营业日从几点开始，营业到凌晨的门店可以设为4，凌晨4点前的订单算作前一个营业日
+    pub day_start_hour: u8,
+}
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            utc_offset: "+08:00".into(),
+            day_start_hour: 0,
+        }
+    }
+}
+impl StoreConfig {
+    pub fn offset(&self) -> AppResult<UtcOffset> {
+        let format = format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+        UtcOffset::parse(&self.utc_offset, format)
+            .map_err(|e| anyhow::anyhow!("invalid utc_offset {}: {}", self.utc_offset, e).into())
+    }
+}
+/// 小票打印配置
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PrintConfig {
+    pub shop_name: String,
+    /// 每行可打印的半角字符数，58mm纸为32，80mm纸为48
+    pub width: usize,
+    /// 打印结账小票的打印机
+    pub receipt: Option<PrinterConfig>,
+    /// 没有单独配置打印机的档口都打印到这里
+    pub kitchen: Option<PrinterConfig>,
+    /// 档口名称到出单打印机的映射
+    #[serde(default)]
+    pub stations: HashMap<String, PrinterConfig>,
+}
+impl Default for PrintConfig {
+    fn default() -> Self {
+        //默认写到文件里，方便没有打印机时本地调试
+        PrintConfig {
+            shop_name: "".into(),
+            width: 32,
+            receipt: Some(PrinterConfig::File { path: "data/print/receipt.bin".into() }),
+            kitchen: Some(PrinterConfig::File { path: "data/print/kitchen.bin".into() }),
+            stations: HashMap::new(),
+        }
+    }
+}
+impl PrintConfig {
+    pub fn station_printer(&self, station: Option<&str>) -> Option<&PrinterConfig> {
+        station
+            .and_then(|station| self.stations.get(station))
+            .or(self.kitchen.as_ref())
+    }
+}
+/// 菜品图片上传配置
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ImageConfig {
+    /// 上传图片的最大字节数
+    pub max_size: u64,
+    /// 缩略图的最长边像素
+    pub thumbnail_size: u32,
+}
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            max_size: 5 * 1024 * 1024,
+            thumbnail_size: 320,
+        }
+    }
+}
+/// 会员积分配置，1积分抵扣1分钱
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MemberConfig {
+    /// 订单每实付1元获得的积分
+    pub points_per_yuan: i64,
+}
+impl Default for MemberConfig {
+    fn default() -> Self {
+        MemberConfig { points_per_yuan: 1 }
+    }
+}
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PrinterConfig {
+    /// 网口打印机，一般是9100端口的raw协议
+    Tcp { addr: String },
+    /// 追加写入文件，相对路径相对于程序运行目录
+    File { path: String },
+}
 fn generate_secret(length: usize) -> String {
     let mut rng = rand::rng();
     let secret: String = (0..length)
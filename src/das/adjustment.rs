@@ -0,0 +1,350 @@
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::bill_split::BillSplitCurd;
+use crate::das::member::MemberCurd;
+use crate::das::pricing::PricingCurd;
+use crate::das::promotion::PromotionCurd;
+use crate::das::report::ReportCurd;
+use crate::das::stock::StockCurd;
+use crate::das::users::UserCurd;
+use crate::dto::adjustment::{ApproverData, OrderAdjustments, RefundData, VoidItemData};
+use crate::entities::item_void::ReasonCode;
+use crate::entities::order::{AppliedCharges, AppliedDiscount, AppliedDiscounts};
+use crate::entities::prelude::{ItemVoid, ItemVoids, OrderItems, Orders, Payments, Refund, Refunds};
+use crate::entities::{item_void, order, order_item, payment, points_entry, refund};
+use crate::error::{AppError, AppResult};
+use crate::events::{self, OrderEvent};
+use crate::payment::provider_for;
+use crate::utils::{get_now_time, verify_password};
+use crate::utils::money::Money;
+
+/// 退菜和退款，都只追加记录并调整订单金额，不删除订单数据
+pub struct AdjustmentCurd;
+impl AdjustmentCurd {
+    /// 校验审批经理的账号密码，返回经理的id，操作人不能审批自己的操作
+    async fn verify_approver(approver: Option<ApproverData>, uid: &str) -> AppResult<Option<String>> {
+        let Some(approver) = approver else {
+            return Ok(None);
+        };
+        let Some(user) = UserCurd::query_by_username(approver.username).await? else {
+            return Err(StatusError::unauthorized().brief("审批人不存在").into());
+        };
+        if verify_password(&approver.password, &user.password).is_err() {
+            return Err(StatusError::unauthorized().brief("审批人密码错误").into());
+        }
+        if user.id == uid {
+            return Err(StatusError::forbidden().brief("不能审批自己的操作").into());
+        }
+        Ok(Some(user.id))
+    }
+    fn check_note(reason: ReasonCode, note: &str) -> AppResult<()> {
+        if reason == ReasonCode::Other && note.trim().is_empty() {
+            return Err(StatusError::bad_request().brief("原因为其他时必须填写备注").into());
+        }
+        Ok(())
+    }
+    /// 支付前退菜，重新计算订单应付金额，已经开始分单的话需要重新分单
+    /// 套餐只能整个退，套餐内容跟着退同样的份数，还没上的菜退回库存
+    pub async fn void_item(order_item_id: String, data: VoidItemData, uid: String) -> AppResult<ItemVoid> {
+        Self::check_note(data.reason, &data.note)?;
+        let approved_by = Self::verify_approver(data.approver, &uid).await?;
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(item) = OrderItems::find_by_id(order_item_id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单项不存在", order_item_id))
+                .into());
+        };
+        if item.combo_item_id.is_some() {
+            return Err(StatusError::bad_request().brief("套餐内的菜品不能单独退，请退整个套餐").into());
+        }
+        let Some(order) = Orders::find_by_id(item.order_id.clone()).one(&txn).await? else {
+            return Err(AppError::internal(format!("订单项{}对应的订单不存在", order_item_id)));
+        };
+        if !matches!(
+            order.status,
+            order::Status::Pending | order::Status::Accepted | order::Status::Cooking | order::Status::Served
+        ) {
+            return Err(AppError::conflict(format!("{:?}状态的订单不能退菜，请使用退款", order.status)));
+        }
+        ReportCurd::ensure_open(&txn, &order.created_at).await?;
+        let paid = Payments::find()
+            .filter(payment::Column::OrderId.eq(order.id.clone()))
+            .filter(payment::Column::Status.ne(payment::Status::Failed))
+            .one(&txn)
+            .await?;
+        if paid.is_some() {
+            return Err(AppError::conflict("订单已有支付，请使用退款"));
+        }
+        let remaining = item.quantity - item.voided_quantity;
+        if data.quantity > remaining {
+            return Err(StatusError::bad_request()
+                .brief(format!("最多还能退{}份", remaining))
+                .into());
+        }
+        let amount = item.price * data.quantity;
+
+        let voided_quantity = item.voided_quantity + data.quantity;
+        //全部退掉时，后厨还没做完的就不用做了
+        let cancel = voided_quantity == item.quantity && item.status.is_open();
+        if item.status.is_open() {
+            StockCurd::restore(&txn, &item, data.quantity, &uid).await?;
+        }
+        let mut item = item.into_active_model();
+        item.voided_quantity = Set(voided_quantity);
+        if cancel {
+            item.status = Set(order_item::Status::Cancelled);
+        }
+        let item = item.update(&txn).await?;
+        let mut components = Vec::new();
+        if item.combo {
+            let rows = OrderItems::find()
+                .filter(order_item::Column::ComboItemId.eq(item.id.clone()))
+                .all(&txn)
+                .await?;
+            for component in rows {
+                let voided_quantity = (component.voided_quantity + data.quantity).min(component.quantity);
+                let cancel = voided_quantity == component.quantity && component.status.is_open();
+                if component.status.is_open() {
+                    StockCurd::restore(&txn, &component, voided_quantity - component.voided_quantity, &uid).await?;
+                }
+                let mut component = component.into_active_model();
+                component.voided_quantity = Set(voided_quantity);
+                if cancel {
+                    component.status = Set(order_item::Status::Cancelled);
+                }
+                components.push((cancel, component.update(&txn).await?));
+            }
+        }
+        //按下单时的规则和参加的活动重新计算优惠、服务费和税，不再满足条件的活动退回名额
+        let lines = OrderItems::find()
+            .filter(order_item::Column::OrderId.eq(order.id.clone()))
+            .filter(order_item::Column::ComboItemId.is_null())
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|line| (line.dish_id, line.price, line.quantity - line.voided_quantity))
+            .collect();
+        let breakdown = PricingCurd::requote(&txn, &order, lines).await?;
+        let dropped: Vec<AppliedDiscount> = order
+            .discounts
+            .0
+            .iter()
+            .filter(|discount| !breakdown.discounts.iter().any(|kept| kept.promotion_id == discount.promotion_id))
+            .cloned()
+            .collect();
+        PromotionCurd::release(&txn, &dropped).await?;
+        let points_redeemed = breakdown.points_discount.fen();
+        if let Some(member_id) = &order.member_id {
+            MemberCurd::change(&txn, member_id, order.points_redeemed - points_redeemed, points_entry::Reason::Refund, Some(order.id.clone())).await?;
+        }
+        let mut order = order.into_active_model();
+        order.points_redeemed = Set(points_redeemed);
+        order.subtotal = Set(breakdown.subtotal);
+        order.discount = Set(breakdown.discount);
+        order.discounts = Set(AppliedDiscounts(breakdown.discounts));
+        order.service_charge = Set(breakdown.service_charge);
+        order.tax = Set(breakdown.tax);
+        order.charges = Set(AppliedCharges(breakdown.charges));
+        order.total = Set(breakdown.total);
+        let order = order.update(&txn).await?;
+        BillSplitCurd::delete_by_order(&txn, order.id.clone()).await?;
+        let record = ItemVoid {
+            id: Ulid::new().to_string(),
+            order_id: order.id.clone(),
+            order_item_id,
+            quantity: data.quantity,
+            amount,
+            reason: data.reason,
+            note: data.note,
+            uid,
+            approved_by,
+            created_at: get_now_time(),
+        };
+        ItemVoids::insert(record.clone().into_active_model()).exec(&txn).await?;
+        txn.commit().await?;
+        events::publish(OrderEvent::OrderStatusChanged(order));
+        for (cancel, item) in std::iter::once((cancel, item)).chain(components) {
+            if cancel {
+                events::publish(OrderEvent::ItemCancelled(item));
+            } else {
+                events::publish(OrderEvent::ItemStatusChanged(item));
+            }
+        }
+        Ok(record)
+    }
+    /// 支付后退款，原路退回到对应的支付渠道
+    /// 一笔支付的累计退款(包括处理中的)不能超过它的到账金额
+    /// 先在事务中写入处理中的退款记录再检查可退金额，提交后才交给渠道退款，渠道完成后再标记为已退款并计入订单，
+    /// 同时退款时后一个事务要等前一个提交后才能写入，不会超退，会员订单退款后扣回多发的积分
+    /// 退款计入退款时间所在的营业日，这一天已经日结时不能再退款
+    pub async fn refund(order_id: String, data: RefundData, uid: String) -> AppResult<Refund> {
+        Self::refund_at(order_id, data, uid, get_now_time()).await
+    }
+    async fn refund_at(order_id: String, data: RefundData, uid: String, now: String) -> AppResult<Refund> {
+        Self::check_note(data.reason, &data.note)?;
+        let amount = data.amount;
+        if amount <= Money::ZERO {
+            return Err(StatusError::bad_request().brief("退款金额必须大于0").into());
+        }
+        let approved_by = Self::verify_approver(data.approver, &uid).await?;
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let mut record = Refund {
+            id: Ulid::new().to_string(),
+            order_id: order_id.clone(),
+            payment_id: data.payment_id.clone(),
+            amount,
+            reason: data.reason,
+            note: data.note,
+            uid,
+            approved_by,
+            late: false,
+            status: refund::Status::Pending,
+            created_at: now,
+        };
+        Refunds::insert(record.clone().into_active_model()).exec(&txn).await?;
+        ReportCurd::ensure_open(&txn, &record.created_at).await?;
+        let Some(order) = Orders::find_by_id(order_id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单不存在", order_id))
+                .into());
+        };
+        if !matches!(order.status, order::Status::Paid | order::Status::Closed) {
+            return Err(AppError::conflict(format!("{:?}状态的订单不能退款，请使用退菜", order.status)));
+        }
+        let Some(payment) = Payments::find_by_id(data.payment_id.clone())
+            .filter(payment::Column::OrderId.eq(order_id.clone()))
+            .one(&txn)
+            .await?
+        else {
+            return Err(StatusError::bad_request()
+                .brief(format!("订单中没有id为{}的支付流水", data.payment_id))
+                .into());
+        };
+        if payment.status != payment::Status::Captured {
+            return Err(AppError::conflict("只有已到账的支付可以退款"));
+        }
+        let refunded: Money = Refunds::find()
+            .filter(refund::Column::PaymentId.eq(payment.id.clone()))
+            .all(&txn)
+            .await?
+            .iter()
+            .map(|refund| refund.amount)
+            .sum();
+        if refunded > payment.amount {
+            return Err(StatusError::bad_request()
+                .brief(format!("这笔支付最多还能退{}", payment.amount - (refunded - amount)))
+                .into());
+        }
+        //已经日结的订单仍然可以退款，但要标记出来，退款计入当天的报表
+        record.late = ReportCurd::is_closed(&txn, &order.created_at).await?;
+        txn.commit().await?;
+
+        //渠道出错时不确定钱有没有退出去，保持处理中，等人工和渠道核对，处理中的金额不能再退
+        if let Err(e) = provider_for(payment.method).refund(&payment, amount).await {
+            log::error!("refund {} of payment {} is pending for reconciliation: {}", record.id, payment.id, e);
+            return Err(e);
+        }
+        let txn = db.begin().await?;
+        let mut active = record.clone().into_active_model();
+        active.status = Set(refund::Status::Done);
+        active.late = Set(record.late);
+        let record = active.update(&txn).await?;
+        Orders::update_many()
+            .col_expr(order::Column::Refunded, Expr::col(order::Column::Refunded).add(amount.fen()))
+            .filter(order::Column::Id.eq(order_id.clone()))
+            .exec(&txn)
+            .await?;
+        //按退款后的实付金额扣回多发的积分
+        if let Some(order) = Orders::find_by_id(order_id).one(&txn).await? {
+            let points_earned = MemberCurd::reverse(&txn, &order).await?;
+            if points_earned != order.points_earned {
+                let mut order = order.into_active_model();
+                order.points_earned = Set(points_earned);
+                order.update(&txn).await?;
+            }
+        }
+        txn.commit().await?;
+        Ok(record)
+    }
+    pub async fn query_by_order(order_id: String) -> AppResult<OrderAdjustments> {
+        let db = get_db_coon();
+        let voids = ItemVoids::find()
+            .filter(item_void::Column::OrderId.eq(order_id.clone()))
+            .order_by_asc(item_void::Column::Id)
+            .all(db)
+            .await?;
+        let refunds = Refunds::find()
+            .filter(refund::Column::OrderId.eq(order_id))
+            .order_by_asc(refund::Column::Id)
+            .all(db)
+            .await?;
+        Ok(OrderAdjustments { voids, refunds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+    use crate::utils::money::Money;
+    use crate::config::db::init_db_coon;
+    use crate::das::adjustment::AdjustmentCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::payment::PaymentCurd;
+    use crate::das::report::ReportCurd;
+    use crate::das::users::UserCurd;
+    use crate::dto::adjustment::{ApproverData, RefundData, VoidItemData};
+    use crate::dto::order::OrderItemData;
+    use crate::dto::payment::TenderData;
+    use crate::entities::item_void::ReasonCode;
+    use crate::entities::order::Status;
+    use crate::entities::payment::Method;
+    use crate::entities::refund;
+    use crate::utils::hash_password;
+
+    #[tokio::test]
+    async fn test_void_then_refund() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_adjustment_dish".to_string(), Money::from_fen(1250), "test".to_string(), None).await.unwrap();
+        let items = vec![OrderItemData { dish_id, quantity: 3, ..Default::default() }];
+        let (order_id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), items).await.unwrap();
+        let order = OrderCurd::query_by_id(order_id.clone()).await.unwrap().unwrap();
+        let items = OrderCurd::query_with_items(vec![order]).await.unwrap().remove(0).items;
+        let void = VoidItemData { quantity: 1, reason: ReasonCode::WrongDish, note: String::new(), approver: None };
+        AdjustmentCurd::void_item(items[0].id.clone(), void, "test".to_string()).await.unwrap();
+        //不能审批自己的退菜
+        let manager = UserCurd::insert_user("test_adjustment_manager".to_string(), hash_password("abc123").unwrap()).await.unwrap();
+        let approver = ApproverData { username: "test_adjustment_manager".to_string(), password: "abc123".to_string() };
+        let own = VoidItemData { quantity: 1, reason: ReasonCode::WrongDish, note: String::new(), approver: Some(approver) };
+        assert!(AdjustmentCurd::void_item(items[0].id.clone(), own, manager).await.is_err());
+        let too_many = VoidItemData { quantity: 3, reason: ReasonCode::WrongDish, note: String::new(), approver: None };
+        assert!(AdjustmentCurd::void_item(items[0].id.clone(), too_many, "test".to_string()).await.is_err());
+        for status in [Status::Accepted, Status::Cooking, Status::Served] {
+            OrderCurd::transition(order_id.clone(), status, "test".to_string()).await.unwrap();
+        }
+        let tenders = vec![TenderData { method: Method::Cash, amount: Money::from_fen(2500), bill_split_id: None }];
+        let payments = PaymentCurd::pay(order_id.clone(), tenders, "test".to_string()).await.unwrap();
+        let refund = |amount| RefundData {
+            payment_id: payments[0].id.clone(),
+            amount,
+            reason: ReasonCode::CustomerComplaint,
+            note: String::new(),
+            approver: None,
+        };
+        let record = AdjustmentCurd::refund(order_id.clone(), refund(Money::from_fen(2000)), "test".to_string()).await.unwrap();
+        assert_eq!(record.status, refund::Status::Done);
+        assert!(AdjustmentCurd::refund(order_id.clone(), refund(Money::from_fen(501)), "test".to_string()).await.is_err());
+        let order = OrderCurd::query_by_id(order_id.clone()).await.unwrap().unwrap();
+        assert_eq!(order.total, Money::from_fen(2500));
+        assert_eq!(order.refunded, Money::from_fen(2000));
+        //退款所在的营业日已经日结后不能再退款
+        ReportCurd::close_day(date!(2000 - 01 - 03), "test".to_string()).await.unwrap();
+        let late = AdjustmentCurd::refund_at(order_id, refund(Money::from_fen(100)), "test".to_string(), "2000-01-03 12:00:00.000".to_string());
+        assert!(late.await.is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::bill_split::{BillSplitWithItems, SplitBillData};
+use crate::entities::bill_split::{Column, Mode, Status};
+use crate::entities::prelude::{BillSplit, BillSplitItem, BillSplitItems, BillSplits, OrderItems, Orders};
+use crate::entities::{bill_split_item, order, order_item};
+use crate::error::{AppError, AppResult};
+use crate::pricing;
+use crate::utils::get_now_time;
+use crate::utils::money::Money;
+
+/// 平均分单最多分成的份数
+const MAX_EVEN_PARTS: u32 = 100;
+
+/// 平均分成n份，除不尽的余数(分)从第一份开始每份多分摊1分
+pub fn split_even(total: Money, parts: u32) -> Vec<Money> {
+    let parts = parts as i64;
+    let base = total.fen() / parts;
+    let remainder = total.fen() % parts;
+    (0..parts).map(|i| Money::from_fen(if i < remainder { base + 1 } else { base })).collect()
+}
+
+/// 一份账单的付款人、金额和包含的订单项
+struct Part {
+    payer: String,
+    amount: Money,
+    item_ids: Vec<String>,
+}
+
+pub struct BillSplitCurd;
+impl BillSplitCurd {
+    /// 为订单分单，各份金额之和必须正好等于订单金额
+    /// 重新分单会替换之前的分单，已经有一份支付后就不能再重新分单
+    pub async fn split(order_id: String, data: SplitBillData) -> AppResult<Vec<BillSplitWithItems>> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(order) = Orders::find_by_id(order_id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单不存在", order_id))
+                .into());
+        };
+        if matches!(order.status, order::Status::Paid | order::Status::Closed | order::Status::Cancelled) {
+            return Err(AppError::conflict(format!("{:?}状态的订单不能分单", order.status)));
+        }
+        let existing = BillSplits::find()
+            .filter(Column::OrderId.eq(order_id.clone()))
+            .all(&txn)
+            .await?;
+        if existing.iter().any(|split| split.status == Status::Paid) {
+            return Err(AppError::conflict("已经有分单支付过了，不能重新分单"));
+        }
+        Self::delete_by_order(&txn, order_id.clone()).await?;
+
+        let (mode, parts) = match data {
+            SplitBillData::Even { parts } => {
+                if parts == 0 {
+                    return Err(StatusError::bad_request().brief("至少要分成1份").into());
+                }
+                //每份至少1分钱，否则会出现永远付不了的0元分单
+                let max_parts = order.total.fen().clamp(0, i64::from(MAX_EVEN_PARTS));
+                if i64::from(parts) > max_parts {
+                    return Err(StatusError::bad_request()
+                        .brief(format!("订单金额{}最多只能平均分成{}份", order.total, max_parts))
+                        .into());
+                }
+                let parts = split_even(order.total, parts)
+                    .into_iter()
+                    .map(|amount| Part { payer: String::new(), amount, item_ids: vec![] })
+                    .collect();
+                (Mode::Even, parts)
+            }
+            SplitBillData::Item { parts } => {
+                //所有未取消的菜品都必须分配给且只分配给一个人，套餐按整个套餐分配
+                //服务费和税按各人的菜品金额分摊
+                let mut items: HashMap<String, Money> = OrderItems::find()
+                    .filter(order_item::Column::OrderId.eq(order_id.clone()))
+                    .filter(order_item::Column::Status.ne(order_item::Status::Cancelled))
+                    .filter(order_item::Column::ComboItemId.is_null())
+                    .all(&txn)
+                    .await?
+                    .into_iter()
+                    .map(|item| (item.id, item.price * (item.quantity - item.voided_quantity)))
+                    .collect();
+                let mut result = Vec::with_capacity(parts.len());
+                for part in parts {
+                    let mut amount = Money::ZERO;
+                    for item_id in &part.item_ids {
+                        amount += items.remove(item_id).ok_or_else(|| {
+                            StatusError::bad_request().brief(format!("订单项{}不存在或者重复分配", item_id))
+                        })?;
+                    }
+                    result.push(Part { payer: part.payer, amount, item_ids: part.item_ids });
+                }
+                if !items.is_empty() {
+                    return Err(StatusError::bad_request()
+                        .brief(format!("还有{}个订单项没有分配", items.len()))
+                        .into());
+                }
+                let weights: Vec<Money> = result.iter().map(|part| part.amount).collect();
+                let extra = order.total - weights.iter().sum();
+                for (part, share) in result.iter_mut().zip(pricing::allocate(extra, &weights)) {
+                    part.amount += share;
+                }
+                (Mode::Item, result)
+            }
+            SplitBillData::Custom { parts } => {
+                let mut result = Vec::with_capacity(parts.len());
+                for part in parts {
+                    if part.amount <= Money::ZERO {
+                        return Err(StatusError::bad_request().brief("分单金额必须大于0").into());
+                    }
+                    result.push(Part { payer: part.payer, amount: part.amount, item_ids: vec![] });
+                }
+                (Mode::Custom, result)
+            }
+        };
+        let sum: Money = parts.iter().map(|part| part.amount).sum();
+        if parts.is_empty() || sum != order.total {
+            return Err(StatusError::bad_request()
+                .brief(format!("分单金额合计{}与订单金额{}不一致", sum, order.total))
+                .into());
+        }
+
+        let mut result = Vec::with_capacity(parts.len());
+        for (index, part) in parts.into_iter().enumerate() {
+            let split = BillSplit {
+                id: Ulid::new().to_string(),
+                order_id: order_id.clone(),
+                index: index as i32,
+                mode,
+                payer: part.payer,
+                amount: part.amount,
+                status: Status::Unpaid,
+                paid_at: None,
+            };
+            BillSplits::insert(split.clone().into_active_model()).exec(&txn).await?;
+            if !part.item_ids.is_empty() {
+                BillSplitItems::insert_many(part.item_ids.iter().map(|item_id| {
+                    BillSplitItem {
+                        bill_split_id: split.id.clone(),
+                        order_item_id: item_id.clone(),
+                    }
+                    .into_active_model()
+                }))
+                .exec(&txn)
+                .await?;
+            }
+            result.push(BillSplitWithItems { split, item_ids: part.item_ids });
+        }
+        txn.commit().await?;
+        Ok(result)
+    }
+    /// 删除订单的所有分单，订单金额变化后之前的分单就失效了
+    pub async fn delete_by_order<C: ConnectionTrait>(db: &C, order_id: String) -> AppResult<()> {
+        let splits = BillSplits::find()
+            .filter(Column::OrderId.eq(order_id))
+            .all(db)
+            .await?;
+        for split in splits {
+            BillSplitItems::delete_many()
+                .filter(bill_split_item::Column::BillSplitId.eq(split.id.clone()))
+                .exec(db)
+                .await?;
+            split.delete(db).await?;
+        }
+        Ok(())
+    }
+    pub async fn query_by_order(order_id: String) -> AppResult<Vec<BillSplitWithItems>> {
+        let db = get_db_coon();
+        let splits = BillSplits::find()
+            .filter(Column::OrderId.eq(order_id))
+            .order_by_asc(Column::Index)
+            .find_with_related(BillSplitItems)
+            .all(db)
+            .await?;
+        Ok(splits
+            .into_iter()
+            .map(|(split, items)| BillSplitWithItems {
+                split,
+                item_ids: items.into_iter().map(|item| item.order_item_id).collect(),
+            })
+            .collect())
+    }
+    pub async fn query_by_id<C: ConnectionTrait>(db: &C, id: String) -> AppResult<BillSplit> {
+        BillSplits::find_by_id(id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的分单不存在", id)).into())
+    }
+    /// 这一份的支付到账后由支付流水结算时调用，见[crate::das::payment::PaymentCurd::settle]
+    pub async fn mark_paid<C: ConnectionTrait>(db: &C, id: String) -> AppResult<BillSplit> {
+        let split = Self::query_by_id(db, id).await?;
+        if split.status == Status::Paid {
+            return Err(AppError::conflict("这一份已经支付过了"));
+        }
+        let mut split = split.into_active_model();
+        split.status = Set(Status::Paid);
+        split.paid_at = Set(Some(get_now_time()));
+        Ok(split.update(db).await?)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::bill_split::{split_even, BillSplitCurd};
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::dto::bill_split::{CustomPart, SplitBillData};
+    use crate::dto::order::OrderItemData;
+    use crate::utils::money::Money;
+
+    #[test]
+    fn test_split_even() {
+        let fen = |amounts: Vec<Money>| amounts.into_iter().map(Money::fen).collect::<Vec<_>>();
+        assert_eq!(fen(split_even(Money::from_fen(1000), 3)), vec![334, 333, 333]);
+        assert_eq!(fen(split_even(Money::from_fen(1001), 3)), vec![334, 334, 333]);
+        assert_eq!(fen(split_even(Money::from_fen(900), 3)), vec![300, 300, 300]);
+        assert_eq!(fen(split_even(Money::from_fen(1), 2)), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_split_bill() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_split_dish".to_string(), Money::from_fen(333), "test".to_string(), None).await.unwrap();
+        let items = vec![OrderItemData { dish_id, quantity: 3, ..Default::default() }];
+        let (order_id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), items).await.unwrap();
+        //份数过多或者超过订单金额的分数时不能分单
+        assert!(BillSplitCurd::split(order_id.clone(), SplitBillData::Even { parts: 4_000_000_000 }).await.is_err());
+        assert!(BillSplitCurd::split(order_id.clone(), SplitBillData::Even { parts: 101 }).await.is_err());
+        let splits = BillSplitCurd::split(order_id.clone(), SplitBillData::Even { parts: 2 }).await.unwrap();
+        let amounts: Vec<i64> = splits.iter().map(|s| s.split.amount.fen()).collect();
+        assert_eq!(amounts, vec![500, 499]);
+        let custom = vec![
+            CustomPart { payer: "张三".to_string(), amount: Money::from_fen(500) },
+            CustomPart { payer: "李四".to_string(), amount: Money::from_fen(498) },
+        ];
+        assert!(BillSplitCurd::split(order_id.clone(), SplitBillData::Custom { parts: custom }).await.is_err());
+        BillSplitCurd::mark_paid(get_db_coon(), splits[0].split.id.clone()).await.unwrap();
+        assert!(BillSplitCurd::split(order_id, SplitBillData::Even { parts: 3 }).await.is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::combo::ComboCurd;
+use crate::das::dish::DishCurd;
+use crate::das::ingredient::IngredientCurd;
+use crate::das::modifier::ModifierCurd;
+use crate::das::pricing::PricingCurd;
+use crate::das::order::OrderCurd;
+use crate::das::schedule::Availability;
+use crate::das::table_session::TableSessionCurd;
+use crate::dto::cart::{AddCartItemData, CartLine, CartView};
+use crate::dto::order::{OrderItemData, OrderOptions};
+use crate::entities::cart_item::{Column, ComboComponents, ModifierIds};
+use crate::entities::dish::Status;
+use crate::entities::dish_variant;
+use crate::entities::order::Channel;
+use crate::entities::prelude::{CartItem, CartItems, DishVariant, DishVariants, Dishes};
+use crate::error::{AppError, AppResult};
+use crate::utils::{format_date, get_now_time, today};
+use crate::utils::money::Money;
+
+pub struct CartCurd;
+impl CartCurd {
+    /// 往购物车里加菜，返回购物车项id
+    pub async fn insert(table_session_id: String, data: AddCartItemData) -> AppResult<String> {
+        let db = get_db_coon();
+        TableSessionCurd::query_open_by_id(db, table_session_id.clone()).await?;
+        let availability = Availability::load(db).await?;
+        let dish = DishCurd::query_orderable(db, &availability, data.dish_id.clone()).await?;
+        DishCurd::query_orderable_variant(db, &dish, data.variant_id.clone()).await?;
+        ModifierCurd::resolve(db, &dish, &data.modifier_ids).await?;
+        ComboCurd::resolve(db, &availability, &dish, &data.components).await?;
+        let uuid = Ulid::new();
+        let item = CartItem {
+            id: uuid.to_string(),
+            table_session_id,
+            dish_id: data.dish_id,
+            variant_id: data.variant_id,
+            modifier_ids: ModifierIds(data.modifier_ids),
+            components: ComboComponents(data.components),
+            quantity: data.quantity,
+            added_by: data.added_by,
+            created_at: get_now_time(),
+        };
+        CartItems::insert(item.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    pub async fn update_quantity(table_session_id: String, id: String, quantity: i32) -> AppResult<CartItem> {
+        let db = get_db_coon();
+        let item = Self::query_by_id(table_session_id, id).await?;
+        let availability = Availability::load(db).await?;
+        let dish = DishCurd::query_orderable(db, &availability, item.dish_id.clone()).await?;
+        DishCurd::query_orderable_variant(db, &dish, item.variant_id.clone()).await?;
+        ModifierCurd::resolve(db, &dish, &item.modifier_ids.0).await?;
+        ComboCurd::resolve(db, &availability, &dish, &item.components.0).await?;
+        let mut item = item.into_active_model();
+        item.quantity = Set(quantity);
+        Ok(item.update(db).await?)
+    }
+    pub async fn delete_by_id(table_session_id: String, id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        CartItems::delete_many()
+            .filter(Column::TableSessionId.eq(table_session_id))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    /// 只能操作自己这一桌的购物车项
+    async fn query_by_id(table_session_id: String, id: String) -> AppResult<CartItem> {
+        let db = get_db_coon();
+        CartItems::find_by_id(id.clone())
+            .filter(Column::TableSessionId.eq(table_session_id))
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的购物车项不存在", id)).into())
+    }
+    /// 查询购物车，已删除或下架的菜品、规格，原料用完的菜品，以及不再满足规则的选项和套餐内容会标记为不可点，不计入总价
+    /// 同时计算可以参加的促销活动，填写了优惠券时一起计算，优惠券不可用的原因放在skipped里
+    pub async fn query_by_session(table_session_id: String, coupon_code: Option<String>) -> AppResult<CartView> {
+        let db = get_db_coon();
+        let rows = CartItems::find()
+            .find_also_related(Dishes)
+            .filter(Column::TableSessionId.eq(table_session_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+        let variants: HashMap<String, DishVariant> = DishVariants::find()
+            .filter(dish_variant::Column::Id.is_in(rows.iter().filter_map(|(item, _)| item.variant_id.clone())))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|variant| (variant.id.clone(), variant))
+            .collect();
+        let unavailable = IngredientCurd::query_unavailable(db, rows.iter().map(|(item, _)| item.dish_id.clone()).collect()).await?;
+        let availability = Availability::load(db).await?;
+        let mut lines = Vec::with_capacity(rows.len());
+        let mut items = Vec::with_capacity(rows.len());
+        for (item, dish) in rows {
+            let variant = item.variant_id.as_ref().and_then(|id| variants.get(id));
+            let (modifiers, components) = match &dish {
+                Some(dish) => (
+                    ModifierCurd::resolve(db, dish, &item.modifier_ids.0).await.ok(),
+                    ComboCurd::resolve(db, &availability, dish, &item.components.0).await.ok(),
+                ),
+                None => (None, None),
+            };
+            let available = dish.as_ref().is_some_and(|dish| dish.status == Status::Normal)
+                && (item.variant_id.is_none() || variant.is_some_and(|variant| variant.status == Status::Normal))
+                && modifiers.is_some()
+                && components.is_some()
+                && !unavailable.contains(&item.dish_id)
+                && availability.dish_open(&item.dish_id);
+            let modifiers = modifiers.unwrap_or_default();
+            let components = components.unwrap_or_default();
+            let (dish_name, base) = dish.map(|dish| (dish.name, dish.price)).unwrap_or_default();
+            let base = variant.map(|variant| variant.price).unwrap_or(base);
+            let price = base
+                + modifiers.iter().map(|modifier| modifier.price_delta).sum()
+                + components.iter().map(|component| component.extra()).sum();
+            if available {
+                lines.push((item.dish_id.clone(), price, item.quantity));
+            }
+            items.push(CartLine {
+                id: item.id,
+                dish_id: item.dish_id,
+                dish_name,
+                variant_id: item.variant_id.clone(),
+                variant_name: variant.map(|variant| variant.name.clone()),
+                modifiers,
+                components,
+                price,
+                quantity: item.quantity,
+                added_by: item.added_by,
+                available,
+            });
+        }
+        let today = format_date(today());
+        let (breakdown, skipped) = PricingCurd::quote(db, Channel::DineIn, lines, coupon_code.as_deref(), 0, &today).await?;
+        Ok(CartView {
+            items,
+            subtotal: breakdown.subtotal,
+            discount: breakdown.discount,
+            discounts: breakdown.discounts,
+            skipped,
+            service_charge: breakdown.service_charge,
+            tax: breakdown.tax,
+            total: breakdown.total,
+        })
+    }
+    /// 把购物车提交成订单，返回订单id和总价
+    /// 清空购物车和下单在同一个事务中，两个人同时提交时只有一个会成功
+    pub async fn submit(table_session_id: String, uid: String, options: OrderOptions) -> AppResult<(String, Money)> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let items = CartItems::find()
+            .filter(Column::TableSessionId.eq(table_session_id.clone()))
+            .order_by_asc(Column::Id)
+            .all(&txn)
+            .await?;
+        if items.is_empty() {
+            return Err(StatusError::bad_request().brief("购物车是空的").into());
+        }
+        let deleted = CartItems::delete_many()
+            .filter(Column::Id.is_in(items.iter().map(|item| item.id.clone())))
+            .exec(&txn)
+            .await?;
+        if deleted.rows_affected != items.len() as u64 {
+            return Err(AppError::conflict("购物车已被其他人提交或修改，请刷新后重试"));
+        }
+        let items = items
+            .into_iter()
+            .map(|item| OrderItemData {
+                dish_id: item.dish_id,
+                variant_id: item.variant_id,
+                modifier_ids: item.modifier_ids.0,
+                components: item.components.0,
+                quantity: item.quantity,
+            })
+            .collect();
+        let (order, order_items) = OrderCurd::insert_in(&txn, uid, Some(table_session_id), options, items).await?;
+        txn.commit().await?;
+        let result = (order.id.clone(), order.total);
+        OrderCurd::publish_created(order, order_items);
+        Ok(result)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::init_db_coon;
+    use crate::das::cart::CartCurd;
+    use crate::das::dining_table::DiningTableCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::table_session::TableSessionCurd;
+    use crate::dto::cart::AddCartItemData;
+    use crate::dto::dining_table::DiningTableData;
+
+    #[tokio::test]
+    async fn test_submit_cart() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_cart_dish".to_string(), Money::from_fen(1000), "test".to_string(), None).await.unwrap();
+        let data = DiningTableData { name: "test_cart_table".to_string(), area: "大厅".to_string(), capacity: 4, ..Default::default() };
+        let table_id = DiningTableCurd::insert(data).await.unwrap();
+        let session = TableSessionCurd::open(table_id, "test".to_string()).await.unwrap();
+        for added_by in ["张三", "李四"] {
+            let data = AddCartItemData { dish_id: dish_id.clone(), quantity: 2, added_by: added_by.to_string(), ..Default::default() };
+            CartCurd::insert(session.id.clone(), data).await.unwrap();
+        }
+        let cart = CartCurd::query_by_session(session.id.clone(), None).await.unwrap();
+        assert_eq!(cart.items.len(), 2);
+        assert_eq!(cart.total, Money::from_fen(4000));
+        let (_, total) = CartCurd::submit(session.id.clone(), session.id.clone(), Default::default()).await.unwrap();
+        assert_eq!(total, Money::from_fen(4000));
+        assert!(CartCurd::query_by_session(session.id.clone(), None).await.unwrap().items.is_empty());
+        assert!(CartCurd::submit(session.id.clone(), session.id, Default::default()).await.is_err());
+    }
+}
@@ -1,11 +1,17 @@
 use anyhow::anyhow;
-use sea_orm::{EntityTrait, IntoActiveModel, ModelTrait, QueryOrder};
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
 use ulid::Ulid;
 use crate::config::db::get_db_coon;
+use crate::das::category_dish_map::CategoryDishMapCurd;
+use crate::das::schedule::ScheduleCurd;
 use crate::entities::category::Column;
-use crate::entities::category_dish_map::CategoryToDish;
+use crate::entities::schedule_window::Target;
 use crate::entities::prelude::{Categories, Category, Dish};
-use crate::error::AppResult;
+use crate::dto::menu::UpdateCategoryData;
+use crate::error::{AppError, AppResult};
+use crate::utils::check_order;
 
 pub struct CategoryCurd;
 impl CategoryCurd {
@@ -32,8 +38,41 @@ impl CategoryCurd {
         .await?;
         Ok(uuid.to_string())
     }
+    /// 修改分类的名称，填了dish_ids时同时替换分类下的菜品
+    pub async fn update(id: String, data: UpdateCategoryData) -> AppResult<Category> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(category) = Categories::find_by_id(id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的分类不存在", id))
+                .into());
+        };
+        let mut active = category.clone().into_active_model();
+        if let Some(name) = data.name {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(StatusError::bad_request().brief("分类名称不能为空").into());
+            }
+            let existing = Categories::find()
+                .filter(Column::Name.eq(name.clone()))
+                .filter(Column::Id.ne(id.clone()))
+                .one(&txn)
+                .await?;
+            if existing.is_some() {
+                return Err(AppError::conflict(format!("分类{}已存在", name)));
+            }
+            active.name = Set(name);
+        }
+        let category = if active.is_changed() { active.update(&txn).await? } else { category };
+        if let Some(dish_ids) = data.dish_ids {
+            CategoryDishMapCurd::replace_for_category(&txn, id, dish_ids).await?;
+        }
+        txn.commit().await?;
+        Ok(category)
+    }
     pub async fn delete_by_id(id: String) -> AppResult<()> {
         let db = get_db_coon();
+        ScheduleCurd::delete_by_target(db, Target::Category, id.clone()).await?;
         Categories::delete_by_id(id).exec(db).await?;
         Ok(())
     }
@@ -44,10 +83,26 @@ impl CategoryCurd {
             .all(db)
             .await?)
     }
-    /// 查询分类关联的菜品
+    /// 按ids的顺序重写全部分类的index
+    pub async fn reorder(ids: Vec<String>) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let existing = Categories::find().all(&txn).await?.into_iter().map(|category| category.id).collect();
+        check_order(existing, &ids).map_err(|reason| StatusError::bad_request().brief(reason))?;
+        for (index, id) in ids.into_iter().enumerate() {
+            Categories::update_many()
+                .col_expr(Column::Index, Expr::value(index as i32))
+                .filter(Column::Id.eq(id))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+    /// 按分类里的位置查询分类关联的菜品
     pub async fn query_related_dishes(id: String) -> AppResult<Vec<Dish>> {
         let db = get_db_coon();
-        let category = Categories::find_by_id(id.clone()).one(db).await?.ok_or_else(|| anyhow!("id为{}的种类不存在",id))?;
-        Ok(category.find_linked(CategoryToDish).all(db).await?)
+        Categories::find_by_id(id.clone()).one(db).await?.ok_or_else(|| anyhow!("id为{}的种类不存在",id))?;
+        CategoryDishMapCurd::query_dishes(db, id).await
     }
 }
\ No newline at end of file
@@ -1,17 +1,25 @@
-use sea_orm::{ColumnTrait, QueryFilter};
+use std::collections::{HashMap, HashSet};
+use salvo::http::StatusError;
+use sea_orm::{ColumnTrait, ConnectionTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait};
 use sea_orm::{EntityTrait, IntoActiveModel};
+use sea_orm::sea_query::Expr;
 use crate::config::db::get_db_coon;
 use crate::entities::category_dish_map::Column;
-use crate::entities::prelude::{CategoryDishMap, CategoryDishMaps};
+use crate::entities::dish;
+use crate::entities::prelude::{Categories, CategoryDishMap, CategoryDishMaps, Dish, Dishes};
 use crate::error::AppResult;
+use crate::utils::check_order;
 
 pub struct CategoryDishMapCurd;
 impl CategoryDishMapCurd {
+    /// 把菜品加到分类的最后
     pub async fn insert(category_id: String, dish_id: String) -> AppResult<()> {
         let db = get_db_coon();
+        let position = Self::next_position(db, &category_id).await?;
         let category_dish_map = CategoryDishMap {
             category_id,
             dish_id,
+            position,
         };
         CategoryDishMaps::insert(
             category_dish_map
@@ -21,6 +29,15 @@ impl CategoryDishMapCurd {
         .await?;
         Ok(())
     }
+    async fn next_position<C: ConnectionTrait>(db: &C, category_id: &str) -> AppResult<i32> {
+        Ok(CategoryDishMaps::find()
+            .filter(Column::CategoryId.eq(category_id))
+            .order_by_desc(Column::Position)
+            .one(db)
+            .await?
+            .map(|map| map.position + 1)
+            .unwrap_or(0))
+    }
     pub async fn delete_by_category_id(category_id: String) -> AppResult<()> {
         let db = get_db_coon();
         CategoryDishMaps::delete_many()
@@ -35,4 +52,129 @@ impl CategoryDishMapCurd {
             .exec(db).await?;
         Ok(())
     }
-}
\ No newline at end of file
+    /// 替换菜品所属的全部分类，原来就在的分类保持位置不变，新加入的分类排在最后
+    pub async fn replace_for_dish<C: ConnectionTrait>(db: &C, dish_id: String, category_ids: Vec<String>) -> AppResult<()> {
+        let mut seen = HashSet::new();
+        let category_ids: Vec<String> = category_ids.into_iter().filter(|id| seen.insert(id.clone())).collect();
+        for category_id in &category_ids {
+            if Categories::find_by_id(category_id.clone()).count(db).await? == 0 {
+                return Err(StatusError::bad_request()
+                    .brief(format!("id为{}的分类不存在", category_id))
+                    .into());
+            }
+        }
+        let positions: HashMap<String, i32> = CategoryDishMaps::find()
+            .filter(Column::DishId.eq(dish_id.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|map| (map.category_id, map.position))
+            .collect();
+        CategoryDishMaps::delete_many()
+            .filter(Column::DishId.eq(dish_id.clone()))
+            .exec(db).await?;
+        for category_id in category_ids {
+            let position = match positions.get(&category_id) {
+                Some(position) => *position,
+                None => Self::next_position(db, &category_id).await?,
+            };
+            let map = CategoryDishMap { category_id, dish_id: dish_id.clone(), position };
+            CategoryDishMaps::insert(map.into_active_model()).exec(db).await?;
+        }
+        Ok(())
+    }
+    /// 替换分类下的全部菜品，按dish_ids的顺序排列
+    pub async fn replace_for_category<C: ConnectionTrait>(db: &C, category_id: String, dish_ids: Vec<String>) -> AppResult<()> {
+        let mut seen = HashSet::new();
+        let dish_ids: Vec<String> = dish_ids.into_iter().filter(|id| seen.insert(id.clone())).collect();
+        for dish_id in &dish_ids {
+            if Dishes::find_by_id(dish_id.clone()).count(db).await? == 0 {
+                return Err(StatusError::bad_request()
+                    .brief(format!("id为{}的菜品不存在", dish_id))
+                    .into());
+            }
+        }
+        CategoryDishMaps::delete_many()
+            .filter(Column::CategoryId.eq(category_id.clone()))
+            .exec(db).await?;
+        for (position, dish_id) in dish_ids.into_iter().enumerate() {
+            let map = CategoryDishMap { category_id: category_id.clone(), dish_id, position: position as i32 };
+            CategoryDishMaps::insert(map.into_active_model()).exec(db).await?;
+        }
+        Ok(())
+    }
+    /// 按dish_ids的顺序重排分类下的菜品，必须包含分类下的全部菜品
+    pub async fn reorder(category_id: String, dish_ids: Vec<String>) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        if Categories::find_by_id(category_id.clone()).count(&txn).await? == 0 {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的分类不存在", category_id))
+                .into());
+        }
+        let existing = CategoryDishMaps::find()
+            .filter(Column::CategoryId.eq(category_id.clone()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|map| map.dish_id)
+            .collect();
+        check_order(existing, &dish_ids).map_err(|reason| StatusError::bad_request().brief(reason))?;
+        for (position, dish_id) in dish_ids.into_iter().enumerate() {
+            CategoryDishMaps::update_many()
+                .col_expr(Column::Position, Expr::value(position as i32))
+                .filter(Column::CategoryId.eq(category_id.clone()))
+                .filter(Column::DishId.eq(dish_id))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+    /// 按分类里的位置查询分类下的菜品
+    pub async fn query_dishes<C: ConnectionTrait>(db: &C, category_id: String) -> AppResult<Vec<Dish>> {
+        let dish_ids: Vec<String> = CategoryDishMaps::find()
+            .filter(Column::CategoryId.eq(category_id))
+            .order_by_asc(Column::Position)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|map| map.dish_id)
+            .collect();
+        let mut dishes: HashMap<String, Dish> = Dishes::find()
+            .filter(dish::Column::Id.is_in(dish_ids.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|dish| (dish.id.clone(), dish))
+            .collect();
+        Ok(dish_ids.into_iter().filter_map(|id| dishes.remove(&id)).collect())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::init_db_coon;
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::das::dish::DishCurd;
+    use crate::entities::prelude::Dish;
+
+    #[tokio::test]
+    async fn test_reorder_category_dishes() {
+        init_db_coon().await;
+        let first = CategoryCurd::insert("test_reorder_first".to_string()).await.unwrap();
+        let second = CategoryCurd::insert("test_reorder_second".to_string()).await.unwrap();
+        let a = DishCurd::insert("test_reorder_a".to_string(), Money::from_fen(100), "test".to_string(), None).await.unwrap();
+        let b = DishCurd::insert("test_reorder_b".to_string(), Money::from_fen(100), "test".to_string(), None).await.unwrap();
+        for category_id in [&first, &second] {
+            CategoryDishMapCurd::insert(category_id.clone(), a.clone()).await.unwrap();
+            CategoryDishMapCurd::insert(category_id.clone(), b.clone()).await.unwrap();
+        }
+        assert!(CategoryDishMapCurd::reorder(first.clone(), vec![b.clone()]).await.is_err());
+        CategoryDishMapCurd::reorder(first.clone(), vec![b.clone(), a.clone()]).await.unwrap();
+        let names = |dishes: Vec<Dish>| dishes.into_iter().map(|dish| dish.name).collect::<Vec<_>>();
+        assert_eq!(names(CategoryCurd::query_related_dishes(first).await.unwrap()), ["test_reorder_b", "test_reorder_a"]);
+        assert_eq!(names(CategoryCurd::query_related_dishes(second).await.unwrap()), ["test_reorder_a", "test_reorder_b"]);
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use salvo::http::StatusError;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder};
+use ulid::Ulid;
+use crate::das::dish::DishCurd;
+use crate::das::modifier::ModifierCurd;
+use crate::das::schedule::Availability;
+use crate::dto::combo::{ComboChoice, ComboComponentLine, ComboSlotDetail, CreateComboSlotData};
+use crate::entities::combo_slot::ComboComponent;
+use crate::entities::dish::Status;
+use crate::entities::prelude::{Categories, CategoryDishMaps, ComboSlot, ComboSlotDish, ComboSlotDishes, ComboSlots, Dish, Dishes};
+use crate::entities::{category_dish_map, combo_slot, combo_slot_dish, dish};
+use crate::error::AppResult;
+use crate::utils::money::Money;
+
+/// 检查套餐各部分的名称和可选菜品，返回不合法的原因
+pub fn check_combo_slots(slots: &[CreateComboSlotData]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for slot in slots {
+        let name = slot.name.trim();
+        if name.is_empty() {
+            return Err("套餐部分的名称不能为空".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("套餐部分{}重复", name));
+        }
+        if slot.category_id.is_none() && slot.dishes.is_empty() {
+            return Err(format!("套餐部分{}需要指定分类或者菜品", name));
+        }
+        let mut dish_ids = HashSet::new();
+        for choice in &slot.dishes {
+            if !dish_ids.insert(&choice.dish_id) {
+                return Err(format!("套餐部分{}的菜品{}重复", name, choice.dish_id));
+            }
+            if choice.upcharge.is_negative() {
+                return Err(format!("套餐部分{}的加价{}不合法", name, choice.upcharge));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub struct ComboCurd;
+impl ComboCurd {
+    /// 替换套餐的全部组成部分，套餐不能再包含套餐
+    pub async fn replace_slots<C: ConnectionTrait>(db: &C, combo_id: String, slots: Vec<CreateComboSlotData>) -> AppResult<()> {
+        check_combo_slots(&slots).map_err(|reason| StatusError::bad_request().brief(reason))?;
+        let combo_ids = Self::query_combo_ids(db).await?;
+        for slot in &slots {
+            if let Some(category_id) = &slot.category_id
+                && Categories::find_by_id(category_id.clone()).count(db).await? == 0
+            {
+                return Err(StatusError::bad_request()
+                    .brief(format!("id为{}的分类不存在", category_id))
+                    .into());
+            }
+            for choice in &slot.dishes {
+                if choice.dish_id == combo_id || combo_ids.contains(&choice.dish_id) {
+                    return Err(StatusError::bad_request().brief("套餐里不能再放套餐").into());
+                }
+                if Dishes::find_by_id(choice.dish_id.clone()).count(db).await? == 0 {
+                    return Err(StatusError::bad_request()
+                        .brief(format!("id为{}的菜品不存在", choice.dish_id))
+                        .into());
+                }
+            }
+        }
+        Self::delete_slots(db, combo_id.clone()).await?;
+        for (index, slot) in slots.into_iter().enumerate() {
+            let combo_slot = ComboSlot {
+                id: Ulid::new().to_string(),
+                combo_id: combo_id.clone(),
+                index: index as i32,
+                name: slot.name.trim().to_string(),
+                category_id: slot.category_id,
+            };
+            ComboSlots::insert(combo_slot.clone().into_active_model()).exec(db).await?;
+            for choice in slot.dishes {
+                let choice = ComboSlotDish { slot_id: combo_slot.id.clone(), dish_id: choice.dish_id, upcharge: choice.upcharge };
+                ComboSlotDishes::insert(choice.into_active_model()).exec(db).await?;
+            }
+        }
+        Ok(())
+    }
+    /// 删除菜品时调用，删除套餐自己的组成部分，并把菜品从其他套餐的可选菜品里去掉
+    pub async fn delete_by_dish<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<()> {
+        Self::delete_slots(db, dish_id.clone()).await?;
+        ComboSlotDishes::delete_many()
+            .filter(combo_slot_dish::Column::DishId.eq(dish_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn delete_slots<C: ConnectionTrait>(db: &C, combo_id: String) -> AppResult<()> {
+        let slots = ComboSlots::find()
+            .filter(combo_slot::Column::ComboId.eq(combo_id.clone()))
+            .all(db)
+            .await?;
+        ComboSlotDishes::delete_many()
+            .filter(combo_slot_dish::Column::SlotId.is_in(slots.into_iter().map(|slot| slot.id)))
+            .exec(db)
+            .await?;
+        ComboSlots::delete_many()
+            .filter(combo_slot::Column::ComboId.eq(combo_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    async fn query_combo_ids<C: ConnectionTrait>(db: &C) -> AppResult<HashSet<String>> {
+        Ok(ComboSlots::find().all(db).await?.into_iter().map(|slot| slot.combo_id).collect())
+    }
+    /// 查询套餐的组成部分，可选菜品只包括未下架的普通菜品，按菜品顺序排列
+    pub async fn query_for_dishes<C: ConnectionTrait>(db: &C, dish_ids: Vec<String>) -> AppResult<HashMap<String, Vec<ComboSlotDetail>>> {
+        let slots = ComboSlots::find()
+            .filter(combo_slot::Column::ComboId.is_in(dish_ids))
+            .order_by_asc(combo_slot::Column::Index)
+            .all(db)
+            .await?;
+        if slots.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut upcharges: HashMap<String, HashMap<String, Money>> = HashMap::new();
+        for choice in ComboSlotDishes::find()
+            .filter(combo_slot_dish::Column::SlotId.is_in(slots.iter().map(|slot| slot.id.clone())))
+            .all(db)
+            .await?
+        {
+            upcharges.entry(choice.slot_id).or_default().insert(choice.dish_id, choice.upcharge);
+        }
+        let mut category_dishes: HashMap<String, Vec<String>> = HashMap::new();
+        for map in CategoryDishMaps::find()
+            .filter(category_dish_map::Column::CategoryId.is_in(slots.iter().filter_map(|slot| slot.category_id.clone())))
+            .all(db)
+            .await?
+        {
+            category_dishes.entry(map.category_id).or_default().push(map.dish_id);
+        }
+        let combo_ids = Self::query_combo_ids(db).await?;
+        let candidate_ids: HashSet<&String> = upcharges.values().flat_map(|dishes| dishes.keys()).chain(category_dishes.values().flatten()).collect();
+        let dishes: Vec<Dish> = Dishes::find()
+            .filter(dish::Column::Id.is_in(candidate_ids.into_iter().cloned()))
+            .filter(dish::Column::Status.eq(Status::Normal))
+            .order_by_asc(dish::Column::Index)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|dish| !combo_ids.contains(&dish.id))
+            .collect();
+        let mut result: HashMap<String, Vec<ComboSlotDetail>> = HashMap::new();
+        for slot in slots {
+            let upcharges = upcharges.remove(&slot.id).unwrap_or_default();
+            let in_category = slot.category_id.as_ref().and_then(|id| category_dishes.get(id));
+            let choices = dishes
+                .iter()
+                .filter(|dish| upcharges.contains_key(&dish.id) || in_category.is_some_and(|ids| ids.contains(&dish.id)))
+                .map(|dish| ComboChoice {
+                    dish_id: dish.id.clone(),
+                    dish_name: dish.name.clone(),
+                    upcharge: upcharges.get(&dish.id).copied().unwrap_or_default(),
+                })
+                .collect();
+            result.entry(slot.combo_id.clone()).or_default().push(ComboSlotDetail { slot, choices });
+        }
+        Ok(result)
+    }
+    /// 校验下单时给套餐选择的内容，每个部分必须正好选一个可选的菜品
+    /// 不是套餐的菜品不能选择套餐内容
+    pub async fn resolve<C: ConnectionTrait>(
+        db: &C,
+        availability: &Availability,
+        dish: &Dish,
+        components: &[ComboComponent],
+    ) -> AppResult<Vec<ComboComponentLine>> {
+        let slots = Self::query_for_dishes(db, vec![dish.id.clone()]).await?.remove(&dish.id).unwrap_or_default();
+        let bad_request = |reason: String| StatusError::bad_request().brief(format!("{}: {}", dish.name, reason));
+        if slots.is_empty() && !components.is_empty() {
+            return Err(bad_request("不是套餐，不能选择套餐内容".to_string()).into());
+        }
+        if let Some(component) = components.iter().find(|component| !slots.iter().any(|slot| slot.slot.id == component.slot_id)) {
+            return Err(bad_request(format!("套餐没有id为{}的部分", component.slot_id)).into());
+        }
+        let mut lines = Vec::with_capacity(slots.len());
+        for slot in slots {
+            let mut chosen = components.iter().filter(|component| component.slot_id == slot.slot.id);
+            let (Some(component), None) = (chosen.next(), chosen.next()) else {
+                return Err(bad_request(format!("{}需要选择一个菜品", slot.slot.name)).into());
+            };
+            let Some(choice) = slot.choices.iter().find(|choice| choice.dish_id == component.dish_id) else {
+                return Err(bad_request(format!("{}不能选择id为{}的菜品", slot.slot.name, component.dish_id)).into());
+            };
+            let component_dish = DishCurd::query_orderable(db, availability, component.dish_id.clone()).await?;
+            let variant = DishCurd::query_orderable_variant(db, &component_dish, component.variant_id.clone()).await?;
+            let modifiers = ModifierCurd::resolve(db, &component_dish, &component.modifier_ids).await?;
+            lines.push(ComboComponentLine {
+                slot_id: slot.slot.id,
+                slot_name: slot.slot.name,
+                dish_id: component_dish.id,
+                dish_name: component_dish.name,
+                variant_id: variant.as_ref().map(|variant| variant.id.clone()),
+                variant_name: variant.map(|variant| variant.name),
+                modifiers,
+                upcharge: choice.upcharge,
+                station: component_dish.station,
+            });
+        }
+        Ok(lines)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::das::combo::ComboCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::dto::combo::{ComboSlotDishData, CreateComboSlotData};
+    use crate::dto::order::OrderItemData;
+    use crate::entities::combo_slot::ComboComponent;
+
+    #[tokio::test]
+    async fn test_order_combo() {
+        init_db_coon().await;
+        let main = DishCurd::insert("test_combo_main".to_string(), Money::from_fen(3000), "test".to_string(), Some("热菜".to_string())).await.unwrap();
+        let drink = DishCurd::insert("test_combo_drink".to_string(), Money::from_fen(800), "test".to_string(), Some("饮品".to_string())).await.unwrap();
+        let category_id = CategoryCurd::insert("test_combo_mains".to_string()).await.unwrap();
+        CategoryDishMapCurd::insert(category_id.clone(), main.clone()).await.unwrap();
+        let combo = DishCurd::insert("test_combo".to_string(), Money::from_fen(3800), "test".to_string(), None).await.unwrap();
+        let slots = vec![
+            CreateComboSlotData { name: "主食".to_string(), category_id: Some(category_id), dishes: vec![] },
+            CreateComboSlotData { name: "饮品".to_string(), category_id: None, dishes: vec![ComboSlotDishData { dish_id: drink.clone(), upcharge: Money::from_fen(300) }] },
+        ];
+        ComboCurd::replace_slots(get_db_coon(), combo.clone(), slots).await.unwrap();
+        let detail = ComboCurd::query_for_dishes(get_db_coon(), vec![combo.clone()]).await.unwrap().remove(&combo).unwrap();
+        assert_eq!(detail[0].choices[0].dish_id, main);
+        let component = |slot: usize, dish_id: &String| ComboComponent { slot_id: detail[slot].slot.id.clone(), dish_id: dish_id.clone(), ..Default::default() };
+        let item = |components| vec![OrderItemData { dish_id: combo.clone(), components, quantity: 1, ..Default::default() }];
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), item(vec![component(0, &main)])).await.is_err());
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), item(vec![component(0, &drink), component(1, &drink)])).await.is_err());
+        let (id, total) = OrderCurd::insert("test".to_string(), None, Default::default(), item(vec![component(0, &main), component(1, &drink)])).await.unwrap();
+        assert_eq!(total, Money::from_fen(4100));
+        let open = OrderCurd::query_open_items(None).await.unwrap();
+        let open: Vec<_> = open.into_iter().filter(|item| item.order_id == id).collect();
+        assert_eq!(open.len(), 2);
+        assert!(open.iter().all(|item| item.price == Money::from_fen(0) && item.combo_item_id.is_some()));
+    }
+}
@@ -0,0 +1,83 @@
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::dining_table::DiningTableData;
+use crate::entities::dining_table::{Column, Status};
+use crate::entities::prelude::{DiningTable, DiningTables, TableSessions};
+use crate::entities::table_session;
+use crate::error::{AppError, AppResult};
+
+pub struct DiningTableCurd;
+impl DiningTableCurd {
+    /// 插入餐桌, 返回餐桌id
+    pub async fn insert(data: DiningTableData) -> AppResult<String> {
+        let status = Self::check_status(data.status)?.unwrap_or(Status::Idle);
+        let db = get_db_coon();
+        let uuid = Ulid::new();
+        let table = DiningTable {
+            id: uuid.to_string(),
+            name: data.name,
+            area: data.area,
+            capacity: data.capacity,
+            status,
+        };
+        DiningTables::insert(table.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    /// 修改餐桌，有客的餐桌不能停用
+    pub async fn update(id: String, data: DiningTableData) -> AppResult<DiningTable> {
+        let status = Self::check_status(data.status)?;
+        let db = get_db_coon();
+        let table = Self::query_by_id(id).await?;
+        let mut active = table.clone().into_active_model();
+        if let Some(status) = status
+            && status != table.status
+        {
+            if table.status == Status::Occupied {
+                return Err(AppError::conflict(format!("餐桌{}有客，结账后才能停用", table.name)));
+            }
+            active.status = Set(status);
+        }
+        active.name = Set(data.name);
+        active.area = Set(data.area);
+        active.capacity = Set(data.capacity);
+        Ok(active.update(db).await?)
+    }
+    /// 删除餐桌，还有进行中的会话时不能删除
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        let open = TableSessions::find()
+            .filter(table_session::Column::TableId.eq(id.clone()))
+            .filter(table_session::Column::ClosedAt.is_null())
+            .count(db)
+            .await?;
+        if open > 0 {
+            return Err(AppError::conflict("餐桌还有进行中的会话，结账后才能删除"));
+        }
+        DiningTables::delete_by_id(id).exec(db).await?;
+        Ok(())
+    }
+    /// 有客状态只能由开台设置
+    fn check_status(status: Option<Status>) -> AppResult<Option<Status>> {
+        if status == Some(Status::Occupied) {
+            return Err(StatusError::bad_request().brief("有客状态由开台设置，不能直接修改").into());
+        }
+        Ok(status)
+    }
+    pub async fn query_by_id(id: String) -> AppResult<DiningTable> {
+        let db = get_db_coon();
+        DiningTables::find_by_id(id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的餐桌不存在", id)).into())
+    }
+    pub async fn query_all() -> AppResult<Vec<DiningTable>> {
+        let db = get_db_coon();
+        Ok(DiningTables::find()
+            .order_by_asc(Column::Area)
+            .order_by_asc(Column::Name)
+            .all(db)
+            .await?)
+    }
+}
@@ -1,25 +1,63 @@
-use sea_orm::{PaginatorTrait, QueryOrder};
+use std::collections::{HashMap, HashSet};
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
 use sea_orm::{EntityTrait, IntoActiveModel};
 use ulid::Ulid;
 use crate::config::db::get_db_coon;
+use crate::das::category_dish_map::CategoryDishMapCurd;
+use crate::das::combo::ComboCurd;
+use crate::das::ingredient::IngredientCurd;
+use crate::das::modifier::ModifierCurd;
+use crate::das::schedule::{Availability, ScheduleCurd};
+use crate::das::stock::StockCurd;
+use crate::dto::menu::{DishDetail, ReplaceDishData, UpdateDishData, VariantData};
 use crate::entities::dish::{Column, Status};
-use crate::entities::prelude::{Dish, Dishes};
-use crate::error::AppResult;
-use crate::utils::get_now_time;
+use crate::entities::dish_variant;
+use crate::entities::schedule_window::Target;
+use crate::entities::prelude::{Dish, DishVariant, DishVariants, Dishes};
+use crate::error::{AppError, AppResult};
+use crate::images;
+use crate::utils::{check_order, get_now_time};
+use crate::utils::money::Money;
+
+/// 检查规格的名称和价格，返回不合法的原因
+pub fn check_variants(variants: &[VariantData]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for variant in variants {
+        let name = variant.name.trim();
+        if name.is_empty() {
+            return Err("规格名称不能为空".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("规格{}重复", name));
+        }
+        if variant.price.is_negative() {
+            return Err(format!("规格{}的价格{}不合法", name, variant.price));
+        }
+    }
+    Ok(())
+}
 
 pub struct DishCurd;
 impl DishCurd {
-    pub async fn insert(name: String, price: f64, picture: String) -> AppResult<String> {
+    pub async fn insert(name: String, price: Money, picture: String, station: Option<String>) -> AppResult<String> {
         let db = get_db_coon();
         let uuid = Ulid::new();
-        let index = Dishes::find().count(db).await?+1;
+        let index = Dishes::find()
+            .order_by_desc(Column::Index)
+            .one(db)
+            .await?
+            .map(|d| d.index + 1)
+            .unwrap_or(0);
         let dish = Dish {
             id: uuid.to_string(),
-            index: index as i32,
+            index,
             name,
             price,
             picture,
             status: Status::Normal,
+            station,
             created_at: get_now_time(),
         };
         Dishes::insert(
@@ -35,20 +73,316 @@ impl DishCurd {
             .await?;
         Ok(dishes)
     }
+    pub async fn query_by_id<C: ConnectionTrait>(db: &C, id: String) -> AppResult<Option<Dish>> {
+        Ok(Dishes::find_by_id(id).one(db).await?)
+    }
+    /// 查询可以点的菜品，菜品不存在、已下架或者不在供应时段内时返回错误
+    /// availability由调用方每次请求加载一次，下单和购物车的每一行都共用
+    pub async fn query_orderable<C: ConnectionTrait>(db: &C, availability: &Availability, id: String) -> AppResult<Dish> {
+        let Some(dish) = Self::query_by_id(db, id.clone()).await? else {
+            return Err(StatusError::bad_request()
+                .brief(format!("id为{}的菜品不存在", id))
+                .into());
+        };
+        if dish.status != Status::Normal {
+            return Err(StatusError::bad_request()
+                .brief(format!("{}已下架", dish.name))
+                .into());
+        }
+        if !availability.dish_open(&dish.id) {
+            return Err(StatusError::bad_request()
+                .brief(format!("{}现在不供应", dish.name))
+                .into());
+        }
+        Ok(dish)
+    }
+    /// 查询下单时使用的规格，菜品有规格时必须选择一个未下架的规格，没有规格时不能选择
+    pub async fn query_orderable_variant<C: ConnectionTrait>(db: &C, dish: &Dish, variant_id: Option<String>) -> AppResult<Option<DishVariant>> {
+        let variants = Self::query_variants(db, dish.id.clone()).await?;
+        let Some(variant_id) = variant_id else {
+            if variants.is_empty() {
+                return Ok(None);
+            }
+            return Err(StatusError::bad_request()
+                .brief(format!("请选择{}的规格", dish.name))
+                .into());
+        };
+        let Some(variant) = variants.into_iter().find(|variant| variant.id == variant_id) else {
+            return Err(StatusError::bad_request()
+                .brief(format!("{}没有id为{}的规格", dish.name, variant_id))
+                .into());
+        };
+        if variant.status != Status::Normal {
+            return Err(StatusError::bad_request()
+                .brief(format!("{}({})已下架", dish.name, variant.name))
+                .into());
+        }
+        Ok(Some(variant))
+    }
+    pub async fn query_variants<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<Vec<DishVariant>> {
+        Ok(DishVariants::find()
+            .filter(dish_variant::Column::DishId.eq(dish_id))
+            .order_by_asc(dish_variant::Column::Index)
+            .all(db)
+            .await?)
+    }
+    /// 给菜品附上规格、可选的选项分组和套餐的组成部分，并标记原料是否够做、当前是否供应
+    pub async fn with_details(dishes: Vec<Dish>) -> AppResult<Vec<DishDetail>> {
+        let db = get_db_coon();
+        let mut modifier_groups = ModifierCurd::query_for_dishes(db, dishes.iter().map(|dish| dish.id.clone()).collect()).await?;
+        let mut combo_slots = ComboCurd::query_for_dishes(db, dishes.iter().map(|dish| dish.id.clone()).collect()).await?;
+        let unavailable = IngredientCurd::query_unavailable(db, dishes.iter().map(|dish| dish.id.clone()).collect()).await?;
+        let availability = Availability::load(db).await?;
+        let mut variants: HashMap<String, Vec<DishVariant>> = HashMap::new();
+        for variant in DishVariants::find()
+            .filter(dish_variant::Column::DishId.is_in(dishes.iter().map(|dish| dish.id.clone())))
+            .order_by_asc(dish_variant::Column::Index)
+            .all(db)
+            .await?
+        {
+            variants.entry(variant.dish_id.clone()).or_default().push(variant);
+        }
+        Ok(dishes
+            .into_iter()
+            .map(|dish| DishDetail {
+                variants: variants.remove(&dish.id).unwrap_or_default(),
+                modifier_groups: modifier_groups.remove(&dish.id).unwrap_or_default(),
+                combo_slots: combo_slots.remove(&dish.id).unwrap_or_default(),
+                available: dish.status == Status::Normal && !unavailable.contains(&dish.id),
+                available_now: availability.dish_open(&dish.id),
+                dish,
+            })
+            .collect())
+    }
+    /// 替换菜品的全部规格，旧规格的库存一并删除
+    /// 有规格时菜品的价格改为第一个规格的价格，菜单和订单上菜品的价格始终是默认规格的价格，
+    /// 规格清空后菜品保留最后的价格，作为唯一的默认价格
+    pub async fn replace_variants<C: ConnectionTrait>(db: &C, dish_id: String, variants: Vec<VariantData>) -> AppResult<()> {
+        check_variants(&variants).map_err(|reason| StatusError::bad_request().brief(reason))?;
+        StockCurd::delete_variant_stocks(db, dish_id.clone()).await?;
+        DishVariants::delete_many()
+            .filter(dish_variant::Column::DishId.eq(dish_id.clone()))
+            .exec(db)
+            .await?;
+        if let Some(first) = variants.first() {
+            Dishes::update_many()
+                .col_expr(Column::Price, Expr::value(first.price))
+                .filter(Column::Id.eq(dish_id.clone()))
+                .exec(db)
+                .await?;
+        }
+        for (index, variant) in variants.into_iter().enumerate() {
+            let variant = DishVariant {
+                id: Ulid::new().to_string(),
+                dish_id: dish_id.clone(),
+                index: index as i32,
+                name: variant.name.trim().to_string(),
+                price: variant.price,
+                status: variant.status,
+            };
+            DishVariants::insert(variant.into_active_model()).exec(db).await?;
+        }
+        Ok(())
+    }
+    /// 修改菜品，只修改填了的字段，id和分类关系保持不变
+    pub async fn update(id: String, data: UpdateDishData) -> AppResult<Dish> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(dish) = Self::query_by_id(&txn, id.clone()).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的菜品不存在", id))
+                .into());
+        };
+        let mut active = dish.clone().into_active_model();
+        if let Some(name) = data.name {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(StatusError::bad_request().brief("菜品名称不能为空").into());
+            }
+            let existing = Dishes::find()
+                .filter(Column::Name.eq(name.clone()))
+                .filter(Column::Id.ne(id.clone()))
+                .one(&txn)
+                .await?;
+            if existing.is_some() {
+                return Err(AppError::conflict(format!("菜品{}已存在", name)));
+            }
+            active.name = Set(name);
+        }
+        if let Some(price) = data.price {
+            if price.is_negative() {
+                return Err(StatusError::bad_request()
+                    .brief(format!("价格{}不合法", price))
+                    .into());
+            }
+            let has_variants = match &data.variants {
+                Some(variants) => !variants.is_empty(),
+                None => !Self::query_variants(&txn, id.clone()).await?.is_empty(),
+            };
+            if has_variants {
+                return Err(StatusError::bad_request()
+                    .brief(format!("{}有规格，价格跟随第一个规格，请修改规格的价格", dish.name))
+                    .into());
+            }
+            active.price = Set(price);
+        }
+        if let Some(picture) = data.picture {
+            active.picture = Set(picture);
+        }
+        if let Some(station) = data.station {
+            active.station = Set(Some(station.trim().to_string()).filter(|station| !station.is_empty()));
+        }
+        if let Some(status) = data.status {
+            StockCurd::clear_auto_delisted(&txn, id.clone()).await?;
+            active.status = Set(status);
+        }
+        if active.is_changed() {
+            active.update(&txn).await?;
+        }
+        if let Some(variants) = data.variants {
+            Self::replace_variants(&txn, id.clone(), variants).await?;
+        }
+        if let Some(category_ids) = data.category_ids {
+            CategoryDishMapCurd::replace_for_dish(&txn, id.clone(), category_ids).await?;
+        }
+        let updated = Self::query_by_id(&txn, id).await?.unwrap_or(dish.clone());
+        txn.commit().await?;
+        if updated.picture != dish.picture {
+            images::remove_if_orphan(db, &dish.picture).await?;
+        }
+        Ok(updated)
+    }
+    /// 整体替换菜品，没有填的档口会被清空，id和套餐内容保持不变
+    pub async fn replace(id: String, data: ReplaceDishData) -> AppResult<Dish> {
+        if let Some(first) = data.variants.first()
+            && first.price != data.price
+        {
+            return Err(StatusError::bad_request()
+                .brief(format!("有规格时价格必须等于第一个规格的价格{}", first.price))
+                .into());
+        }
+        let data = UpdateDishData {
+            name: Some(data.name),
+            price: data.variants.is_empty().then_some(data.price),
+            picture: Some(data.picture),
+            station: Some(data.station.unwrap_or_default()),
+            status: Some(data.status),
+            category_ids: Some(data.category_ids),
+            variants: Some(data.variants),
+        };
+        Self::update(id, data).await
+    }
+    /// 按ids的顺序重写全部菜品的index
+    pub async fn reorder(ids: Vec<String>) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let existing = Dishes::find().all(&txn).await?.into_iter().map(|dish| dish.id).collect();
+        check_order(existing, &ids).map_err(|reason| StatusError::bad_request().brief(reason))?;
+        for (index, id) in ids.into_iter().enumerate() {
+            Dishes::update_many()
+                .col_expr(Column::Index, Expr::value(index as i32))
+                .filter(Column::Id.eq(id))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+    /// 手动上架或下架菜品
+    pub async fn set_status(id: String, status: Status) -> AppResult<Dish> {
+        Self::update(id, UpdateDishData { status: Some(status), ..Default::default() }).await
+    }
+    /// 删除菜品，图片没有其他菜品使用时一起删除
     pub async fn delete_by_id(id: String) -> AppResult<()> {
         let db = get_db_coon();
+        let Some(dish) = Self::query_by_id(db, id.clone()).await? else {
+            return Ok(());
+        };
+        ComboCurd::delete_by_dish(db, id.clone()).await?;
+        StockCurd::delete_by_dish(db, id.clone()).await?;
+        IngredientCurd::delete_recipe(db, id.clone()).await?;
+        ScheduleCurd::delete_by_target(db, Target::Dish, id.clone()).await?;
+        DishVariants::delete_many()
+            .filter(dish_variant::Column::DishId.eq(id.clone()))
+            .exec(db)
+            .await?;
         Dishes::delete_by_id(id).exec(db).await?;
+        images::remove_if_orphan(db, &dish.picture).await?;
         Ok(())
     }
 }
 #[cfg(test)]
 mod tests {
-    use crate::config::db::init_db_coon;
+    use crate::utils::money::Money;
+    use crate::config::db::{get_db_coon, init_db_coon};
     use crate::das::dish::DishCurd;
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::dto::menu::{ReplaceDishData, UpdateDishData, VariantData};
+    use crate::entities::dish::Status;
 
     #[tokio::test]
     async fn test_create_dish() {
         init_db_coon().await;
-        DishCurd::insert("test".to_string(), 100.0, "test".to_string()).await.unwrap();
+        DishCurd::insert("test".to_string(), Money::from_fen(10000), "test".to_string(), None).await.unwrap();
     }
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn test_dish_variants() {
+        init_db_coon().await;
+        let id = DishCurd::insert("test_variant_dish".to_string(), Money::from_fen(0), "test".to_string(), None).await.unwrap();
+        let variant = |name: &str, price| VariantData { name: name.to_string(), price, status: Status::Normal };
+        assert!(DishCurd::replace_variants(get_db_coon(), id.clone(), vec![variant("小份", Money::from_fen(1800)), variant("小份", Money::from_fen(2800))]).await.is_err());
+        DishCurd::replace_variants(get_db_coon(), id.clone(), vec![variant("小份", Money::from_fen(1800)), variant("大份", Money::from_fen(2800))]).await.unwrap();
+        let dish = DishCurd::query_by_id(get_db_coon(), id.clone()).await.unwrap().unwrap();
+        assert_eq!(dish.price, Money::from_fen(1800));
+        assert!(DishCurd::query_orderable_variant(get_db_coon(), &dish, None).await.is_err());
+        let variants = DishCurd::query_variants(get_db_coon(), id.clone()).await.unwrap();
+        let large = DishCurd::query_orderable_variant(get_db_coon(), &dish, Some(variants[1].id.clone())).await.unwrap();
+        assert_eq!(large.unwrap().price, Money::from_fen(2800));
+        //菜品价格跟随第一个规格，不能单独修改，规格清空后保留最后的价格，不选规格下单
+        let price = |price| UpdateDishData { price: Some(Money::from_fen(price)), ..Default::default() };
+        assert!(DishCurd::update(id.clone(), price(2000)).await.is_err());
+        let data = UpdateDishData { variants: Some(vec![variant("大份", Money::from_fen(3000)), variant("小份", Money::from_fen(2000))]), ..price(2000) };
+        assert!(DishCurd::update(id.clone(), data).await.is_err());
+        let data = UpdateDishData { variants: Some(vec![variant("大份", Money::from_fen(3000)), variant("小份", Money::from_fen(2000))]), ..Default::default() };
+        assert_eq!(DishCurd::update(id.clone(), data).await.unwrap().price, Money::from_fen(3000));
+        let dish = DishCurd::update(id.clone(), UpdateDishData { variants: Some(vec![]), ..Default::default() }).await.unwrap();
+        assert_eq!(dish.price, Money::from_fen(3000));
+        assert!(DishCurd::query_orderable_variant(get_db_coon(), &dish, None).await.unwrap().is_none());
+        assert_eq!(DishCurd::update(id, price(2000)).await.unwrap().price, Money::from_fen(2000));
+    }
+    #[tokio::test]
+    async fn test_update_dish() {
+        init_db_coon().await;
+        let id = DishCurd::insert("test_update_dish".to_string(), Money::from_fen(1000), "test".to_string(), None).await.unwrap();
+        let lunch = CategoryCurd::insert("test_update_lunch".to_string()).await.unwrap();
+        let dinner = CategoryCurd::insert("test_update_dinner".to_string()).await.unwrap();
+        CategoryDishMapCurd::insert(lunch.clone(), id.clone()).await.unwrap();
+        let data = UpdateDishData {
+            name: Some("test_update_dish_new".to_string()),
+            price: Some(Money::from_fen(1250)),
+            category_ids: Some(vec![dinner.clone()]),
+            ..Default::default()
+        };
+        let dish = DishCurd::update(id.clone(), data).await.unwrap();
+        assert_eq!((dish.id.as_str(), dish.name.as_str(), dish.price), (id.as_str(), "test_update_dish_new", Money::from_fen(1250)));
+        assert!(CategoryCurd::query_related_dishes(lunch.clone()).await.unwrap().is_empty());
+        assert_eq!(CategoryCurd::query_related_dishes(dinner).await.unwrap().len(), 1);
+        let dish = DishCurd::set_status(id.clone(), Status::Delist).await.unwrap();
+        assert_eq!(dish.status, Status::Delist);
+        //整体替换时没有填的档口被清空，分类被替换
+        DishCurd::update(id.clone(), UpdateDishData { station: Some("热菜".to_string()), ..Default::default() }).await.unwrap();
+        let data = ReplaceDishData {
+            name: "test_update_dish".to_string(),
+            price: Money::from_fen(1500),
+            picture: "test".to_string(),
+            station: None,
+            status: Status::Normal,
+            category_ids: vec![lunch.clone()],
+            variants: vec![],
+        };
+        let dish = DishCurd::replace(id, data).await.unwrap();
+        assert_eq!((dish.price, dish.station, dish.status), (Money::from_fen(1500), None, Status::Normal));
+        assert_eq!(CategoryCurd::query_related_dishes(lunch).await.unwrap().len(), 1);
+    }
+}
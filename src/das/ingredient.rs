@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+use log::warn;
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::ingredient::{DishCost, IngredientData, IngredientDetail, RecipeData, RecipeDetail};
+use crate::entities::ingredient::round_quantity;
+use crate::entities::prelude::{Dishes, Ingredient, Ingredients, RecipeLine, RecipeLines};
+use crate::entities::{dish, ingredient, recipe_line};
+use crate::error::{AppError, AppResult};
+use crate::utils::money::Money;
+
+pub struct IngredientCurd;
+impl IngredientCurd {
+    /// 插入原料, 返回原料id
+    pub async fn insert(data: IngredientData) -> AppResult<String> {
+        let db = get_db_coon();
+        Self::check_name(&data.name, None).await?;
+        let uuid = Ulid::new();
+        let ingredient = Ingredient {
+            id: uuid.to_string(),
+            name: data.name,
+            unit: data.unit,
+            quantity: round_quantity(data.quantity),
+            unit_cost: data.unit_cost,
+            low_threshold: data.low_threshold,
+        };
+        Ingredients::insert(ingredient.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    /// 修改原料，补货和盘点时直接设置库存
+    pub async fn update(id: String, data: IngredientData) -> AppResult<Ingredient> {
+        let db = get_db_coon();
+        Self::check_name(&data.name, Some(&id)).await?;
+        let Some(ingredient) = Ingredients::find_by_id(id.clone()).one(db).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的原料不存在", id))
+                .into());
+        };
+        let mut ingredient = ingredient.into_active_model();
+        ingredient.name = Set(data.name);
+        ingredient.unit = Set(data.unit);
+        ingredient.quantity = Set(round_quantity(data.quantity));
+        ingredient.unit_cost = Set(data.unit_cost);
+        ingredient.low_threshold = Set(data.low_threshold);
+        Ok(ingredient.update(db).await?)
+    }
+    async fn check_name(name: &str, id: Option<&String>) -> AppResult<()> {
+        let db = get_db_coon();
+        let existing = Ingredients::find()
+            .filter(ingredient::Column::Name.eq(name))
+            .one(db)
+            .await?;
+        if existing.is_some_and(|existing| Some(&existing.id) != id) {
+            return Err(AppError::conflict(format!("原料{}已存在", name)));
+        }
+        Ok(())
+    }
+    /// 删除原料，用到这个原料的配方行一起删除
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        RecipeLines::delete_many()
+            .filter(recipe_line::Column::IngredientId.eq(id.clone()))
+            .exec(&txn)
+            .await?;
+        Ingredients::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+    pub async fn query_all() -> AppResult<Vec<IngredientDetail>> {
+        let db = get_db_coon();
+        Ok(Ingredients::find()
+            .order_by_asc(ingredient::Column::Name)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|ingredient| IngredientDetail { low: ingredient.is_low(), ingredient })
+            .collect())
+    }
+    /// 替换菜品的配方，lines为空时删除配方
+    pub async fn replace_recipe(dish_id: String, data: RecipeData) -> AppResult<Vec<RecipeDetail>> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        if Dishes::find_by_id(dish_id.clone()).one(&txn).await?.is_none() {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的菜品不存在", dish_id))
+                .into());
+        }
+        let mut ingredient_ids = HashSet::new();
+        for line in &data.lines {
+            if !ingredient_ids.insert(&line.ingredient_id) {
+                return Err(StatusError::bad_request()
+                    .brief(format!("原料{}重复", line.ingredient_id))
+                    .into());
+            }
+            if Ingredients::find_by_id(line.ingredient_id.clone()).one(&txn).await?.is_none() {
+                return Err(StatusError::bad_request()
+                    .brief(format!("id为{}的原料不存在", line.ingredient_id))
+                    .into());
+            }
+        }
+        Self::delete_recipe(&txn, dish_id.clone()).await?;
+        for line in data.lines {
+            let line = RecipeLine { dish_id: dish_id.clone(), ingredient_id: line.ingredient_id, quantity: line.quantity };
+            RecipeLines::insert(line.into_active_model()).exec(&txn).await?;
+        }
+        txn.commit().await?;
+        Self::query_recipe(dish_id).await
+    }
+    /// 删除菜品时调用
+    pub async fn delete_recipe<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<()> {
+        RecipeLines::delete_many()
+            .filter(recipe_line::Column::DishId.eq(dish_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    pub async fn query_recipe(dish_id: String) -> AppResult<Vec<RecipeDetail>> {
+        let db = get_db_coon();
+        Ok(RecipeLines::find()
+            .find_also_related(Ingredients)
+            .filter(recipe_line::Column::DishId.eq(dish_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(line, ingredient)| {
+                let ingredient = ingredient?;
+                Some(RecipeDetail {
+                    cost: ingredient.unit_cost.mul_f64(line.quantity),
+                    ingredient_name: ingredient.name,
+                    unit: ingredient.unit,
+                    line,
+                })
+            })
+            .collect())
+    }
+    /// 按配方汇总每个菜品的原料成本
+    pub async fn query_costs() -> AppResult<Vec<DishCost>> {
+        let db = get_db_coon();
+        let ingredients: HashMap<String, Ingredient> = Ingredients::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|ingredient| (ingredient.id.clone(), ingredient))
+            .collect();
+        let mut costs: HashMap<String, Money> = HashMap::new();
+        for line in RecipeLines::find().all(db).await? {
+            if let Some(ingredient) = ingredients.get(&line.ingredient_id) {
+                *costs.entry(line.dish_id).or_default() += ingredient.unit_cost.mul_f64(line.quantity);
+            }
+        }
+        Ok(Dishes::find()
+            .order_by_asc(dish::Column::Index)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|dish| {
+                let cost = costs.get(&dish.id).copied().unwrap_or_default();
+                DishCost {
+                    dish_id: dish.id,
+                    dish_name: dish.name,
+                    price: dish.price,
+                    cost,
+                    margin: dish.price - cost,
+                }
+            })
+            .collect())
+    }
+    /// 有原料不够做一份的菜品，这些菜品在菜单上显示为不可点
+    pub async fn query_unavailable<C: ConnectionTrait>(db: &C, dish_ids: Vec<String>) -> AppResult<HashSet<String>> {
+        Ok(RecipeLines::find()
+            .find_also_related(Ingredients)
+            .filter(recipe_line::Column::DishId.is_in(dish_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|(line, ingredient)| ingredient.as_ref().is_some_and(|ingredient| ingredient.quantity < line.quantity))
+            .map(|(line, _)| line.dish_id)
+            .collect())
+    }
+    /// 下单时按配方扣减原料，任何一种原料不够时整个订单失败
+    pub async fn consume<C: ConnectionTrait>(db: &C, dish_id: &str, dish_name: &str, servings: i32) -> AppResult<()> {
+        for (line, ingredient) in Self::query_lines(db, dish_id).await? {
+            let need = line.quantity * servings as f64;
+            if round_quantity(ingredient.quantity - need) < 0.0 {
+                return Err(AppError::conflict(format!("{}的原料{}不足", dish_name, ingredient.name)));
+            }
+            Self::change(db, ingredient, -need).await?;
+        }
+        Ok(())
+    }
+    /// 取消订单或者退掉还没做的菜时退回原料
+    pub async fn restore<C: ConnectionTrait>(db: &C, dish_id: &str, servings: i32) -> AppResult<()> {
+        for (line, ingredient) in Self::query_lines(db, dish_id).await? {
+            Self::change(db, ingredient, line.quantity * servings as f64).await?;
+        }
+        Ok(())
+    }
+    async fn query_lines<C: ConnectionTrait>(db: &C, dish_id: &str) -> AppResult<Vec<(RecipeLine, Ingredient)>> {
+        Ok(RecipeLines::find()
+            .find_also_related(Ingredients)
+            .filter(recipe_line::Column::DishId.eq(dish_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(line, ingredient)| Some((line, ingredient?)))
+            .collect())
+    }
+    async fn change<C: ConnectionTrait>(db: &C, ingredient: Ingredient, delta: f64) -> AppResult<()> {
+        let was_low = ingredient.is_low();
+        let mut active = ingredient.into_active_model();
+        active.quantity = Set(round_quantity(active.quantity.as_ref() + delta));
+        let ingredient = active.update(db).await?;
+        if ingredient.is_low() && !was_low {
+            warn!("原料不足提醒: {}只剩{}{}", ingredient.name, ingredient.quantity, ingredient.unit);
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::dish::DishCurd;
+    use crate::das::ingredient::IngredientCurd;
+    use crate::das::order::OrderCurd;
+    use crate::dto::ingredient::{IngredientData, RecipeData, RecipeLineData};
+    use crate::dto::order::OrderItemData;
+    use crate::entities::order;
+    use crate::utils::money::Money;
+
+    #[tokio::test]
+    async fn test_recipe_consumes_ingredients() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_recipe_dish".to_string(), Money::from_fen(2000), "test".to_string(), None).await.unwrap();
+        let data = IngredientData {
+            name: "test_recipe_pork".to_string(),
+            unit: "kg".to_string(),
+            quantity: 0.5,
+            unit_cost: Money::from_fen(3000),
+            low_threshold: 0.0,
+        };
+        let pork = IngredientCurd::insert(data).await.unwrap();
+        let lines = vec![RecipeLineData { ingredient_id: pork.clone(), quantity: 0.2 }];
+        let recipe = IngredientCurd::replace_recipe(dish_id.clone(), RecipeData { lines }).await.unwrap();
+        assert_eq!(recipe[0].cost, Money::from_fen(600));
+        let cost = IngredientCurd::query_costs().await.unwrap().into_iter().find(|cost| cost.dish_id == dish_id).unwrap();
+        assert_eq!(cost.margin, Money::from_fen(1400));
+        let item = |quantity| vec![OrderItemData { dish_id: dish_id.clone(), quantity, ..Default::default() }];
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), item(3)).await.is_err());
+        let (id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), item(2)).await.unwrap();
+        let unavailable = IngredientCurd::query_unavailable(get_db_coon(), vec![dish_id.clone()]).await.unwrap();
+        assert!(unavailable.contains(&dish_id));
+        OrderCurd::transition(id, order::Status::Cancelled, "test".to_string()).await.unwrap();
+        let unavailable = IngredientCurd::query_unavailable(get_db_coon(), vec![dish_id]).await.unwrap();
+        assert!(unavailable.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder};
+use ulid::Ulid;
+use crate::config::{MemberConfig, CONFIG};
+use crate::config::db::get_db_coon;
+use crate::dto::member::RegisterMemberData;
+use crate::entities::member::Column;
+use crate::entities::points_entry::Reason;
+use crate::entities::prelude::{Member, Members, Order, PointsEntries, PointsEntry};
+use crate::entities::points_entry;
+use crate::error::{AppError, AppResult};
+use crate::utils::{get_now_time, hash_password, verify_password};
+
+/// 会员积分配置，测试时没有加载配置文件，使用默认配置
+fn member_config() -> MemberConfig {
+    CONFIG.get().map(|config| config.member.clone()).unwrap_or_default()
+}
+
+pub struct MemberCurd;
+impl MemberCurd {
+    /// 注册会员，手机号不能重复
+    pub async fn register(data: RegisterMemberData) -> AppResult<Member> {
+        let db = get_db_coon();
+        if Members::find().filter(Column::Phone.eq(data.phone.clone())).one(db).await?.is_some() {
+            return Err(AppError::conflict(format!("手机号{}已注册", data.phone)));
+        }
+        let member = Member {
+            id: Ulid::new().to_string(),
+            phone: data.phone,
+            name: data.name,
+            password: hash_password(&data.password)?,
+            points: 0,
+            created_at: get_now_time(),
+        };
+        Members::insert(member.clone().into_active_model()).exec(db).await?;
+        Ok(member)
+    }
+    /// 手机号和密码登录，不区分是手机号不存在还是密码错误
+    pub async fn login(phone: String, password: String) -> AppResult<Member> {
+        let db = get_db_coon();
+        let member = Members::find().filter(Column::Phone.eq(phone)).one(db).await?;
+        match member {
+            Some(member) if verify_password(&password, &member.password).is_ok() => Ok(member),
+            _ => Err(StatusError::unauthorized().brief("手机号或密码错误").into()),
+        }
+    }
+    pub async fn query_by_id<C: ConnectionTrait>(db: &C, id: String) -> AppResult<Member> {
+        Members::find_by_id(id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的会员不存在", id)).into())
+    }
+    /// 员工用顾客报的手机号查会员
+    pub async fn query_by_phone(phone: String) -> AppResult<Member> {
+        let db = get_db_coon();
+        Members::find()
+            .filter(Column::Phone.eq(phone.clone()))
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("手机号{}不是会员", phone)).into())
+    }
+    /// 积分流水，最新的在前面
+    pub async fn query_entries(member_id: String) -> AppResult<Vec<PointsEntry>> {
+        let db = get_db_coon();
+        Ok(PointsEntries::find()
+            .filter(points_entry::Column::MemberId.eq(member_id))
+            .order_by_desc(points_entry::Column::Id)
+            .all(db)
+            .await?)
+    }
+    /// 变更积分并记录流水，扣减时余额不足返回错误，同时扣减时只有余额够的会成功
+    pub async fn change<C: ConnectionTrait>(db: &C, member_id: &str, delta: i64, reason: Reason, order_id: Option<String>) -> AppResult<()> {
+        if delta == 0 {
+            return Ok(());
+        }
+        let updated = Members::update_many()
+            .col_expr(Column::Points, Expr::col(Column::Points).add(delta))
+            .filter(Column::Id.eq(member_id))
+            .filter(Column::Points.gte(-delta.min(0)))
+            .exec(db)
+            .await?;
+        if updated.rows_affected == 0 {
+            let member = Self::query_by_id(db, member_id.to_string()).await?;
+            return Err(AppError::conflict(format!("积分余额不足，当前余额{}", member.points)));
+        }
+        let member = Self::query_by_id(db, member_id.to_string()).await?;
+        let entry = PointsEntry {
+            id: Ulid::new().to_string(),
+            member_id: member.id,
+            order_id,
+            reason,
+            delta,
+            balance: member.points,
+            created_at: get_now_time(),
+        };
+        PointsEntries::insert(entry.into_active_model()).exec(db).await?;
+        Ok(())
+    }
+    /// 订单支付后按实付金额发放积分，不满1元的部分不计，返回获得的积分
+    pub async fn earn<C: ConnectionTrait>(db: &C, order: &Order) -> AppResult<i64> {
+        let Some(member_id) = &order.member_id else {
+            return Ok(0);
+        };
+        let points = (order.total - order.refunded).fen().max(0) / 100 * member_config().points_per_yuan;
+        Self::change(db, member_id, points, Reason::Earn, Some(order.id.clone())).await?;
+        Ok(points)
+    }
+    /// 订单退款后按剩余的实付金额重新计算积分，扣回多发的部分，返回订单保留的积分
+    /// 会员已经用掉的积分不再追扣，余额扣到0为止
+    pub async fn reverse<C: ConnectionTrait>(db: &C, order: &Order) -> AppResult<i64> {
+        let Some(member_id) = &order.member_id else {
+            return Ok(order.points_earned);
+        };
+        let keep = (order.total - order.refunded).fen().max(0) / 100 * member_config().points_per_yuan;
+        let balance = Self::query_by_id(db, member_id.clone()).await?.points;
+        let deduct = (order.points_earned - keep).clamp(0, balance.max(0));
+        Self::change(db, member_id, -deduct, Reason::Refund, Some(order.id.clone())).await?;
+        Ok(order.points_earned - deduct)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::init_db_coon;
+    use crate::das::adjustment::AdjustmentCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::member::MemberCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::payment::PaymentCurd;
+    use crate::dto::adjustment::RefundData;
+    use crate::dto::member::RegisterMemberData;
+    use crate::dto::order::{OrderItemData, OrderOptions};
+    use crate::dto::payment::TenderData;
+    use crate::entities::item_void::ReasonCode;
+    use crate::entities::order::Status;
+    use crate::entities::payment::Method;
+    use crate::config::db::get_db_coon;
+    use crate::utils::money::Money;
+
+    #[tokio::test]
+    async fn test_points() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_member_dish".to_string(), Money::from_fen(5050), "test".to_string(), None).await.unwrap();
+        let data = RegisterMemberData { phone: "13800000025".to_string(), name: "test".to_string(), password: "12345678".to_string() };
+        let member = MemberCurd::register(data).await.unwrap();
+        let options = |redeem_points| OrderOptions { member_id: Some(member.id.clone()), redeem_points, ..Default::default() };
+        let items = || vec![OrderItemData { dish_id: dish_id.clone(), quantity: 1, ..Default::default() }];
+        //余额不足时不能抵扣
+        assert!(OrderCurd::insert("test".to_string(), None, options(100), items()).await.is_err());
+        let (id, total) = OrderCurd::insert("test".to_string(), None, options(0), items()).await.unwrap();
+        for status in [Status::Accepted, Status::Cooking, Status::Served] {
+            OrderCurd::transition(id.clone(), status, "test".to_string()).await.unwrap();
+        }
+        //结账付清后发放积分
+        let tenders = vec![TenderData { method: Method::Cash, amount: total, bill_split_id: None }];
+        let payments = PaymentCurd::pay(id.clone(), tenders, "test".to_string()).await.unwrap();
+        assert_eq!(OrderCurd::query_by_id(id.clone()).await.unwrap().unwrap().points_earned, 50);
+        //退款后按剩余的实付金额30.50扣回20积分
+        let refund = RefundData {
+            payment_id: payments[0].id.clone(),
+            amount: Money::from_fen(2000),
+            reason: ReasonCode::CustomerComplaint,
+            note: String::new(),
+            approver: None,
+        };
+        AdjustmentCurd::refund(id.clone(), refund, "test".to_string()).await.unwrap();
+        assert_eq!(OrderCurd::query_by_id(id).await.unwrap().unwrap().points_earned, 30);
+        assert_eq!(MemberCurd::query_by_id(get_db_coon(), member.id.clone()).await.unwrap().points, 30);
+        let (id, total) = OrderCurd::insert("test".to_string(), None, options(30), items()).await.unwrap();
+        assert_eq!(total, Money::from_fen(5020));
+        //取消订单后退回抵扣的积分
+        OrderCurd::transition(id, Status::Cancelled, "test".to_string()).await.unwrap();
+        let mut deltas: Vec<i64> = MemberCurd::query_entries(member.id.clone()).await.unwrap().iter().map(|entry| entry.delta).collect();
+        deltas.sort();
+        assert_eq!(deltas, vec![-30, -20, 30, 50]);
+        assert_eq!(MemberCurd::query_by_id(get_db_coon(), member.id).await.unwrap().points, 30);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::category_dish_map::CategoryDishMapCurd;
+use crate::das::dish::{check_variants, DishCurd};
+use crate::dto::menu::{ImportEntry, ImportReport, MenuCategory, MenuData, MenuDish, MenuKind, RejectedEntry, VariantData};
+use crate::entities::prelude::{Categories, Category, CategoryDishMaps, Dish, DishVariants, Dishes};
+use crate::entities::{category, dish, dish_variant};
+use crate::error::AppResult;
+use crate::images;
+use crate::utils::get_now_time;
+
+/// 菜单的整体导入导出
+pub struct MenuCurd;
+impl MenuCurd {
+    pub async fn export() -> AppResult<MenuData> {
+        let db = get_db_coon();
+        let categories = Categories::find().order_by_asc(category::Column::Index).all(db).await?;
+        let dishes = Dishes::find().order_by_asc(dish::Column::Index).all(db).await?;
+        let mut dish_variants: HashMap<String, Vec<VariantData>> = HashMap::new();
+        for variant in DishVariants::find().order_by_asc(dish_variant::Column::Index).all(db).await? {
+            dish_variants.entry(variant.dish_id).or_default().push(VariantData {
+                name: variant.name,
+                price: variant.price,
+                status: variant.status,
+            });
+        }
+        let mut dish_categories: HashMap<String, Vec<String>> = HashMap::new();
+        let maps = CategoryDishMaps::find().all(db).await?;
+        //按分类的顺序排列菜品所属的分类
+        for category in &categories {
+            for map in maps.iter().filter(|map| map.category_id == category.id) {
+                dish_categories.entry(map.dish_id.clone()).or_default().push(category.name.clone());
+            }
+        }
+        Ok(MenuData {
+            categories: categories
+                .into_iter()
+                .map(|category| MenuCategory { name: category.name })
+                .collect(),
+            dishes: dishes
+                .into_iter()
+                .map(|dish| MenuDish {
+                    categories: dish_categories.remove(&dish.id).unwrap_or_default(),
+                    variants: dish_variants.remove(&dish.id).unwrap_or_default(),
+                    name: dish.name,
+                    price: dish.price,
+                    picture: dish.picture,
+                    status: dish.status,
+                    station: dish.station,
+                })
+                .collect(),
+        })
+    }
+    /// 导入菜单，按名称匹配已有的分类和菜品，已有的菜品会被更新并替换所属分类和规格
+    /// 在一个事务里执行，试运行或者有被拒绝的条目时回滚，只返回导入结果
+    pub async fn import(data: MenuData, dry_run: bool) -> AppResult<ImportReport> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let mut report = ImportReport { dry_run, ..Default::default() };
+        let mut categories: HashMap<String, String> = Categories::find()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|category| (category.name, category.id))
+            .collect();
+        let mut next_index = Categories::find()
+            .order_by_desc(category::Column::Index)
+            .one(&txn)
+            .await?
+            .map(|category| category.index + 1)
+            .unwrap_or(0);
+        let mut seen = HashSet::new();
+        for category in data.categories {
+            let name = category.name.trim().to_string();
+            let reject = |reason: &str| RejectedEntry { kind: MenuKind::Category, name: name.clone(), reason: reason.to_string() };
+            if name.is_empty() {
+                report.rejected.push(reject("名称不能为空"));
+                continue;
+            }
+            if !seen.insert(name.clone()) {
+                report.rejected.push(reject("名称在导入数据中重复"));
+                continue;
+            }
+            if categories.contains_key(&name) {
+                continue;
+            }
+            let category = Category { id: Ulid::new().to_string(), index: next_index, name: name.clone() };
+            next_index += 1;
+            Categories::insert(category.clone().into_active_model()).exec(&txn).await?;
+            categories.insert(name.clone(), category.id);
+            report.created.push(ImportEntry { kind: MenuKind::Category, name });
+        }
+
+        let mut dishes: HashMap<String, Dish> = Dishes::find()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|dish| (dish.name.clone(), dish))
+            .collect();
+        let mut replaced_pictures = Vec::new();
+        let mut next_index = dishes.values().map(|dish| dish.index + 1).max().unwrap_or(0);
+        let mut seen = HashSet::new();
+        for data in data.dishes {
+            let name = data.name.trim().to_string();
+            let reject = |reason: String| RejectedEntry { kind: MenuKind::Dish, name: name.clone(), reason };
+            if name.is_empty() {
+                report.rejected.push(reject("名称不能为空".to_string()));
+                continue;
+            }
+            if !seen.insert(name.clone()) {
+                report.rejected.push(reject("名称在导入数据中重复".to_string()));
+                continue;
+            }
+            if data.price.is_negative() {
+                report.rejected.push(reject(format!("价格{}不合法", data.price)));
+                continue;
+            }
+            if let Err(reason) = check_variants(&data.variants) {
+                report.rejected.push(reject(reason));
+                continue;
+            }
+            if let Some(first) = data.variants.first()
+                && first.price != data.price
+            {
+                report.rejected.push(reject(format!("有规格时价格必须等于第一个规格的价格{}", first.price)));
+                continue;
+            }
+            let category_ids: Result<Vec<String>, String> = data
+                .categories
+                .iter()
+                .map(|name| categories.get(name).cloned().ok_or_else(|| format!("分类{}不存在", name)))
+                .collect();
+            let category_ids = match category_ids {
+                Ok(ids) => ids,
+                Err(reason) => {
+                    report.rejected.push(reject(reason));
+                    continue;
+                }
+            };
+            let dish_id = if let Some(dish) = dishes.remove(&name) {
+                let id = dish.id.clone();
+                if dish.picture != data.picture {
+                    replaced_pictures.push(dish.picture.clone());
+                }
+                let mut dish = dish.into_active_model();
+                dish.price = Set(data.price);
+                dish.picture = Set(data.picture);
+                dish.status = Set(data.status);
+                dish.station = Set(data.station);
+                dish.update(&txn).await?;
+                report.updated.push(ImportEntry { kind: MenuKind::Dish, name });
+                id
+            } else {
+                let dish = Dish {
+                    id: Ulid::new().to_string(),
+                    index: next_index,
+                    name: name.clone(),
+                    price: data.price,
+                    picture: data.picture,
+                    status: data.status,
+                    station: data.station,
+                    created_at: get_now_time(),
+                };
+                next_index += 1;
+                Dishes::insert(dish.clone().into_active_model()).exec(&txn).await?;
+                report.created.push(ImportEntry { kind: MenuKind::Dish, name });
+                dish.id
+            };
+            DishCurd::replace_variants(&txn, dish_id.clone(), data.variants).await?;
+            CategoryDishMapCurd::replace_for_dish(&txn, dish_id, category_ids).await?;
+        }
+        if dry_run || !report.rejected.is_empty() {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+            report.applied = true;
+            for picture in replaced_pictures {
+                images::remove_if_orphan(db, &picture).await?;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::db::init_db_coon;
+    use crate::das::menu::MenuCurd;
+    use crate::dto::menu::MenuData;
+
+    #[tokio::test]
+    async fn test_import_menu() {
+        init_db_coon().await;
+        let csv = "kind,name,price,picture,status,station,categories\n\
+            Category,test_menu_category,,,,,\n\
+            Dish,test_menu_dish,12.5,,Normal,热菜,test_menu_category\n\
+            Dish,test_menu_dish,13,,,,\n\
+            Dish,test_menu_orphan,10,,,,test_menu_missing\n";
+        let report = MenuCurd::import(MenuData::from_csv(csv).unwrap(), false).await.unwrap();
+        assert_eq!(report.rejected.len(), 2);
+        assert!(!report.applied);
+        let csv = "kind,name,price,picture,status,station,categories\n\
+            Category,test_menu_category,,,,,\n\
+            Dish,test_menu_dish,12.5,,Normal,热菜,test_menu_category\n";
+        let report = MenuCurd::import(MenuData::from_csv(csv).unwrap(), true).await.unwrap();
+        assert_eq!(report.created.len(), 2);
+        assert!(!report.applied);
+        MenuCurd::import(MenuData::from_csv(csv).unwrap(), false).await.unwrap();
+        let report = MenuCurd::import(MenuData::from_csv(csv).unwrap(), false).await.unwrap();
+        assert_eq!(report.updated.len(), 1);
+        let menu = MenuCurd::export().await.unwrap();
+        let dish = menu.dishes.iter().find(|dish| dish.name == "test_menu_dish").unwrap();
+        assert_eq!(dish.categories, vec!["test_menu_category".to_string()]);
+        assert!(menu.to_csv().unwrap().contains("Dish,test_menu_dish,12.50,,Normal,热菜,test_menu_category"));
+    }
+}
@@ -1,6 +1,23 @@
-///类似java的dao(data access object)，但是rust是struct。
+//! 类似java的dao(data access object)，但是rust是struct。
 
 pub mod users;
 pub mod category;
 pub mod dish;
-pub mod category_dish_map;
\ No newline at end of file
+pub mod category_dish_map;
+pub mod order;
+pub mod dining_table;
+pub mod table_session;
+pub mod cart;
+pub mod bill_split;
+pub mod payment;
+pub mod adjustment;
+pub mod report;
+pub mod menu;
+pub mod modifier;
+pub mod combo;
+pub mod stock;
+pub mod ingredient;
+pub mod schedule;
+pub mod pricing;
+pub mod promotion;
+pub mod member;
\ No newline at end of file
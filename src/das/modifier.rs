@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use salvo::http::StatusError;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::modifier::{CreateModifierGroupData, ModifierGroupDetail, ModifierGroupWithModifiers};
+use crate::entities::dish::Status;
+use crate::entities::modifier_group_link::Target;
+use crate::entities::order_item::ItemModifier;
+use crate::entities::prelude::{CategoryDishMaps, Dish, Modifier, ModifierGroup, ModifierGroupLink, ModifierGroupLinks, ModifierGroups, Modifiers};
+use crate::entities::{category_dish_map, modifier, modifier_group, modifier_group_link};
+use crate::error::AppResult;
+
+pub struct ModifierCurd;
+impl ModifierCurd {
+    /// 创建选项分组，同时创建分组中的选项并挂到菜品和分类上
+    pub async fn insert(data: CreateModifierGroupData) -> AppResult<String> {
+        if data.max_select < data.min_select {
+            return Err(StatusError::bad_request().brief("max_select不能小于min_select").into());
+        }
+        if data.min_select as usize > data.modifiers.len() {
+            return Err(StatusError::bad_request().brief("min_select不能超过选项的数量").into());
+        }
+        let mut names = HashSet::new();
+        for modifier in &data.modifiers {
+            if modifier.name.trim().is_empty() || !names.insert(modifier.name.trim()) {
+                return Err(StatusError::bad_request()
+                    .brief(format!("选项名称\"{}\"为空或重复", modifier.name))
+                    .into());
+            }
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let index = ModifierGroups::find()
+            .order_by_desc(modifier_group::Column::Index)
+            .one(&txn)
+            .await?
+            .map(|group| group.index + 1)
+            .unwrap_or(0);
+        let group = ModifierGroup {
+            id: Ulid::new().to_string(),
+            index,
+            name: data.name,
+            required: data.required,
+            min_select: data.min_select,
+            max_select: data.max_select,
+        };
+        ModifierGroups::insert(group.clone().into_active_model()).exec(&txn).await?;
+        for (index, modifier) in data.modifiers.into_iter().enumerate() {
+            let modifier = Modifier {
+                id: Ulid::new().to_string(),
+                group_id: group.id.clone(),
+                index: index as i32,
+                name: modifier.name.trim().to_string(),
+                price_delta: modifier.price_delta,
+                status: Status::Normal,
+            };
+            Modifiers::insert(modifier.into_active_model()).exec(&txn).await?;
+        }
+        let links = data
+            .dish_ids
+            .into_iter()
+            .map(|id| (Target::Dish, id))
+            .chain(data.category_ids.into_iter().map(|id| (Target::Category, id)));
+        for (target, target_id) in links {
+            let link = ModifierGroupLink { group_id: group.id.clone(), target, target_id };
+            ModifierGroupLinks::insert(link.into_active_model()).exec(&txn).await?;
+        }
+        txn.commit().await?;
+        Ok(group.id)
+    }
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        ModifierGroupLinks::delete_many()
+            .filter(modifier_group_link::Column::GroupId.eq(id.clone()))
+            .exec(&txn)
+            .await?;
+        Modifiers::delete_many()
+            .filter(modifier::Column::GroupId.eq(id.clone()))
+            .exec(&txn)
+            .await?;
+        ModifierGroups::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+    pub async fn query_all() -> AppResult<Vec<ModifierGroupDetail>> {
+        let db = get_db_coon();
+        let groups = ModifierGroups::find().order_by_asc(modifier_group::Column::Index).all(db).await?;
+        let mut links: HashMap<String, Vec<ModifierGroupLink>> = HashMap::new();
+        for link in ModifierGroupLinks::find().all(db).await? {
+            links.entry(link.group_id.clone()).or_default().push(link);
+        }
+        Ok(Self::with_modifiers(db, groups)
+            .await?
+            .into_iter()
+            .map(|group| ModifierGroupDetail { links: links.remove(&group.group.id).unwrap_or_default(), group })
+            .collect())
+    }
+    /// 查询每个菜品可以选择的分组，包括挂在菜品上的和挂在菜品所属分类上的
+    pub async fn query_for_dishes<C: ConnectionTrait>(db: &C, dish_ids: Vec<String>) -> AppResult<HashMap<String, Vec<ModifierGroupWithModifiers>>> {
+        let mut dish_groups: HashMap<String, HashSet<String>> = HashMap::new();
+        let dish_links = ModifierGroupLinks::find()
+            .filter(modifier_group_link::Column::Target.eq(Target::Dish))
+            .filter(modifier_group_link::Column::TargetId.is_in(dish_ids.clone()))
+            .all(db)
+            .await?;
+        for link in dish_links {
+            dish_groups.entry(link.target_id).or_default().insert(link.group_id);
+        }
+        let maps = CategoryDishMaps::find()
+            .filter(category_dish_map::Column::DishId.is_in(dish_ids))
+            .all(db)
+            .await?;
+        let category_links = ModifierGroupLinks::find()
+            .filter(modifier_group_link::Column::Target.eq(Target::Category))
+            .filter(modifier_group_link::Column::TargetId.is_in(maps.iter().map(|map| map.category_id.clone())))
+            .all(db)
+            .await?;
+        for map in &maps {
+            for link in category_links.iter().filter(|link| link.target_id == map.category_id) {
+                dish_groups.entry(map.dish_id.clone()).or_default().insert(link.group_id.clone());
+            }
+        }
+        let group_ids: HashSet<&String> = dish_groups.values().flatten().collect();
+        let groups = ModifierGroups::find()
+            .filter(modifier_group::Column::Id.is_in(group_ids.into_iter().cloned()))
+            .order_by_asc(modifier_group::Column::Index)
+            .all(db)
+            .await?;
+        let groups = Self::with_modifiers(db, groups).await?;
+        Ok(dish_groups
+            .into_iter()
+            .map(|(dish_id, ids)| {
+                let groups = groups.iter().filter(|group| ids.contains(&group.group.id)).cloned().collect();
+                (dish_id, groups)
+            })
+            .collect())
+    }
+    /// 校验下单时选择的选项并复制下来
+    /// 选项必须属于菜品可选的分组且未下架，每个分组选择的数量要满足分组的规则
+    pub async fn resolve<C: ConnectionTrait>(db: &C, dish: &Dish, modifier_ids: &[String]) -> AppResult<Vec<ItemModifier>> {
+        let groups = Self::query_for_dishes(db, vec![dish.id.clone()]).await?.remove(&dish.id).unwrap_or_default();
+        let bad_request = |reason: String| StatusError::bad_request().brief(format!("{}: {}", dish.name, reason));
+        let mut selected = HashSet::new();
+        for id in modifier_ids {
+            if !selected.insert(id) {
+                return Err(bad_request("选项重复".to_string()).into());
+            }
+            let modifier = groups.iter().flat_map(|group| group.modifiers.iter()).find(|modifier| &modifier.id == id);
+            match modifier {
+                None => return Err(bad_request(format!("不能选择id为{}的选项", id)).into()),
+                Some(modifier) if modifier.status != Status::Normal => {
+                    return Err(bad_request(format!("{}已下架", modifier.name)).into());
+                }
+                Some(_) => {}
+            }
+        }
+        let mut modifiers = Vec::new();
+        for group in &groups {
+            let chosen: Vec<&Modifier> = group.modifiers.iter().filter(|modifier| selected.contains(&modifier.id)).collect();
+            group.group.check_count(chosen.len() as i32).map_err(bad_request)?;
+            modifiers.extend(chosen.into_iter().map(|modifier| ItemModifier {
+                modifier_id: modifier.id.clone(),
+                group_name: group.group.name.clone(),
+                name: modifier.name.clone(),
+                price_delta: modifier.price_delta,
+            }));
+        }
+        Ok(modifiers)
+    }
+    async fn with_modifiers<C: ConnectionTrait>(db: &C, groups: Vec<ModifierGroup>) -> AppResult<Vec<ModifierGroupWithModifiers>> {
+        let mut modifiers: HashMap<String, Vec<Modifier>> = HashMap::new();
+        for modifier in Modifiers::find()
+            .filter(modifier::Column::GroupId.is_in(groups.iter().map(|group| group.id.clone())))
+            .order_by_asc(modifier::Column::Index)
+            .all(db)
+            .await?
+        {
+            modifiers.entry(modifier.group_id.clone()).or_default().push(modifier);
+        }
+        Ok(groups
+            .into_iter()
+            .map(|group| ModifierGroupWithModifiers { modifiers: modifiers.remove(&group.id).unwrap_or_default(), group })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::init_db_coon;
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::modifier::ModifierCurd;
+    use crate::das::order::OrderCurd;
+    use crate::dto::modifier::{CreateModifierData, CreateModifierGroupData};
+    use crate::dto::order::OrderItemData;
+
+    #[tokio::test]
+    async fn test_order_with_modifiers() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_modifier_dish".to_string(), Money::from_fen(1000), "test".to_string(), None).await.unwrap();
+        let category_id = CategoryCurd::insert("test_modifier_category".to_string()).await.unwrap();
+        CategoryDishMapCurd::insert(category_id.clone(), dish_id.clone()).await.unwrap();
+        let modifier = |name: &str, price_delta| CreateModifierData { name: name.to_string(), price_delta };
+        ModifierCurd::insert(CreateModifierGroupData {
+            name: "辣度".to_string(),
+            required: true,
+            min_select: 1,
+            max_select: 1,
+            modifiers: vec![modifier("微辣", Money::from_fen(0)), modifier("特辣", Money::from_fen(0))],
+            dish_ids: vec![dish_id.clone()],
+            category_ids: vec![],
+        }).await.unwrap();
+        ModifierCurd::insert(CreateModifierGroupData {
+            name: "加料".to_string(),
+            required: false,
+            min_select: 0,
+            max_select: 2,
+            modifiers: vec![modifier("加蛋", Money::from_fen(200)), modifier("加肉", Money::from_fen(500))],
+            dish_ids: vec![],
+            category_ids: vec![category_id],
+        }).await.unwrap();
+        let groups = ModifierCurd::query_for_dishes(crate::config::db::get_db_coon(), vec![dish_id.clone()]).await.unwrap();
+        let groups = &groups[&dish_id];
+        assert_eq!(groups.len(), 2);
+        let spicy = groups[0].modifiers[0].id.clone();
+        let egg = groups[1].modifiers[0].id.clone();
+        let item = |modifier_ids: Vec<String>| vec![OrderItemData { dish_id: dish_id.clone(), modifier_ids, quantity: 2, ..Default::default() }];
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), item(vec![egg.clone()])).await.is_err());
+        let (_, total) = OrderCurd::insert("test".to_string(), None, Default::default(), item(vec![spicy, egg])).await.unwrap();
+        assert_eq!(total, Money::from_fen(2400));
+    }
+}
@@ -0,0 +1,368 @@
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::combo::ComboCurd;
+use crate::das::dish::DishCurd;
+use crate::das::modifier::ModifierCurd;
+use crate::das::member::MemberCurd;
+use crate::das::pricing::PricingCurd;
+use crate::das::promotion::PromotionCurd;
+use crate::das::report::ReportCurd;
+use crate::das::schedule::Availability;
+use crate::das::stock::StockCurd;
+use crate::das::table_session::TableSessionCurd;
+use crate::dto::order::{OrderItemData, OrderOptions, OrderWithItems};
+use crate::entities::{order, order_item};
+use crate::entities::order::{AppliedCharges, AppliedDiscounts, Channel};
+use crate::entities::prelude::{Order, OrderItem, OrderItems, OrderStatusHistories, OrderStatusHistory, Orders};
+use crate::error::AppResult;
+use crate::events::{self, OrderEvent};
+use crate::entities::order_item::ItemModifiers;
+use crate::entities::points_entry::Reason;
+use crate::utils::{business_date_of, format_date, get_now_time};
+use crate::utils::money::Money;
+
+pub struct OrderCurd;
+impl OrderCurd {
+    /// 下单, 返回订单id和总价
+    /// 每个菜品(和规格)都必须存在且未下架，选项要满足分组的规则
+    /// 菜名、规格名、选项和单价在此时复制到订单项上，单价包含选项的加价
+    /// 套餐按套餐价计入一行，选择的内容展开成单价为0的订单项交给后厨
+    /// 指定了桌台会话时，会话必须仍在进行中，管理库存的菜品会扣减库存
+    /// 按订单渠道适用的规则计算服务费和税，没有指定渠道时按有没有桌台会话区分堂食和外带
+    /// 自动参加可以参加的促销活动，填写了优惠券时优惠券必须可用，否则下单失败
+    /// 指定了会员时可以用积分抵扣，抵扣的积分在下单时扣除
+    pub async fn insert(
+        uid: String,
+        table_session_id: Option<String>,
+        options: OrderOptions,
+        items: Vec<OrderItemData>,
+    ) -> AppResult<(String, Money)> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let (order, order_items) = Self::insert_in(&txn, uid, table_session_id, options, items).await?;
+        txn.commit().await?;
+        let result = (order.id.clone(), order.total);
+        Self::publish_created(order, order_items);
+        Ok(result)
+    }
+    /// 在调用方的事务中下单，提交事务后需要调用[Self::publish_created]发布事件
+    pub async fn insert_in<C: ConnectionTrait>(
+        db: &C,
+        uid: String,
+        table_session_id: Option<String>,
+        options: OrderOptions,
+        items: Vec<OrderItemData>,
+    ) -> AppResult<(Order, Vec<OrderItem>)> {
+        let OrderOptions { channel, coupon_code, member_id, redeem_points } = options;
+        let channel = channel.unwrap_or_else(|| Channel::default_for(table_session_id.as_ref()));
+        if let Some(member_id) = &member_id {
+            MemberCurd::query_by_id(db, member_id.clone()).await?;
+        } else if redeem_points > 0 {
+            return Err(StatusError::bad_request().brief("只有会员可以使用积分抵扣").into());
+        }
+        if let Some(session_id) = &table_session_id {
+            TableSessionCurd::query_open_by_id(db, session_id.clone()).await?;
+        }
+        let created_at = get_now_time();
+        ReportCurd::ensure_open(db, &created_at).await?;
+        let order_id = Ulid::new().to_string();
+        let mut lines = Vec::with_capacity(items.len());
+        let mut order_items = Vec::with_capacity(items.len());
+        let availability = Availability::load(db).await?;
+        for item in items {
+            let dish = DishCurd::query_orderable(db, &availability, item.dish_id).await?;
+            let variant = DishCurd::query_orderable_variant(db, &dish, item.variant_id).await?;
+            let modifiers = ModifierCurd::resolve(db, &dish, &item.modifier_ids).await?;
+            let components = ComboCurd::resolve(db, &availability, &dish, &item.components).await?;
+            let base = variant.as_ref().map(|variant| variant.price).unwrap_or(dish.price);
+            let price = modifiers.iter().map(|modifier| modifier.price_delta).sum::<Money>()
+                + components.iter().map(|component| component.extra()).sum()
+                + base;
+            let price = price.max(Money::ZERO);
+            lines.push((dish.id.clone(), price, item.quantity));
+            let id = Ulid::new().to_string();
+            let combo = !components.is_empty();
+            order_items.push(OrderItem {
+                id: id.clone(),
+                order_id: order_id.clone(),
+                dish_id: dish.id,
+                dish_name: dish.name,
+                variant_id: variant.as_ref().map(|variant| variant.id.clone()),
+                variant_name: variant.map(|variant| variant.name),
+                modifiers: ItemModifiers(modifiers),
+                price,
+                quantity: item.quantity,
+                voided_quantity: 0,
+                station: if combo { None } else { dish.station },
+                status: order_item::Status::Pending,
+                combo,
+                combo_item_id: None,
+            });
+            order_items.extend(components.into_iter().map(|component| OrderItem {
+                id: Ulid::new().to_string(),
+                order_id: order_id.clone(),
+                dish_id: component.dish_id,
+                dish_name: component.dish_name,
+                variant_id: component.variant_id,
+                variant_name: component.variant_name,
+                modifiers: ItemModifiers(component.modifiers),
+                price: Money::ZERO,
+                quantity: item.quantity,
+                voided_quantity: 0,
+                station: component.station,
+                status: order_item::Status::Pending,
+                combo: false,
+                combo_item_id: Some(id.clone()),
+            }));
+        }
+        let today = format_date(business_date_of(&created_at)?);
+        let (breakdown, skipped) = PricingCurd::quote(db, channel, lines, coupon_code.as_deref(), redeem_points, &today).await?;
+        if coupon_code.is_some() && !breakdown.discounts.iter().any(|discount| discount.coupon_id.is_some()) {
+            let reason = skipped
+                .into_iter()
+                .find(|skipped| skipped.coupon_code.is_some())
+                .map(|skipped| format!("{}: {}", skipped.name, skipped.reason))
+                .unwrap_or_else(|| "优惠券不可用".to_string());
+            return Err(StatusError::bad_request().brief(reason).into());
+        }
+        PromotionCurd::consume(db, &breakdown.discounts).await?;
+        let points_redeemed = breakdown.points_discount.fen();
+        if let Some(member_id) = &member_id {
+            MemberCurd::change(db, member_id, -points_redeemed, Reason::Redeem, Some(order_id.clone())).await?;
+        }
+        let order = Order {
+            id: order_id.clone(),
+            uid: uid.clone(),
+            table_session_id,
+            channel,
+            subtotal: breakdown.subtotal,
+            discount: breakdown.discount,
+            discounts: AppliedDiscounts(breakdown.discounts),
+            member_id,
+            points_redeemed,
+            points_earned: 0,
+            service_charge: breakdown.service_charge,
+            tax: breakdown.tax,
+            charges: AppliedCharges(breakdown.charges),
+            total: breakdown.total,
+            refunded: Money::ZERO,
+            status: order::Status::Pending,
+            created_at,
+        };
+        Orders::insert(order.clone().into_active_model()).exec(db).await?;
+        OrderItems::insert_many(order_items.iter().cloned().map(IntoActiveModel::into_active_model))
+            .exec(db)
+            .await?;
+        StockCurd::consume(db, &order_items, &order_id, &uid).await?;
+        Self::insert_history(db, &order_id, None, order::Status::Pending, uid).await?;
+        Ok((order, order_items))
+    }
+    pub fn publish_created(order: Order, items: Vec<OrderItem>) {
+        events::publish(OrderEvent::OrderCreated(order));
+        for item in items {
+            events::publish(OrderEvent::ItemCreated(item));
+        }
+    }
+    pub async fn query_by_id(id: String) -> AppResult<Option<Order>> {
+        let db = get_db_coon();
+        Ok(Orders::find_by_id(id).one(db).await?)
+    }
+    pub async fn query_by_session(table_session_id: String) -> AppResult<Vec<Order>> {
+        let db = get_db_coon();
+        Ok(Orders::find()
+            .filter(order::Column::TableSessionId.eq(table_session_id))
+            .order_by_asc(order::Column::Id)
+            .all(db)
+            .await?)
+    }
+    /// 查询订单和订单下的所有菜品
+    pub async fn query_with_items(orders: Vec<Order>) -> AppResult<Vec<OrderWithItems>> {
+        let db = get_db_coon();
+        let mut items = OrderItems::find()
+            .filter(order_item::Column::OrderId.is_in(orders.iter().map(|order| order.id.clone())))
+            .order_by_asc(order_item::Column::Id)
+            .all(db)
+            .await?;
+        Ok(orders
+            .into_iter()
+            .map(|order| {
+                let (own, rest) = items.drain(..).partition(|item| item.order_id == order.id);
+                items = rest;
+                OrderWithItems { order, items: own }
+            })
+            .collect())
+    }
+    /// 变更订单状态，状态流转规则见[order::Status::transition]，每次变更都会记录到历史表
+    /// 已支付只能由结账时到账的金额付清订单后设置，见[crate::das::payment::PaymentCurd::settle]
+    pub async fn transition(id: String, next: order::Status, uid: String) -> AppResult<Order> {
+        if next == order::Status::Paid {
+            return Err(StatusError::bad_request().brief("订单付清后自动变为已支付，请使用结账接口").into());
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let (order, cancelled) = Self::transition_in(&txn, id, next, uid).await?;
+        txn.commit().await?;
+        Self::publish_transition(order.clone(), cancelled);
+        Ok(order)
+    }
+    /// 在调用方的事务中变更订单状态，提交事务后需要调用[Self::publish_transition]发布事件
+    pub async fn transition_in<C: ConnectionTrait>(
+        db: &C,
+        id: String,
+        next: order::Status,
+        uid: String,
+    ) -> AppResult<(Order, Vec<OrderItem>)> {
+        let Some(order) = Orders::find_by_id(id.clone()).one(db).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单不存在", id))
+                .into());
+        };
+        let prev = order.status;
+        let next = prev.transition(next)?;
+        let points_earned = if next == order::Status::Paid { MemberCurd::earn(db, &order).await? } else { order.points_earned };
+        let mut order = order.into_active_model();
+        order.status = Set(next);
+        order.points_earned = Set(points_earned);
+        let order = order.update(db).await?;
+        Self::insert_history(db, &id, Some(prev), next, uid.clone()).await?;
+        //订单取消时，后厨还没做完的菜也一起取消，并退回库存、活动名额和抵扣的积分
+        let mut cancelled = Vec::new();
+        if next == order::Status::Cancelled {
+            PromotionCurd::release(db, &order.discounts.0).await?;
+            if let Some(member_id) = &order.member_id {
+                MemberCurd::change(db, member_id, order.points_redeemed, Reason::Refund, Some(id.clone())).await?;
+            }
+            let items = OrderItems::find()
+                .filter(order_item::Column::OrderId.eq(id.clone()))
+                .all(db)
+                .await?;
+            for item in items.into_iter().filter(|item| item.status.is_open()) {
+                StockCurd::restore(db, &item, item.quantity - item.voided_quantity, &uid).await?;
+                let mut item = item.into_active_model();
+                item.status = Set(order_item::Status::Cancelled);
+                cancelled.push(item.update(db).await?);
+            }
+        }
+        Ok((order, cancelled))
+    }
+    pub fn publish_transition(order: Order, cancelled: Vec<OrderItem>) {
+        events::publish(OrderEvent::OrderStatusChanged(order));
+        for item in cancelled {
+            events::publish(OrderEvent::ItemCancelled(item));
+        }
+    }
+    /// 变更单个菜品的制作状态，后厨开始制作或者上菜时调用
+    /// 退菜会影响订单金额，需要走退菜接口留下记录
+    /// 套餐行的状态跟随套餐内容变化，不能直接变更
+    pub async fn transition_item(id: String, next: order_item::Status) -> AppResult<OrderItem> {
+        if next == order_item::Status::Cancelled {
+            return Err(StatusError::bad_request().brief("退菜请使用退菜接口").into());
+        }
+        let db = get_db_coon();
+        let Some(item) = OrderItems::find_by_id(id.clone()).one(db).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单项不存在", id))
+                .into());
+        };
+        if item.combo {
+            return Err(StatusError::bad_request().brief("套餐请分别变更其中菜品的状态").into());
+        }
+        let next = item.status.transition(next)?;
+        let mut item = item.into_active_model();
+        item.status = Set(next);
+        let item = item.update(db).await?;
+        events::publish(OrderEvent::ItemStatusChanged(item.clone()));
+        if let Some(combo_item_id) = &item.combo_item_id
+            && let Some(combo_item) = Self::sync_combo_item(db, combo_item_id.clone()).await?
+        {
+            events::publish(OrderEvent::ItemStatusChanged(combo_item));
+        }
+        Ok(item)
+    }
+    /// 套餐内容都上齐后套餐行变为已上菜，有内容开始制作后变为制作中，状态有变化时返回套餐行
+    async fn sync_combo_item<C: ConnectionTrait>(db: &C, combo_item_id: String) -> AppResult<Option<OrderItem>> {
+        let Some(combo_item) = OrderItems::find_by_id(combo_item_id.clone()).one(db).await? else {
+            return Ok(None);
+        };
+        let statuses: Vec<order_item::Status> = OrderItems::find()
+            .filter(order_item::Column::ComboItemId.eq(combo_item_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|component| component.status)
+            .filter(|status| *status != order_item::Status::Cancelled)
+            .collect();
+        let next = if statuses.iter().all(|status| *status == order_item::Status::Served) {
+            order_item::Status::Served
+        } else if statuses.iter().any(|status| *status != order_item::Status::Pending) {
+            order_item::Status::Cooking
+        } else {
+            order_item::Status::Pending
+        };
+        if statuses.is_empty() || !combo_item.status.is_open() || combo_item.status == next {
+            return Ok(None);
+        }
+        let mut combo_item = combo_item.into_active_model();
+        combo_item.status = Set(next);
+        Ok(Some(combo_item.update(db).await?))
+    }
+    /// 查询后厨还需要处理的菜品，可以按档口过滤，套餐只列出其中的菜品
+    pub async fn query_open_items(station: Option<String>) -> AppResult<Vec<OrderItem>> {
+        let db = get_db_coon();
+        let mut select = OrderItems::find()
+            .filter(order_item::Column::Status.is_in([order_item::Status::Pending, order_item::Status::Cooking]))
+            .filter(order_item::Column::Combo.eq(false));
+        if let Some(station) = station {
+            select = select.filter(order_item::Column::Station.eq(station));
+        }
+        Ok(select.order_by_asc(order_item::Column::Id).all(db).await?)
+    }
+    async fn insert_history<C: ConnectionTrait>(
+        db: &C,
+        order_id: &str,
+        from_status: Option<order::Status>,
+        to_status: order::Status,
+        uid: String,
+    ) -> AppResult<()> {
+        let history = OrderStatusHistory {
+            id: Ulid::new().to_string(),
+            order_id: order_id.to_string(),
+            from_status,
+            to_status,
+            uid,
+            created_at: get_now_time(),
+        };
+        OrderStatusHistories::insert(history.into_active_model()).exec(db).await?;
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::init_db_coon;
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::dto::order::OrderItemData;
+    use crate::entities::order::Status;
+
+    #[tokio::test]
+    async fn test_create_order() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_order_dish".to_string(), Money::from_fen(1250), "test".to_string(), Some("热菜".to_string())).await.unwrap();
+        let items = vec![OrderItemData { dish_id, quantity: 2, ..Default::default() }];
+        let (id, total) = OrderCurd::insert("test".to_string(), None, Default::default(), items).await.unwrap();
+        assert_eq!(total, Money::from_fen(2500));
+        let open = OrderCurd::query_open_items(Some("热菜".to_string())).await.unwrap();
+        assert!(open.iter().any(|item| item.order_id == id));
+        let order = OrderCurd::transition(id.clone(), Status::Accepted, "test".to_string()).await.unwrap();
+        assert_eq!(order.status, Status::Accepted);
+        assert!(OrderCurd::transition(id.clone(), Status::Paid, "test".to_string()).await.is_err());
+        OrderCurd::transition(id.clone(), Status::Cancelled, "test".to_string()).await.unwrap();
+        let open = OrderCurd::query_open_items(None).await.unwrap();
+        assert!(open.iter().all(|item| item.order_id != id));
+        let missing = vec![OrderItemData { dish_id: "missing".to_string(), quantity: 1, ..Default::default() }];
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), missing).await.is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::bill_split::BillSplitCurd;
+use crate::das::order::OrderCurd;
+use crate::das::report::ReportCurd;
+use crate::dto::payment::TenderData;
+use crate::entities::payment::{Column, Status};
+use crate::entities::prelude::{BillSplits, Order, Orders, Payment, Payments};
+use crate::entities::{bill_split, order};
+use crate::error::{AppError, AppResult};
+use crate::payment::{provider_for, ChargeOutcome};
+use crate::utils::get_now_time;
+use crate::utils::money::Money;
+
+pub struct PaymentCurd;
+impl PaymentCurd {
+    /// 结账，每种支付方式生成一条支付流水并交给对应的渠道处理
+    /// 已到账和等待回调的金额加上本次金额不能超过订单金额(或者分单金额)
+    /// 先在事务中写入等待中的支付流水再检查待付金额，同时结账时后一个事务要等前一个提交后才能写入，不会超付
+    pub async fn pay(order_id: String, tenders: Vec<TenderData>, uid: String) -> AppResult<Vec<Payment>> {
+        if tenders.iter().any(|tender| tender.amount <= Money::ZERO) {
+            return Err(StatusError::bad_request().brief("支付金额必须大于0").into());
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let mut pending = Vec::with_capacity(tenders.len());
+        for tender in tenders {
+            let payment = Payment {
+                id: Ulid::new().to_string(),
+                order_id: order_id.clone(),
+                bill_split_id: tender.bill_split_id,
+                method: tender.method,
+                provider: provider_for(tender.method).name().to_string(),
+                provider_ref: None,
+                amount: tender.amount,
+                status: Status::Pending,
+                uid: uid.clone(),
+                created_at: get_now_time(),
+                captured_at: None,
+            };
+            Payments::insert(payment.clone().into_active_model()).exec(&txn).await?;
+            pending.push(payment);
+        }
+        let Some(order) = Orders::find_by_id(order_id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单不存在", order_id))
+                .into());
+        };
+        if order.status != order::Status::Served {
+            return Err(AppError::conflict(format!("{:?}状态的订单不能结账", order.status)));
+        }
+        ReportCurd::ensure_open(&txn, &order.created_at).await?;
+        let existing: Vec<Payment> = Self::query_by_order(&txn, order_id.clone())
+            .await?
+            .into_iter()
+            .filter(|payment| payment.status != Status::Failed)
+            .collect();
+        let split_ids: HashSet<&String> = pending.iter().filter_map(|payment| payment.bill_split_id.as_ref()).collect();
+        for split_id in split_ids {
+            let split = BillSplits::find_by_id(split_id.clone()).one(&txn).await?
+                .filter(|split| split.order_id == order_id)
+                .ok_or_else(|| StatusError::bad_request().brief(format!("订单中没有id为{}的分单", split_id)))?;
+            let paid: Money = existing
+                .iter()
+                .filter(|payment| payment.bill_split_id.as_ref() == Some(split_id))
+                .map(|payment| payment.amount)
+                .sum();
+            if paid > split.amount {
+                return Err(StatusError::bad_request().brief("支付金额超过了分单的待付金额").into());
+            }
+        }
+        if existing.iter().map(|payment| payment.amount).sum::<Money>() > order.total {
+            return Err(StatusError::bad_request().brief("支付金额超过了订单的待付金额").into());
+        }
+        txn.commit().await?;
+
+        //渠道处理可能要等待，不放在事务里，前面的渠道出错后后面的支付不再发起
+        let mut outcomes = Vec::with_capacity(pending.len());
+        let mut error = None;
+        for payment in pending {
+            let outcome = if error.is_some() {
+                ChargeOutcome::Failed { reason: "前面的支付出错，没有发起".to_string() }
+            } else {
+                match provider_for(payment.method).charge(&payment).await {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        //出错的支付保持等待状态，由渠道回调或者人工处理
+                        error = Some(e);
+                        continue;
+                    }
+                }
+            };
+            outcomes.push((payment, outcome));
+        }
+        let txn = db.begin().await?;
+        let mut payments = Vec::with_capacity(outcomes.len());
+        for (payment, outcome) in outcomes {
+            let mut payment = payment.into_active_model();
+            match outcome {
+                ChargeOutcome::Captured { provider_ref } => {
+                    payment.status = Set(Status::Captured);
+                    payment.provider_ref = Set(provider_ref);
+                    payment.captured_at = Set(Some(get_now_time()));
+                }
+                ChargeOutcome::Pending { provider_ref } => {
+                    payment.provider_ref = Set(Some(provider_ref));
+                }
+                ChargeOutcome::Failed { reason } => {
+                    log::warn!("payment failed: {}", reason);
+                    payment.status = Set(Status::Failed);
+                }
+            }
+            payments.push(payment.update(&txn).await?);
+        }
+        let paid = Self::settle(&txn, order_id, uid).await?;
+        txn.commit().await?;
+        if let Some(order) = paid {
+            OrderCurd::publish_transition(order, vec![]);
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(payments),
+        }
+    }
+    /// 渠道异步回调，更新等待中的支付流水并重新结算订单
+    /// 只更新还在等待中的流水，重复的回调不会重复结算
+    pub async fn complete(id: String, success: bool) -> AppResult<Payment> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let (status, captured_at) = if success { (Status::Captured, Some(get_now_time())) } else { (Status::Failed, None) };
+        let updated = Payments::update_many()
+            .col_expr(Column::Status, Expr::value(status))
+            .col_expr(Column::CapturedAt, Expr::value(captured_at))
+            .filter(Column::Id.eq(id.clone()))
+            .filter(Column::Status.eq(Status::Pending))
+            .exec(&txn)
+            .await?;
+        let Some(payment) = Payments::find_by_id(id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的支付流水不存在", id))
+                .into());
+        };
+        if updated.rows_affected == 0 {
+            return Err(AppError::conflict("支付流水已经处理过了"));
+        }
+        let paid = Self::settle(&txn, payment.order_id.clone(), payment.uid.clone()).await?;
+        txn.commit().await?;
+        if let Some(order) = paid {
+            OrderCurd::publish_transition(order, vec![]);
+        }
+        Ok(payment)
+    }
+    /// 在调用方的事务中根据已到账的金额结算：分单付清后标记为已支付，订单付清后变为已支付
+    /// 订单变为已支付时返回订单，提交事务后需要调用[OrderCurd::publish_transition]发布事件
+    pub async fn settle<C: ConnectionTrait>(db: &C, order_id: String, uid: String) -> AppResult<Option<Order>> {
+        let captured: Vec<Payment> = Self::query_by_order(db, order_id.clone())
+            .await?
+            .into_iter()
+            .filter(|payment| payment.status == Status::Captured)
+            .collect();
+        let splits = BillSplits::find()
+            .filter(bill_split::Column::OrderId.eq(order_id.clone()))
+            .all(db)
+            .await?;
+        for split in splits {
+            let paid: Money = captured
+                .iter()
+                .filter(|payment| payment.bill_split_id.as_ref() == Some(&split.id))
+                .map(|payment| payment.amount)
+                .sum();
+            if split.status == bill_split::Status::Unpaid && paid >= split.amount {
+                BillSplitCurd::mark_paid(db, split.id).await?;
+            }
+        }
+        let paid: Money = captured.iter().map(|payment| payment.amount).sum();
+        if let Some(order) = Orders::find_by_id(order_id).one(db).await?
+            && order.status == order::Status::Served
+            && paid >= order.total
+        {
+            let (order, _) = OrderCurd::transition_in(db, order.id, order::Status::Paid, uid).await?;
+            return Ok(Some(order));
+        }
+        Ok(None)
+    }
+    pub async fn query_by_order<C: ConnectionTrait>(db: &C, order_id: String) -> AppResult<Vec<Payment>> {
+        Ok(Payments::find()
+            .filter(Column::OrderId.eq(order_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::payment::PaymentCurd;
+    use crate::dto::order::OrderItemData;
+    use crate::dto::payment::TenderData;
+    use crate::entities::order::Status;
+    use crate::entities::payment::Method;
+    use crate::utils::money::Money;
+
+    #[tokio::test]
+    async fn test_mixed_payment() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_payment_dish".to_string(), Money::from_fen(3000), "test".to_string(), None).await.unwrap();
+        let items = vec![OrderItemData { dish_id: dish_id.clone(), quantity: 1, ..Default::default() }];
+        let (order_id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), items).await.unwrap();
+        for status in [Status::Accepted, Status::Cooking, Status::Served] {
+            OrderCurd::transition(order_id.clone(), status, "test".to_string()).await.unwrap();
+        }
+        //没有付款不能直接变为已支付
+        assert!(OrderCurd::transition(order_id.clone(), Status::Paid, "test".to_string()).await.is_err());
+        assert_eq!(OrderCurd::query_by_id(order_id.clone()).await.unwrap().unwrap().status, Status::Served);
+        let too_much = vec![TenderData { method: Method::Cash, amount: Money::from_fen(3001), bill_split_id: None }];
+        assert!(PaymentCurd::pay(order_id.clone(), too_much, "test".to_string()).await.is_err());
+        let tenders = vec![
+            TenderData { method: Method::Cash, amount: Money::from_fen(1000), bill_split_id: None },
+            TenderData { method: Method::Card, amount: Money::from_fen(2000), bill_split_id: None },
+        ];
+        PaymentCurd::pay(order_id.clone(), tenders, "test".to_string()).await.unwrap();
+        let order = OrderCurd::query_by_id(order_id).await.unwrap().unwrap();
+        assert_eq!(order.status, Status::Paid);
+        //同时结账时只有一个能成功
+        let items = vec![OrderItemData { dish_id, quantity: 1, ..Default::default() }];
+        let (order_id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), items).await.unwrap();
+        for status in [Status::Accepted, Status::Cooking, Status::Served] {
+            OrderCurd::transition(order_id.clone(), status, "test".to_string()).await.unwrap();
+        }
+        let tenders = || vec![TenderData { method: Method::Cash, amount: Money::from_fen(3000), bill_split_id: None }];
+        let (first, second) = tokio::join!(
+            PaymentCurd::pay(order_id.clone(), tenders(), "test".to_string()),
+            PaymentCurd::pay(order_id.clone(), tenders(), "test".to_string()),
+        );
+        assert!(first.is_ok() != second.is_ok());
+        assert_eq!(PaymentCurd::query_by_order(get_db_coon(), order_id).await.unwrap().len(), 1);
+    }
+}
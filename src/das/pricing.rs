@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::pricing::PricingRuleData;
+use crate::das::promotion::PromotionCurd;
+use crate::entities::order::{AppliedCharge, AppliedDiscount, Channel};
+use crate::entities::pricing_rule::{CategoryIds, Channels, Column, Kind};
+use crate::entities::prelude::{Categories, CategoryDishMaps, Order, PricingRule, PricingRules};
+use crate::entities::{category, category_dish_map};
+use crate::error::{AppError, AppResult};
+use crate::pricing::{self, Breakdown, Line};
+use crate::promotion::{self, Skipped};
+use crate::utils::money::Money;
+
+pub struct PricingCurd;
+impl PricingCurd {
+    /// 插入服务费或税的规则, 返回规则id
+    pub async fn insert(data: PricingRuleData) -> AppResult<String> {
+        Self::check(&data, None).await?;
+        let db = get_db_coon();
+        let uuid = Ulid::new();
+        let rule = PricingRule {
+            id: uuid.to_string(),
+            name: data.name,
+            kind: data.kind,
+            rate: data.rate,
+            inclusive: data.inclusive,
+            channels: Channels(data.channels),
+            exempt_category_ids: CategoryIds(data.exempt_category_ids),
+            enabled: data.enabled,
+        };
+        PricingRules::insert(rule.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    pub async fn update(id: String, data: PricingRuleData) -> AppResult<PricingRule> {
+        Self::check(&data, Some(&id)).await?;
+        let db = get_db_coon();
+        let Some(rule) = PricingRules::find_by_id(id.clone()).one(db).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的规则不存在", id))
+                .into());
+        };
+        let mut rule = rule.into_active_model();
+        rule.name = Set(data.name);
+        rule.kind = Set(data.kind);
+        rule.rate = Set(data.rate);
+        rule.inclusive = Set(data.inclusive);
+        rule.channels = Set(Channels(data.channels));
+        rule.exempt_category_ids = Set(CategoryIds(data.exempt_category_ids));
+        rule.enabled = Set(data.enabled);
+        Ok(rule.update(db).await?)
+    }
+    /// 名称不能重复，服务费不能是价内的，免收的分类必须存在
+    async fn check(data: &PricingRuleData, id: Option<&String>) -> AppResult<()> {
+        if data.kind == Kind::ServiceCharge && data.inclusive {
+            return Err(StatusError::bad_request().brief("服务费不能设置为价内").into());
+        }
+        let db = get_db_coon();
+        let existing = PricingRules::find()
+            .filter(Column::Name.eq(data.name.clone()))
+            .one(db)
+            .await?;
+        if existing.is_some_and(|existing| Some(&existing.id) != id) {
+            return Err(AppError::conflict(format!("规则{}已存在", data.name)));
+        }
+        let found = Categories::find()
+            .filter(category::Column::Id.is_in(data.exempt_category_ids.clone()))
+            .count(db)
+            .await?;
+        if found != data.exempt_category_ids.len() as u64 {
+            return Err(StatusError::bad_request().brief("免收的分类不存在或者重复").into());
+        }
+        Ok(())
+    }
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        PricingRules::delete_by_id(id).exec(db).await?;
+        Ok(())
+    }
+    pub async fn query_all() -> AppResult<Vec<PricingRule>> {
+        let db = get_db_coon();
+        Ok(PricingRules::find().order_by_asc(Column::Id).all(db).await?)
+    }
+    /// 按渠道当前启用的规则和可以参加的促销活动计算金额，lines是菜品id、单价和数量
+    /// coupon_code是顾客填写的优惠券码，redeem_points是会员用来抵扣的积分，today是营业日
+    /// 返回金额明细和没有参加的活动
+    pub async fn quote<C: ConnectionTrait>(
+        db: &C,
+        channel: Channel,
+        lines: Vec<(String, Money, i32)>,
+        coupon_code: Option<&str>,
+        redeem_points: i64,
+        today: &str,
+    ) -> AppResult<(Breakdown, Vec<Skipped>)> {
+        let charges = PricingRules::find()
+            .filter(Column::Enabled.eq(true))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|rule| rule.channels.0.contains(&channel))
+            .map(|rule| AppliedCharge {
+                rule_id: rule.id,
+                name: rule.name,
+                kind: rule.kind,
+                rate: rule.rate,
+                inclusive: rule.inclusive,
+                exempt_category_ids: rule.exempt_category_ids.0,
+                amount: Money::ZERO,
+            })
+            .collect();
+        let (candidates, mut skipped) = PromotionCurd::candidates(db, coupon_code).await?;
+        let lines = Self::lines(db, lines).await?;
+        let mut evaluation = promotion::evaluate(candidates, &lines, today, false);
+        let points_discount = promotion::redeem(&mut evaluation, &lines, redeem_points);
+        skipped.extend(evaluation.skipped);
+        let breakdown = Self::compute(charges, evaluation.discounts, evaluation.line_discounts, lines);
+        Ok((Breakdown { points_discount, ..breakdown }, skipped))
+    }
+    /// 按订单上保存的规则、参加过的活动和抵扣的积分重新计算金额，退菜后调用，抵扣的积分只会变少
+    pub async fn requote<C: ConnectionTrait>(db: &C, order: &Order, lines: Vec<(String, Money, i32)>) -> AppResult<Breakdown> {
+        let candidates = PromotionCurd::granted(db, &order.discounts.0).await?;
+        let lines = Self::lines(db, lines).await?;
+        let mut evaluation = promotion::evaluate(candidates, &lines, "", true);
+        let points_discount = promotion::redeem(&mut evaluation, &lines, order.points_redeemed);
+        let breakdown = Self::compute(order.charges.0.clone(), evaluation.discounts, evaluation.line_discounts, lines);
+        Ok(Breakdown { points_discount, ..breakdown })
+    }
+    /// 查出菜品所属的分类
+    async fn lines<C: ConnectionTrait>(db: &C, lines: Vec<(String, Money, i32)>) -> AppResult<Vec<promotion::Line>> {
+        let mut categories: HashMap<String, Vec<String>> = HashMap::new();
+        let maps = CategoryDishMaps::find()
+            .filter(category_dish_map::Column::DishId.is_in(lines.iter().map(|(dish_id, _, _)| dish_id.clone())))
+            .all(db)
+            .await?;
+        for map in maps {
+            categories.entry(map.dish_id).or_default().push(map.category_id);
+        }
+        Ok(lines
+            .into_iter()
+            .map(|(dish_id, price, quantity)| promotion::Line {
+                category_ids: categories.get(&dish_id).cloned().unwrap_or_default(),
+                dish_id,
+                price,
+                quantity,
+            })
+            .collect())
+    }
+    fn compute(charges: Vec<AppliedCharge>, discounts: Vec<AppliedDiscount>, line_discounts: Vec<Money>, lines: Vec<promotion::Line>) -> Breakdown {
+        let lines: Vec<Line> = lines
+            .into_iter()
+            .zip(line_discounts)
+            .map(|(line, discount)| Line { amount: line.price * line.quantity, discount, category_ids: line.category_ids })
+            .collect();
+        pricing::compute(charges, discounts, &lines)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::init_db_coon;
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::pricing::PricingCurd;
+    use crate::dto::order::{OrderItemData, OrderOptions};
+    use crate::dto::pricing::PricingRuleData;
+    use crate::entities::order::Channel;
+    use crate::entities::pricing_rule::Kind;
+    use crate::utils::money::Money;
+
+    #[tokio::test]
+    async fn test_service_charge() {
+        init_db_coon().await;
+        let drinks = CategoryCurd::insert("test_pricing_drinks".to_string()).await.unwrap();
+        let steak = DishCurd::insert("test_pricing_steak".to_string(), Money::from_fen(10000), "test".to_string(), None).await.unwrap();
+        let wine = DishCurd::insert("test_pricing_wine".to_string(), Money::from_fen(5000), "test".to_string(), None).await.unwrap();
+        CategoryDishMapCurd::insert(drinks.clone(), wine.clone()).await.unwrap();
+        let data = PricingRuleData {
+            name: "test_pricing_service".to_string(),
+            kind: Kind::ServiceCharge,
+            rate: 1000,
+            inclusive: false,
+            channels: vec![Channel::Delivery],
+            exempt_category_ids: vec![drinks],
+            enabled: true,
+        };
+        PricingCurd::insert(data).await.unwrap();
+        let items = vec![
+            OrderItemData { dish_id: steak, quantity: 1, ..Default::default() },
+            OrderItemData { dish_id: wine, quantity: 1, ..Default::default() },
+        ];
+        let (id, total) = OrderCurd::insert("test".to_string(), None, OrderOptions { channel: Some(Channel::Delivery), ..Default::default() }, items).await.unwrap();
+        assert_eq!(total, Money::from_fen(16000));
+        let order = OrderCurd::query_by_id(id).await.unwrap().unwrap();
+        assert_eq!((order.subtotal, order.service_charge), (Money::from_fen(15000), Money::from_fen(1000)));
+    }
+}
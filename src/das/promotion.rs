@@ -0,0 +1,339 @@
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::promotion::{CouponData, PromotionData};
+use crate::entities::order::AppliedDiscount;
+use crate::entities::prelude::{Categories, Coupon, Coupons, Dishes, Promotion, Promotions};
+use crate::entities::promotion::{Column, Kind, Target, TargetIds};
+use crate::entities::{category, coupon, dish};
+use crate::error::{AppError, AppResult};
+use crate::promotion::{Candidate, Skipped};
+use crate::utils::{get_now_time, parse_date};
+
+pub struct PromotionCurd;
+impl PromotionCurd {
+    /// 插入促销活动, 返回活动id
+    pub async fn insert(data: PromotionData) -> AppResult<String> {
+        Self::check(&data, None).await?;
+        let db = get_db_coon();
+        let uuid = Ulid::new();
+        let promotion = Promotion {
+            id: uuid.to_string(),
+            name: data.name,
+            kind: data.kind,
+            target: data.target,
+            target_ids: TargetIds(data.target_ids),
+            rate: data.rate,
+            amount: data.amount,
+            buy_quantity: data.buy_quantity,
+            free_quantity: data.free_quantity,
+            min_spend: data.min_spend,
+            start_date: data.start_date,
+            end_date: data.end_date,
+            usage_limit: data.usage_limit,
+            used_count: 0,
+            coupon_only: data.coupon_only,
+            enabled: data.enabled,
+        };
+        Promotions::insert(promotion.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    /// 修改活动规则，已经使用的次数保留
+    pub async fn update(id: String, data: PromotionData) -> AppResult<Promotion> {
+        Self::check(&data, Some(&id)).await?;
+        let db = get_db_coon();
+        let promotion = Self::query_by_id(db, id).await?;
+        let mut promotion = promotion.into_active_model();
+        promotion.name = Set(data.name);
+        promotion.kind = Set(data.kind);
+        promotion.target = Set(data.target);
+        promotion.target_ids = Set(TargetIds(data.target_ids));
+        promotion.rate = Set(data.rate);
+        promotion.amount = Set(data.amount);
+        promotion.buy_quantity = Set(data.buy_quantity);
+        promotion.free_quantity = Set(data.free_quantity);
+        promotion.min_spend = Set(data.min_spend);
+        promotion.start_date = Set(data.start_date);
+        promotion.end_date = Set(data.end_date);
+        promotion.usage_limit = Set(data.usage_limit);
+        promotion.coupon_only = Set(data.coupon_only);
+        promotion.enabled = Set(data.enabled);
+        Ok(promotion.update(db).await?)
+    }
+    /// 名称不能重复，规则要和活动类型匹配，指定的菜品或分类必须存在，有效期的日期要合法
+    async fn check(data: &PromotionData, id: Option<&String>) -> AppResult<()> {
+        let invalid = match data.kind {
+            Kind::Percent if data.rate == 0 => Some("打折活动的折扣率必须大于0"),
+            Kind::Fixed if data.amount.fen() == 0 => Some("减价活动的金额必须大于0"),
+            Kind::BuyXGetY if data.buy_quantity < 1 || data.free_quantity < 1 => Some("买X送Y活动的购买和赠送数量都必须大于0"),
+            _ => None,
+        };
+        if let Some(invalid) = invalid {
+            return Err(StatusError::bad_request().brief(invalid).into());
+        }
+        if let Some(start_date) = &data.start_date {
+            parse_date(start_date)?;
+        }
+        if let Some(end_date) = &data.end_date {
+            parse_date(end_date)?;
+        }
+        if let (Some(start_date), Some(end_date)) = (&data.start_date, &data.end_date)
+            && start_date > end_date
+        {
+            return Err(StatusError::bad_request().brief("活动开始日期不能晚于结束日期").into());
+        }
+        let db = get_db_coon();
+        let existing = Promotions::find()
+            .filter(Column::Name.eq(data.name.clone()))
+            .one(db)
+            .await?;
+        if existing.is_some_and(|existing| Some(&existing.id) != id) {
+            return Err(AppError::conflict(format!("活动{}已存在", data.name)));
+        }
+        let found = match data.target {
+            Target::Order => return Ok(()),
+            Target::Dish => Dishes::find().filter(dish::Column::Id.is_in(data.target_ids.clone())).count(db).await?,
+            Target::Category => Categories::find().filter(category::Column::Id.is_in(data.target_ids.clone())).count(db).await?,
+        };
+        if data.target_ids.is_empty() || found != data.target_ids.len() as u64 {
+            return Err(StatusError::bad_request().brief("参加活动的菜品或分类为空、不存在或者重复").into());
+        }
+        Ok(())
+    }
+    /// 删除活动和它的优惠券，已经被订单使用过的活动只能停用
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(promotion) = Promotions::find_by_id(id.clone()).one(&txn).await? else {
+            return Ok(());
+        };
+        if promotion.used_count > 0 {
+            return Err(AppError::conflict(format!("活动{}已经被订单使用过，请停用", promotion.name)));
+        }
+        Coupons::delete_many().filter(coupon::Column::PromotionId.eq(id.clone())).exec(&txn).await?;
+        Promotions::delete_by_id(id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+    pub async fn query_all() -> AppResult<Vec<Promotion>> {
+        let db = get_db_coon();
+        Ok(Promotions::find().order_by_asc(Column::Id).all(db).await?)
+    }
+    async fn query_by_id<C: ConnectionTrait>(db: &C, id: String) -> AppResult<Promotion> {
+        Promotions::find_by_id(id.clone())
+            .one(db)
+            .await?
+            .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的活动不存在", id)).into())
+    }
+    /// 插入优惠券, 返回优惠券id，券码不区分大小写
+    pub async fn insert_coupon(data: CouponData) -> AppResult<String> {
+        let code = Self::check_coupon(&data, None).await?;
+        let db = get_db_coon();
+        let uuid = Ulid::new();
+        let coupon = Coupon {
+            id: uuid.to_string(),
+            code,
+            promotion_id: data.promotion_id,
+            usage_limit: data.usage_limit,
+            used_count: 0,
+            enabled: data.enabled,
+            created_at: get_now_time(),
+        };
+        Coupons::insert(coupon.into_active_model()).exec(db).await?;
+        Ok(uuid.to_string())
+    }
+    pub async fn update_coupon(id: String, data: CouponData) -> AppResult<Coupon> {
+        let code = Self::check_coupon(&data, Some(&id)).await?;
+        let db = get_db_coon();
+        let Some(coupon) = Coupons::find_by_id(id.clone()).one(db).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的优惠券不存在", id))
+                .into());
+        };
+        let mut coupon = coupon.into_active_model();
+        coupon.code = Set(code);
+        coupon.promotion_id = Set(data.promotion_id);
+        coupon.usage_limit = Set(data.usage_limit);
+        coupon.enabled = Set(data.enabled);
+        Ok(coupon.update(db).await?)
+    }
+    /// 活动必须存在，券码不能重复，返回统一成大写的券码
+    async fn check_coupon(data: &CouponData, id: Option<&String>) -> AppResult<String> {
+        let db = get_db_coon();
+        Self::query_by_id(db, data.promotion_id.clone()).await?;
+        let code = data.code.trim().to_uppercase();
+        let existing = Coupons::find().filter(coupon::Column::Code.eq(code.clone())).one(db).await?;
+        if existing.is_some_and(|existing| Some(&existing.id) != id) {
+            return Err(AppError::conflict(format!("优惠券{}已存在", code)));
+        }
+        Ok(code)
+    }
+    /// 已经被订单使用过的优惠券只能停用
+    pub async fn delete_coupon(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        if let Some(coupon) = Coupons::find_by_id(id.clone()).one(db).await? {
+            if coupon.used_count > 0 {
+                return Err(AppError::conflict(format!("优惠券{}已经被订单使用过，请停用", coupon.code)));
+            }
+            Coupons::delete_by_id(id).exec(db).await?;
+        }
+        Ok(())
+    }
+    /// 查询优惠券，可以按活动过滤
+    pub async fn query_coupons(promotion_id: Option<String>) -> AppResult<Vec<Coupon>> {
+        let db = get_db_coon();
+        let mut select = Coupons::find();
+        if let Some(promotion_id) = promotion_id {
+            select = select.filter(coupon::Column::PromotionId.eq(promotion_id));
+        }
+        Ok(select.order_by_asc(coupon::Column::Id).all(db).await?)
+    }
+    /// 下单或预览时参与计算的活动：所有启用的自动参加的活动，加上优惠券对应的活动
+    /// 找不到的优惠券作为没有参加的活动返回
+    pub async fn candidates<C: ConnectionTrait>(db: &C, coupon_code: Option<&str>) -> AppResult<(Vec<Candidate>, Vec<Skipped>)> {
+        let mut candidates: Vec<Candidate> = Promotions::find()
+            .filter(Column::Enabled.eq(true))
+            .filter(Column::CouponOnly.eq(false))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|promotion| Candidate { promotion, coupon: None })
+            .collect();
+        let mut skipped = Vec::new();
+        let Some(code) = coupon_code.map(|code| code.trim().to_uppercase()) else {
+            return Ok((candidates, skipped));
+        };
+        let coupon = Coupons::find().filter(coupon::Column::Code.eq(code.clone())).one(db).await?;
+        let promotion = match &coupon {
+            Some(coupon) => Promotions::find_by_id(coupon.promotion_id.clone()).one(db).await?,
+            None => None,
+        };
+        match (coupon, promotion) {
+            (Some(_), Some(promotion)) if candidates.iter().any(|candidate| candidate.promotion.id == promotion.id) => {
+                skipped.push(Skipped { name: promotion.name, coupon_code: Some(code), reason: "活动不需要优惠券，会自动参加".to_string() });
+            }
+            (Some(coupon), Some(promotion)) => candidates.push(Candidate { promotion, coupon: Some(coupon) }),
+            _ => skipped.push(Skipped { name: code.clone(), coupon_code: Some(code), reason: "优惠券不存在".to_string() }),
+        }
+        Ok((candidates, skipped))
+    }
+    /// 订单已经参加的活动，退菜后按这些活动重新计算优惠
+    pub async fn granted<C: ConnectionTrait>(db: &C, discounts: &[AppliedDiscount]) -> AppResult<Vec<Candidate>> {
+        let mut candidates = Vec::with_capacity(discounts.len());
+        for discount in discounts {
+            let Some(promotion) = Promotions::find_by_id(discount.promotion_id.clone()).one(db).await? else {
+                continue;
+            };
+            let coupon = match &discount.coupon_id {
+                Some(coupon_id) => Coupons::find_by_id(coupon_id.clone()).one(db).await?,
+                None => None,
+            };
+            candidates.push(Candidate { promotion, coupon });
+        }
+        Ok(candidates)
+    }
+    /// 下单时占用活动和优惠券的使用次数，两个订单同时抢最后一个名额时只有一个会成功
+    pub async fn consume<C: ConnectionTrait>(db: &C, discounts: &[AppliedDiscount]) -> AppResult<()> {
+        for discount in discounts {
+            let updated = Promotions::update_many()
+                .col_expr(Column::UsedCount, Expr::col(Column::UsedCount).add(1))
+                .filter(Column::Id.eq(discount.promotion_id.clone()))
+                .filter(
+                    Condition::any()
+                        .add(Column::UsageLimit.is_null())
+                        .add(Expr::col(Column::UsedCount).lt(Expr::col(Column::UsageLimit))),
+                )
+                .exec(db)
+                .await?;
+            if updated.rows_affected == 0 {
+                return Err(AppError::conflict(format!("活动{}名额已经用完", discount.name)));
+            }
+            let Some(coupon_id) = &discount.coupon_id else {
+                continue;
+            };
+            let updated = Coupons::update_many()
+                .col_expr(coupon::Column::UsedCount, Expr::col(coupon::Column::UsedCount).add(1))
+                .filter(coupon::Column::Id.eq(coupon_id.clone()))
+                .filter(
+                    Condition::any()
+                        .add(coupon::Column::UsageLimit.is_null())
+                        .add(Expr::col(coupon::Column::UsedCount).lt(Expr::col(coupon::Column::UsageLimit))),
+                )
+                .exec(db)
+                .await?;
+            if updated.rows_affected == 0 {
+                return Err(AppError::conflict(format!("活动{}的优惠券已经用完", discount.name)));
+            }
+        }
+        Ok(())
+    }
+    /// 订单取消或者退菜后不再参加活动时，退回占用的使用次数
+    pub async fn release<C: ConnectionTrait>(db: &C, discounts: &[AppliedDiscount]) -> AppResult<()> {
+        for discount in discounts {
+            Promotions::update_many()
+                .col_expr(Column::UsedCount, Expr::col(Column::UsedCount).sub(1))
+                .filter(Column::Id.eq(discount.promotion_id.clone()))
+                .filter(Column::UsedCount.gt(0))
+                .exec(db)
+                .await?;
+            if let Some(coupon_id) = &discount.coupon_id {
+                Coupons::update_many()
+                    .col_expr(coupon::Column::UsedCount, Expr::col(coupon::Column::UsedCount).sub(1))
+                    .filter(coupon::Column::Id.eq(coupon_id.clone()))
+                    .filter(coupon::Column::UsedCount.gt(0))
+                    .exec(db)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::init_db_coon;
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::promotion::PromotionCurd;
+    use crate::dto::order::{OrderItemData, OrderOptions};
+    use crate::dto::promotion::{CouponData, PromotionData};
+    use crate::entities::order::Status;
+    use crate::entities::promotion::{Kind, Target};
+    use crate::utils::money::Money;
+
+    #[tokio::test]
+    async fn test_coupon() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_promotion_dish".to_string(), Money::from_fen(3000), "test".to_string(), None).await.unwrap();
+        let data = PromotionData {
+            name: "test_promotion_half".to_string(),
+            kind: Kind::Percent,
+            target: Target::Dish,
+            target_ids: vec![dish_id.clone()],
+            rate: 5000,
+            amount: Money::ZERO,
+            buy_quantity: 0,
+            free_quantity: 0,
+            min_spend: Money::ZERO,
+            start_date: None,
+            end_date: None,
+            usage_limit: None,
+            coupon_only: true,
+            enabled: true,
+        };
+        let promotion_id = PromotionCurd::insert(data).await.unwrap();
+        let data = CouponData { code: "test_half".to_string(), promotion_id, usage_limit: Some(1), enabled: true };
+        PromotionCurd::insert_coupon(data).await.unwrap();
+        let coupon = |code: &str| OrderOptions { coupon_code: Some(code.to_string()), ..Default::default() };
+        let items = || vec![OrderItemData { dish_id: dish_id.clone(), quantity: 2, ..Default::default() }];
+        let (id, total) = OrderCurd::insert("test".to_string(), None, coupon("TEST_HALF"), items()).await.unwrap();
+        assert_eq!(total, Money::from_fen(3000));
+        //优惠券只能用一次，取消订单后退回
+        assert!(OrderCurd::insert("test".to_string(), None, coupon("test_half"), items()).await.is_err());
+        OrderCurd::transition(id, Status::Cancelled, "test".to_string()).await.unwrap();
+        let (_, total) = OrderCurd::insert("test".to_string(), None, coupon("test_half"), items()).await.unwrap();
+        assert_eq!(total, Money::from_fen(3000));
+    }
+}
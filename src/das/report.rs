@@ -0,0 +1,196 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, TransactionTrait};
+use salvo::http::StatusError;
+use time::Date;
+use ulid::Ulid;
+use sea_orm::Iterable;
+use crate::config::db::get_db_coon;
+use crate::dto::report::DailyReport;
+use crate::entities::prelude::{ItemVoids, OrderItems, Orders, Payments, Refunds, ZReport, ZReports};
+use crate::entities::z_report::{PaymentSummaries, PaymentSummary};
+use crate::entities::{item_void, order, order_item, payment, refund, z_report};
+use crate::error::{AppError, AppResult};
+use crate::utils::{business_date_of, business_day_range, format_date, get_now_time, today};
+use crate::utils::money::Money;
+
+pub struct ReportCurd;
+impl ReportCurd {
+    /// 营业日报表，已经日结的返回日结时的数据，否则实时计算
+    pub async fn daily(date: Date) -> AppResult<DailyReport> {
+        let db = get_db_coon();
+        if let Some(report) = Self::query_z_report(db, date).await? {
+            return Ok(report.into());
+        }
+        Self::compute(db, date).await
+    }
+    /// 日结，把当天的数据保存为Z报表
+    /// 所有订单都结账或取消、退款都处理完后才能日结，日结后不能再修改这一天的订单，也不能再退款
+    pub async fn close_day(date: Date, uid: String) -> AppResult<DailyReport> {
+        if date > today() {
+            return Err(StatusError::bad_request().brief("不能日结还没开始的营业日").into());
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        if Self::query_z_report(&txn, date).await?.is_some() {
+            return Err(AppError::conflict(format!("营业日{}已经日结", format_date(date))));
+        }
+        let report = Self::compute(&txn, date).await?;
+        if report.open_orders > 0 {
+            return Err(AppError::conflict(format!("还有{}个订单没有结账，不能日结", report.open_orders)));
+        }
+        let (start, end) = business_day_range(date);
+        let pending_refunds = Refunds::find()
+            .filter(refund::Column::CreatedAt.gte(start))
+            .filter(refund::Column::CreatedAt.lt(end))
+            .filter(refund::Column::Status.eq(refund::Status::Pending))
+            .count(&txn)
+            .await?;
+        if pending_refunds > 0 {
+            return Err(AppError::conflict(format!("还有{}笔退款在处理中，不能日结", pending_refunds)));
+        }
+        let report = ZReport {
+            id: Ulid::new().to_string(),
+            business_date: report.business_date,
+            gross_sales: report.gross_sales,
+            voids: report.voids,
+            discounts: report.discounts,
+            refunds: report.refunds,
+            net_sales: report.net_sales,
+            service_charges: report.service_charges,
+            taxes: report.taxes,
+            order_count: report.order_count,
+            average_ticket: report.average_ticket,
+            payments: PaymentSummaries(report.payments),
+            uid,
+            closed_at: get_now_time(),
+        };
+        ZReports::insert(report.clone().into_active_model()).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(report.into())
+    }
+    /// 某个时间所在的营业日是否已经日结
+    pub async fn is_closed<C: ConnectionTrait>(db: &C, time: &str) -> AppResult<bool> {
+        Ok(Self::query_z_report(db, business_date_of(time)?).await?.is_some())
+    }
+    /// 修改订单前检查订单所在的营业日还没有日结
+    pub async fn ensure_open<C: ConnectionTrait>(db: &C, time: &str) -> AppResult<()> {
+        if Self::is_closed(db, time).await? {
+            return Err(AppError::conflict(format!(
+                "营业日{}已经日结，不能再修改",
+                format_date(business_date_of(time)?)
+            )));
+        }
+        Ok(())
+    }
+    async fn query_z_report<C: ConnectionTrait>(db: &C, date: Date) -> AppResult<Option<ZReport>> {
+        Ok(ZReports::find()
+            .filter(z_report::Column::BusinessDate.eq(format_date(date)))
+            .one(db)
+            .await?)
+    }
+    /// 销售数据按下单时间归属营业日，退款按退款时间归属营业日
+    async fn compute<C: ConnectionTrait>(db: &C, date: Date) -> AppResult<DailyReport> {
+        let (start, end) = business_day_range(date);
+        let orders = Orders::find()
+            .filter(order::Column::CreatedAt.gte(start.clone()))
+            .filter(order::Column::CreatedAt.lt(end.clone()))
+            .filter(order::Column::Status.ne(order::Status::Cancelled))
+            .all(db)
+            .await?;
+        let order_ids: Vec<String> = orders.iter().map(|order| order.id.clone()).collect();
+        let open_orders = orders
+            .iter()
+            .filter(|order| !matches!(order.status, order::Status::Paid | order::Status::Closed))
+            .count();
+        let gross_sales: Money = OrderItems::find()
+            .filter(order_item::Column::OrderId.is_in(order_ids.clone()))
+            .all(db)
+            .await?
+            .iter()
+            .map(|item| item.price * item.quantity)
+            .sum();
+        let voids: Money = ItemVoids::find()
+            .filter(item_void::Column::OrderId.is_in(order_ids.clone()))
+            .all(db)
+            .await?
+            .iter()
+            .map(|void| void.amount)
+            .sum();
+        //退菜后订单上的优惠会重新计算，这里取最终的优惠金额
+        let discounts: Money = orders.iter().map(|order| order.discount).sum();
+        let refunds = Refunds::find()
+            .filter(refund::Column::CreatedAt.gte(start))
+            .filter(refund::Column::CreatedAt.lt(end))
+            .filter(refund::Column::Status.eq(refund::Status::Done))
+            .find_also_related(Payments)
+            .all(db)
+            .await?;
+        let payments = Payments::find()
+            .filter(payment::Column::OrderId.is_in(order_ids))
+            .filter(payment::Column::Status.eq(payment::Status::Captured))
+            .all(db)
+            .await?;
+        let mut summaries = Vec::new();
+        let mut total_refunded = Money::ZERO;
+        for method in payment::Method::iter() {
+            let amount: Money = payments
+                .iter()
+                .filter(|payment| payment.method == method)
+                .map(|payment| payment.amount)
+                .sum();
+            let refunded: Money = refunds
+                .iter()
+                .filter(|(_, payment)| payment.as_ref().is_some_and(|payment| payment.method == method))
+                .map(|(refund, _)| refund.amount)
+                .sum();
+            total_refunded += refunded;
+            if amount != Money::ZERO || refunded != Money::ZERO {
+                summaries.push(PaymentSummary { method, amount, refunded });
+            }
+        }
+        let net_sales = gross_sales - voids - discounts - total_refunded;
+        let service_charges: Money = orders.iter().map(|order| order.service_charge).sum();
+        let taxes: Money = orders.iter().map(|order| order.tax).sum();
+        let order_count = orders.len() as i64;
+        let average_ticket = if order_count > 0 { Money::from_fen(net_sales.fen() / order_count) } else { Money::ZERO };
+        Ok(DailyReport {
+            business_date: format_date(date),
+            closed: false,
+            gross_sales,
+            voids,
+            discounts,
+            refunds: total_refunded,
+            net_sales,
+            service_charges,
+            taxes,
+            order_count: order_count as i32,
+            average_ticket,
+            payments: summaries,
+            open_orders: open_orders as i32,
+            closed_at: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::report::ReportCurd;
+    use crate::utils::business_day_range;
+
+    #[tokio::test]
+    async fn test_close_day() {
+        init_db_coon().await;
+        //用很早的日期，避免影响其他测试在今天下单
+        let date = date!(2000 - 01 - 01);
+        let (start, end) = business_day_range(date);
+        assert_eq!(start, "2000-01-01 00:00:00.000");
+        assert_eq!(end, "2000-01-02 00:00:00.000");
+        let report = ReportCurd::close_day(date, "test".to_string()).await.unwrap();
+        assert!(report.closed);
+        assert_eq!(report.order_count, 0);
+        assert!(ReportCurd::close_day(date, "test".to_string()).await.is_err());
+        assert!(ReportCurd::ensure_open(get_db_coon(), "2000-01-01 12:00:00.000").await.is_err());
+        assert!(ReportCurd::ensure_open(get_db_coon(), "2000-01-02 12:00:00.000").await.is_ok());
+    }
+}
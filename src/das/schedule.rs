@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use salvo::http::StatusError;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait};
+use time::OffsetDateTime;
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::dto::schedule::{ScheduleData, ScheduleView};
+use crate::entities::prelude::{Categories, CategoryDishMaps, Dishes, ScheduleException, ScheduleExceptions, ScheduleWindow, ScheduleWindows};
+use crate::entities::schedule_window::{clock_minutes, Target, Weekdays};
+use crate::entities::{schedule_exception, schedule_window};
+use crate::error::AppResult;
+use crate::utils::{format_date, now_local, parse_date};
+
+/// 某个时刻各个分类和菜品是否供应
+pub struct Availability {
+    windows: HashMap<(Target, String), Vec<ScheduleWindow>>,
+    exceptions: HashMap<(Target, String), Vec<ScheduleException>>,
+    dish_categories: HashMap<String, Vec<String>>,
+    weekday: u8,
+    minute: u16,
+    date: String,
+}
+impl Availability {
+    /// 加载门店时区当前时刻的供应情况
+    pub async fn load<C: ConnectionTrait>(db: &C) -> AppResult<Self> {
+        Self::load_at(db, now_local()).await
+    }
+    pub async fn load_at<C: ConnectionTrait>(db: &C, now: OffsetDateTime) -> AppResult<Self> {
+        let mut windows: HashMap<(Target, String), Vec<ScheduleWindow>> = HashMap::new();
+        for window in ScheduleWindows::find().all(db).await? {
+            windows.entry((window.target, window.target_id.clone())).or_default().push(window);
+        }
+        let mut exceptions: HashMap<(Target, String), Vec<ScheduleException>> = HashMap::new();
+        for exception in ScheduleExceptions::find().all(db).await? {
+            exceptions.entry((exception.target, exception.target_id.clone())).or_default().push(exception);
+        }
+        let mut dish_categories: HashMap<String, Vec<String>> = HashMap::new();
+        for map in CategoryDishMaps::find().all(db).await? {
+            dish_categories.entry(map.dish_id).or_default().push(map.category_id);
+        }
+        Ok(Availability {
+            windows,
+            exceptions,
+            dish_categories,
+            weekday: now.weekday().number_from_monday(),
+            minute: now.hour() as u16 * 60 + now.minute() as u16,
+            date: format_date(now.date()),
+        })
+    }
+    /// 例外日期优先，多个例外日期重叠时暂停供应优先，没有供应时段时全天供应
+    fn is_open(&self, target: Target, id: &str) -> bool {
+        let key = (target, id.to_string());
+        let exceptions: Vec<&ScheduleException> = self
+            .exceptions
+            .get(&key)
+            .map(|exceptions| exceptions.iter().filter(|exception| exception.covers(&self.date)).collect())
+            .unwrap_or_default();
+        if !exceptions.is_empty() {
+            return exceptions.iter().all(|exception| exception.available);
+        }
+        match self.windows.get(&key) {
+            Some(windows) => windows.iter().any(|window| window.contains(self.weekday, self.minute)),
+            None => true,
+        }
+    }
+    pub fn category_open(&self, id: &str) -> bool {
+        self.is_open(Target::Category, id)
+    }
+    /// 菜品自己在供应时段内，并且不属于任何分类或者至少有一个所属分类在供应
+    pub fn dish_open(&self, id: &str) -> bool {
+        if !self.is_open(Target::Dish, id) {
+            return false;
+        }
+        match self.dish_categories.get(id) {
+            Some(categories) => categories.iter().any(|category_id| self.category_open(category_id)),
+            None => true,
+        }
+    }
+}
+
+pub struct ScheduleCurd;
+impl ScheduleCurd {
+    /// 替换分类或菜品的供应时段和例外日期
+    pub async fn replace(target: Target, target_id: String, data: ScheduleData) -> AppResult<ScheduleView> {
+        for window in &data.windows {
+            if window.days.iter().any(|day| !(1..=7).contains(day)) {
+                return Err(StatusError::bad_request().brief("星期只能是1到7").into());
+            }
+            for clock in [&window.start_time, &window.end_time] {
+                if clock_minutes(clock).is_none() {
+                    return Err(StatusError::bad_request()
+                        .brief(format!("时间{}格式错误，应为形如07:00的时间", clock))
+                        .into());
+                }
+            }
+        }
+        for exception in &data.exceptions {
+            if parse_date(&exception.start_date)? > parse_date(&exception.end_date)? {
+                return Err(StatusError::bad_request()
+                    .brief(format!("开始日期{}晚于结束日期{}", exception.start_date, exception.end_date))
+                    .into());
+            }
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let exists = match target {
+            Target::Category => Categories::find_by_id(target_id.clone()).count(&txn).await?,
+            Target::Dish => Dishes::find_by_id(target_id.clone()).count(&txn).await?,
+        };
+        if exists == 0 {
+            let name = match target {
+                Target::Category => "分类",
+                Target::Dish => "菜品",
+            };
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的{}不存在", target_id, name))
+                .into());
+        }
+        Self::delete_by_target(&txn, target, target_id.clone()).await?;
+        for window in data.windows {
+            let window = ScheduleWindow {
+                id: Ulid::new().to_string(),
+                target,
+                target_id: target_id.clone(),
+                days: Weekdays(window.days),
+                start_time: window.start_time,
+                end_time: window.end_time,
+            };
+            ScheduleWindows::insert(window.into_active_model()).exec(&txn).await?;
+        }
+        for exception in data.exceptions {
+            let exception = ScheduleException {
+                id: Ulid::new().to_string(),
+                target,
+                target_id: target_id.clone(),
+                start_date: exception.start_date,
+                end_date: exception.end_date,
+                available: exception.available,
+                note: exception.note,
+            };
+            ScheduleExceptions::insert(exception.into_active_model()).exec(&txn).await?;
+        }
+        txn.commit().await?;
+        Self::query(target, target_id).await
+    }
+    pub async fn query(target: Target, target_id: String) -> AppResult<ScheduleView> {
+        let db = get_db_coon();
+        let windows = ScheduleWindows::find()
+            .filter(schedule_window::Column::Target.eq(target))
+            .filter(schedule_window::Column::TargetId.eq(target_id.clone()))
+            .order_by_asc(schedule_window::Column::Id)
+            .all(db)
+            .await?;
+        let exceptions = ScheduleExceptions::find()
+            .filter(schedule_exception::Column::Target.eq(target))
+            .filter(schedule_exception::Column::TargetId.eq(target_id))
+            .order_by_asc(schedule_exception::Column::StartDate)
+            .all(db)
+            .await?;
+        Ok(ScheduleView { windows, exceptions })
+    }
+    /// 删除分类或菜品时调用
+    pub async fn delete_by_target<C: ConnectionTrait>(db: &C, target: Target, target_id: String) -> AppResult<()> {
+        ScheduleWindows::delete_many()
+            .filter(schedule_window::Column::Target.eq(target))
+            .filter(schedule_window::Column::TargetId.eq(target_id.clone()))
+            .exec(db)
+            .await?;
+        ScheduleExceptions::delete_many()
+            .filter(schedule_exception::Column::Target.eq(target))
+            .filter(schedule_exception::Column::TargetId.eq(target_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use time::macros::datetime;
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::category::CategoryCurd;
+    use crate::das::category_dish_map::CategoryDishMapCurd;
+    use crate::das::dish::DishCurd;
+    use crate::das::schedule::{Availability, ScheduleCurd};
+    use crate::dto::schedule::{ExceptionData, ScheduleData, WindowData};
+    use crate::entities::schedule_window::Target;
+
+    #[tokio::test]
+    async fn test_breakfast_schedule() {
+        init_db_coon().await;
+        let category_id = CategoryCurd::insert("test_schedule_breakfast".to_string()).await.unwrap();
+        let dish_id = DishCurd::insert("test_schedule_congee".to_string(), Money::from_fen(500), "test".to_string(), None).await.unwrap();
+        CategoryDishMapCurd::insert(category_id.clone(), dish_id.clone()).await.unwrap();
+        let data = ScheduleData {
+            windows: vec![WindowData { days: vec![1, 2, 3, 4, 5], start_time: "07:00".to_string(), end_time: "10:30".to_string() }],
+            exceptions: vec![ExceptionData {
+                start_date: "2030-01-01".to_string(),
+                end_date: "2030-01-01".to_string(),
+                available: false,
+                note: "元旦".to_string(),
+            }],
+        };
+        ScheduleCurd::replace(Target::Category, category_id.clone(), data).await.unwrap();
+        // 2030-01-02是星期三
+        let availability = Availability::load_at(get_db_coon(), datetime!(2030-01-02 08:00 +8)).await.unwrap();
+        assert!(availability.category_open(&category_id));
+        assert!(availability.dish_open(&dish_id));
+        let availability = Availability::load_at(get_db_coon(), datetime!(2030-01-02 11:00 +8)).await.unwrap();
+        assert!(!availability.dish_open(&dish_id));
+        let availability = Availability::load_at(get_db_coon(), datetime!(2030-01-01 08:00 +8)).await.unwrap();
+        assert!(!availability.category_open(&category_id));
+    }
+}
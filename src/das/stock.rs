@@ -0,0 +1,324 @@
+use log::warn;
+use salvo::http::StatusError;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveEnum, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::das::ingredient::IngredientCurd;
+use crate::dto::stock::{AdjustStockData, SetStockData, StockDetail};
+use crate::entities::dish::Status;
+use crate::entities::prelude::{DishVariants, Dishes, OrderItem, Stock, StockMovement, StockMovements, Stocks};
+use crate::entities::stock_movement::Reason;
+use crate::entities::{dish, dish_variant, stock, stock_movement};
+use crate::error::{AppError, AppResult};
+use crate::utils::get_now_time;
+
+pub struct StockCurd;
+impl StockCurd {
+    /// 设置库存和提醒阈值，菜品或规格还没有库存记录时从现在开始管理库存
+    pub async fn set(data: SetStockData, uid: String) -> AppResult<Stock> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        if Dishes::find_by_id(data.dish_id.clone()).one(&txn).await?.is_none() {
+            return Err(StatusError::bad_request()
+                .brief(format!("id为{}的菜品不存在", data.dish_id))
+                .into());
+        }
+        if let Some(variant_id) = &data.variant_id {
+            let variant = DishVariants::find_by_id(variant_id.clone())
+                .filter(dish_variant::Column::DishId.eq(data.dish_id.clone()))
+                .one(&txn)
+                .await?;
+            if variant.is_none() {
+                return Err(StatusError::bad_request()
+                    .brief(format!("菜品没有id为{}的规格", variant_id))
+                    .into());
+            }
+        }
+        let existing = Stocks::find()
+            .filter(stock::Column::DishId.eq(data.dish_id.clone()))
+            .filter(match &data.variant_id {
+                Some(variant_id) => stock::Column::VariantId.eq(variant_id.clone()),
+                None => stock::Column::VariantId.is_null(),
+            })
+            .one(&txn)
+            .await?;
+        let stock = match existing {
+            Some(stock) => {
+                let mut stock = stock.into_active_model();
+                stock.low_threshold = Set(data.low_threshold);
+                stock.update(&txn).await?
+            }
+            None => {
+                let stock = Stock {
+                    id: Ulid::new().to_string(),
+                    dish_id: data.dish_id,
+                    variant_id: data.variant_id,
+                    quantity: 0,
+                    low_threshold: data.low_threshold,
+                    auto_delisted: false,
+                    updated_at: get_now_time(),
+                };
+                Stocks::insert(stock.clone().into_active_model()).exec(&txn).await?;
+                stock
+            }
+        };
+        let delta = data.quantity - stock.quantity;
+        let stock = Self::apply(&txn, stock, delta, Reason::Count, None, data.note, uid).await?;
+        txn.commit().await?;
+        Ok(stock)
+    }
+    /// 补货、损耗等手动调整，下单和取消的变动由系统记录，不能手动调整
+    pub async fn adjust(id: String, data: AdjustStockData, uid: String) -> AppResult<Stock> {
+        if matches!(data.reason, Reason::Order | Reason::Cancel) {
+            return Err(StatusError::bad_request().brief("下单和取消的库存变动由系统记录").into());
+        }
+        if data.reason == Reason::Other && data.note.trim().is_empty() {
+            return Err(StatusError::bad_request().brief("原因为其他时必须填写备注").into());
+        }
+        if data.delta == 0 {
+            return Err(StatusError::bad_request().brief("调整数量不能为0").into());
+        }
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(stock) = Stocks::find_by_id(id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的库存不存在", id))
+                .into());
+        };
+        let stock = Self::apply(&txn, stock, data.delta, data.reason, None, data.note, uid).await?;
+        txn.commit().await?;
+        Ok(stock)
+    }
+    /// 不再管理库存，被自动下架的菜品恢复上架
+    pub async fn delete_by_id(id: String) -> AppResult<()> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        if let Some(stock) = Stocks::find_by_id(id.clone()).one(&txn).await? {
+            Self::delete(&txn, stock).await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+    /// 删除菜品时调用，删除菜品和它所有规格的库存
+    pub async fn delete_by_dish<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<()> {
+        let stocks = Stocks::find().filter(stock::Column::DishId.eq(dish_id)).all(db).await?;
+        for stock in stocks {
+            Self::delete(db, stock).await?;
+        }
+        Ok(())
+    }
+    /// 手动上架或下架菜品时调用，之后补货或卖完不再自动改变菜品状态
+    pub async fn clear_auto_delisted<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<()> {
+        Stocks::update_many()
+            .col_expr(stock::Column::AutoDelisted, Expr::value(false))
+            .filter(stock::Column::DishId.eq(dish_id))
+            .filter(stock::Column::VariantId.is_null())
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+    /// 替换规格时调用，旧规格的库存随规格一起删除
+    pub async fn delete_variant_stocks<C: ConnectionTrait>(db: &C, dish_id: String) -> AppResult<()> {
+        let stocks = Stocks::find()
+            .filter(stock::Column::DishId.eq(dish_id))
+            .filter(stock::Column::VariantId.is_not_null())
+            .all(db)
+            .await?;
+        for stock in stocks {
+            Self::delete(db, stock).await?;
+        }
+        Ok(())
+    }
+    async fn delete<C: ConnectionTrait>(db: &C, stock: Stock) -> AppResult<()> {
+        if stock.auto_delisted {
+            Self::set_status(db, &stock, Status::Delist, Status::Normal).await?;
+        }
+        StockMovements::delete_many()
+            .filter(stock_movement::Column::StockId.eq(stock.id.clone()))
+            .exec(db)
+            .await?;
+        Stocks::delete_by_id(stock.id).exec(db).await?;
+        Ok(())
+    }
+    pub async fn query_all() -> AppResult<Vec<StockDetail>> {
+        let db = get_db_coon();
+        let stocks = Stocks::find().order_by_asc(stock::Column::DishId).all(db).await?;
+        Self::with_names(stocks).await
+    }
+    /// 库存不高于提醒阈值的菜品，需要补货
+    pub async fn query_alerts() -> AppResult<Vec<StockDetail>> {
+        let db = get_db_coon();
+        let stocks = Stocks::find()
+            .filter(Expr::col(stock::Column::Quantity).lte(Expr::col(stock::Column::LowThreshold)))
+            .order_by_asc(stock::Column::Quantity)
+            .all(db)
+            .await?;
+        Self::with_names(stocks).await
+    }
+    pub async fn query_movements(id: String) -> AppResult<Vec<StockMovement>> {
+        let db = get_db_coon();
+        Ok(StockMovements::find()
+            .filter(stock_movement::Column::StockId.eq(id))
+            .order_by_desc(stock_movement::Column::Id)
+            .all(db)
+            .await?)
+    }
+    async fn with_names(stocks: Vec<Stock>) -> AppResult<Vec<StockDetail>> {
+        let db = get_db_coon();
+        let dishes = Dishes::find()
+            .filter(dish::Column::Id.is_in(stocks.iter().map(|stock| stock.dish_id.clone())))
+            .all(db)
+            .await?;
+        let variants = DishVariants::find()
+            .filter(dish_variant::Column::Id.is_in(stocks.iter().filter_map(|stock| stock.variant_id.clone())))
+            .all(db)
+            .await?;
+        Ok(stocks
+            .into_iter()
+            .map(|stock| StockDetail {
+                dish_name: dishes.iter().find(|dish| dish.id == stock.dish_id).map(|dish| dish.name.clone()).unwrap_or_default(),
+                variant_name: variants.iter().find(|variant| Some(&variant.id) == stock.variant_id.as_ref()).map(|variant| variant.name.clone()),
+                low: stock.is_low(),
+                stock,
+            })
+            .collect())
+    }
+    /// 订单项会扣减的库存，包括整个菜品的库存和所选规格的库存
+    async fn query_for_item<C: ConnectionTrait>(db: &C, item: &OrderItem) -> AppResult<Vec<Stock>> {
+        let mut variant = Condition::any().add(stock::Column::VariantId.is_null());
+        if let Some(variant_id) = &item.variant_id {
+            variant = variant.add(stock::Column::VariantId.eq(variant_id.clone()));
+        }
+        Ok(Stocks::find()
+            .filter(stock::Column::DishId.eq(item.dish_id.clone()))
+            .filter(variant)
+            .all(db)
+            .await?)
+    }
+    /// 下单时扣减库存和配方里的原料，任何一项库存不足时整个订单失败
+    pub async fn consume<C: ConnectionTrait>(db: &C, items: &[OrderItem], order_id: &str, uid: &str) -> AppResult<()> {
+        for item in items {
+            IngredientCurd::consume(db, &item.dish_id, &item.display_name(), item.quantity).await?;
+            for stock in Self::query_for_item(db, item).await? {
+                if stock.quantity < item.quantity {
+                    return Err(AppError::conflict(format!("{}库存不足，还剩{}份", item.display_name(), stock.quantity)));
+                }
+                Self::apply(db, stock, -item.quantity, Reason::Order, Some(order_id.to_string()), String::new(), uid.to_string()).await?;
+            }
+        }
+        Ok(())
+    }
+    /// 取消订单或者退掉还没上的菜时退回库存和原料
+    pub async fn restore<C: ConnectionTrait>(db: &C, item: &OrderItem, quantity: i32, uid: &str) -> AppResult<()> {
+        if quantity <= 0 {
+            return Ok(());
+        }
+        IngredientCurd::restore(db, &item.dish_id, quantity).await?;
+        for stock in Self::query_for_item(db, item).await? {
+            Self::apply(db, stock, quantity, Reason::Cancel, Some(item.order_id.clone()), String::new(), uid.to_string()).await?;
+        }
+        Ok(())
+    }
+    /// 修改库存并记录变动，库存为0时自动下架，从0补货后恢复被自动下架的菜品
+    async fn apply<C: ConnectionTrait>(
+        db: &C,
+        stock: Stock,
+        delta: i32,
+        reason: Reason,
+        order_id: Option<String>,
+        note: String,
+        uid: String,
+    ) -> AppResult<Stock> {
+        let quantity = stock.quantity + delta;
+        if quantity < 0 {
+            return Err(AppError::conflict(format!("库存不足，当前库存为{}", stock.quantity)));
+        }
+        let was_low = stock.is_low();
+        let mut auto_delisted = stock.auto_delisted;
+        if quantity == 0 && !auto_delisted {
+            auto_delisted = Self::set_status(db, &stock, Status::Normal, Status::Delist).await?;
+        } else if quantity > 0 && auto_delisted {
+            Self::set_status(db, &stock, Status::Delist, Status::Normal).await?;
+            auto_delisted = false;
+        }
+        let mut active = stock.into_active_model();
+        active.quantity = Set(quantity);
+        active.auto_delisted = Set(auto_delisted);
+        active.updated_at = Set(get_now_time());
+        let stock = active.update(db).await?;
+        if delta != 0 {
+            let movement = StockMovement {
+                id: Ulid::new().to_string(),
+                stock_id: stock.id.clone(),
+                delta,
+                quantity,
+                reason,
+                order_id,
+                note,
+                uid,
+                created_at: get_now_time(),
+            };
+            StockMovements::insert(movement.into_active_model()).exec(db).await?;
+        }
+        if stock.is_low() && !was_low {
+            warn!("库存不足提醒: 菜品{}(规格{:?})只剩{}份", stock.dish_id, stock.variant_id, stock.quantity);
+        }
+        Ok(stock)
+    }
+    /// 把菜品或规格从from状态改为to状态，返回是否有修改
+    async fn set_status<C: ConnectionTrait>(db: &C, stock: &Stock, from: Status, to: Status) -> AppResult<bool> {
+        let result = match &stock.variant_id {
+            Some(variant_id) => DishVariants::update_many()
+                .col_expr(dish_variant::Column::Status, Expr::value(to.to_value()))
+                .filter(dish_variant::Column::Id.eq(variant_id.clone()))
+                .filter(dish_variant::Column::Status.eq(from))
+                .exec(db)
+                .await?,
+            None => Dishes::update_many()
+                .col_expr(dish::Column::Status, Expr::value(to.to_value()))
+                .filter(dish::Column::Id.eq(stock.dish_id.clone()))
+                .filter(dish::Column::Status.eq(from))
+                .exec(db)
+                .await?,
+        };
+        Ok(result.rows_affected > 0)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+    use crate::config::db::{get_db_coon, init_db_coon};
+    use crate::das::dish::DishCurd;
+    use crate::das::order::OrderCurd;
+    use crate::das::stock::StockCurd;
+    use crate::dto::order::OrderItemData;
+    use crate::dto::stock::{AdjustStockData, SetStockData};
+    use crate::entities::dish::Status;
+    use crate::entities::order;
+    use crate::entities::stock_movement::Reason;
+
+    #[tokio::test]
+    async fn test_stock_delist_and_relist() {
+        init_db_coon().await;
+        let dish_id = DishCurd::insert("test_stock_dish".to_string(), Money::from_fen(1000), "test".to_string(), None).await.unwrap();
+        let data = SetStockData { dish_id: dish_id.clone(), variant_id: None, quantity: 2, low_threshold: 1, note: String::new() };
+        let stock = StockCurd::set(data, "test".to_string()).await.unwrap();
+        let item = |quantity| vec![OrderItemData { dish_id: dish_id.clone(), quantity, ..Default::default() }];
+        assert!(OrderCurd::insert("test".to_string(), None, Default::default(), item(3)).await.is_err());
+        let (id, _) = OrderCurd::insert("test".to_string(), None, Default::default(), item(2)).await.unwrap();
+        let status = || async { DishCurd::query_by_id(get_db_coon(), dish_id.clone()).await.unwrap().unwrap().status };
+        assert_eq!(status().await, Status::Delist);
+        assert!(StockCurd::query_alerts().await.unwrap().iter().any(|alert| alert.stock.id == stock.id));
+        OrderCurd::transition(id, order::Status::Cancelled, "test".to_string()).await.unwrap();
+        assert_eq!(status().await, Status::Normal);
+        let waste = AdjustStockData { delta: -2, reason: Reason::Waste, note: String::new() };
+        let stock = StockCurd::adjust(stock.id, waste, "test".to_string()).await.unwrap();
+        assert_eq!(stock.quantity, 0);
+        assert_eq!(status().await, Status::Delist);
+        let restock = AdjustStockData { delta: 5, reason: Reason::Restock, note: String::new() };
+        StockCurd::adjust(stock.id.clone(), restock, "test".to_string()).await.unwrap();
+        assert_eq!(status().await, Status::Normal);
+        assert_eq!(StockCurd::query_movements(stock.id).await.unwrap().len(), 5);
+    }
+}
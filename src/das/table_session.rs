@@ -0,0 +1,104 @@
+use salvo::http::StatusError;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, Set, TransactionTrait};
+use ulid::Ulid;
+use crate::config::db::get_db_coon;
+use crate::entities::dining_table;
+use crate::entities::prelude::{DiningTable, DiningTables, TableSession, TableSessions};
+use crate::entities::table_session::Column;
+use crate::error::{AppError, AppResult};
+use crate::utils::get_now_time;
+
+pub struct TableSessionCurd;
+impl TableSessionCurd {
+    /// 开台，返回新的会话
+    /// 餐桌必须是空闲状态，停用的餐桌不能开台，开台后变为有客
+    pub async fn open(table_id: String, uid: String) -> AppResult<TableSession> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let Some(table) = DiningTables::find_by_id(table_id.clone()).one(&txn).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的餐桌不存在", table_id))
+                .into());
+        };
+        if table.status == dining_table::Status::Disabled {
+            return Err(AppError::conflict(format!("餐桌{}已停用", table.name)));
+        }
+        if table.status != dining_table::Status::Idle {
+            return Err(AppError::conflict(format!("餐桌{}当前不是空闲状态", table.name)));
+        }
+        let mut table = table.into_active_model();
+        table.status = Set(dining_table::Status::Occupied);
+        table.update(&txn).await?;
+        let session = TableSession {
+            id: Ulid::new().to_string(),
+            table_id,
+            uid,
+            opened_at: get_now_time(),
+            closed_at: None,
+        };
+        TableSessions::insert(session.clone().into_active_model()).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(session)
+    }
+    /// 结账后关闭会话，餐桌恢复空闲
+    pub async fn close(id: String) -> AppResult<TableSession> {
+        let db = get_db_coon();
+        let txn = db.begin().await?;
+        let session = Self::query_open_by_id(&txn, id).await?;
+        if let Some(table) = DiningTables::find_by_id(session.table_id.clone()).one(&txn).await? {
+            let mut table = table.into_active_model();
+            table.status = Set(dining_table::Status::Idle);
+            table.update(&txn).await?;
+        }
+        let mut session = session.into_active_model();
+        session.closed_at = Set(Some(get_now_time()));
+        let session = session.update(&txn).await?;
+        txn.commit().await?;
+        Ok(session)
+    }
+    /// 查询仍在进行中的会话，已关闭或不存在时返回错误
+    pub async fn query_open_by_id<C: ConnectionTrait>(db: &C, id: String) -> AppResult<TableSession> {
+        TableSessions::find_by_id(id.clone())
+            .filter(Column::ClosedAt.is_null())
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::conflict(format!("id为{}的桌台会话不存在或已关闭", id)))
+    }
+    /// 查询会话所在的餐桌，会话关闭后也可以查询
+    pub async fn query_table(id: String) -> AppResult<Option<DiningTable>> {
+        let db = get_db_coon();
+        let table = TableSessions::find_by_id(id)
+            .find_also_related(DiningTables)
+            .one(db)
+            .await?
+            .and_then(|(_, table)| table);
+        Ok(table)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::config::db::init_db_coon;
+    use crate::das::dining_table::DiningTableCurd;
+    use crate::das::table_session::TableSessionCurd;
+    use crate::dto::dining_table::DiningTableData;
+    use crate::entities::dining_table::Status;
+
+    #[tokio::test]
+    async fn test_open_and_close_session() {
+        init_db_coon().await;
+        let data = DiningTableData { name: "test_table".to_string(), area: "大厅".to_string(), capacity: 4, ..Default::default() };
+        let table_id = DiningTableCurd::insert(data).await.unwrap();
+        let session = TableSessionCurd::open(table_id.clone(), "test".to_string()).await.unwrap();
+        assert!(TableSessionCurd::open(table_id.clone(), "test".to_string()).await.is_err());
+        TableSessionCurd::close(session.id.clone()).await.unwrap();
+        assert!(TableSessionCurd::close(session.id).await.is_err());
+        //有进行中的会话时不能删除和停用，停用后不能开台
+        let session = TableSessionCurd::open(table_id.clone(), "test".to_string()).await.unwrap();
+        assert!(DiningTableCurd::delete_by_id(table_id.clone()).await.is_err());
+        let disable = || DiningTableData { name: "test_table".to_string(), area: "大厅".to_string(), capacity: 4, status: Some(Status::Disabled) };
+        assert!(DiningTableCurd::update(table_id.clone(), disable()).await.is_err());
+        TableSessionCurd::close(session.id).await.unwrap();
+        DiningTableCurd::update(table_id.clone(), disable()).await.unwrap();
+        assert!(TableSessionCurd::open(table_id, "test".to_string()).await.is_err());
+    }
+}
@@ -26,6 +26,7 @@ impl UserCurd {
         .await?;
         Ok(uuid.to_string())
     }
+    #[allow(dead_code)]
     pub async fn query_by_id(id: String) -> AppResult<Option<User>> {
         let db = get_db_coon();
         let user = Users::find_by_id(id).one(db).await?;
@@ -50,7 +51,7 @@ mod tests {
     async fn test_create_user() {
         init_db_coon().await;
         let password = "abc123";
-        let password = hash_password(&password).unwrap();
+        let password = hash_password(password).unwrap();
         UserCurd::insert_user("test".to_string(), password).await.unwrap();
     }
 }
\ No newline at end of file
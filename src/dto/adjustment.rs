@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::item_void::ReasonCode;
+use crate::entities::prelude::{ItemVoid, Refund};
+use crate::utils::money::Money;
+
+/// 经理当场输入账号密码审批
+#[derive(Deserialize, Debug)]
+pub struct ApproverData {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct VoidItemData {
+    #[validate(range(min = 1, message = "quantity must be greater than 0"))]
+    pub quantity: i32,
+    pub reason: ReasonCode,
+    #[serde(default)]
+    pub note: String,
+    pub approver: Option<ApproverData>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RefundData {
+    pub payment_id: String,
+    pub amount: Money,
+    pub reason: ReasonCode,
+    #[serde(default)]
+    pub note: String,
+    pub approver: Option<ApproverData>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OrderAdjustments {
+    pub voids: Vec<ItemVoid>,
+    pub refunds: Vec<Refund>,
+}
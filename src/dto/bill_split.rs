@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use crate::entities::prelude::BillSplit;
+use crate::utils::money::Money;
+
+/// 分单方式，例如 {"mode":"even","parts":3}
+#[derive(Deserialize, Debug)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SplitBillData {
+    Even { parts: u32 },
+    Item { parts: Vec<ItemPart> },
+    Custom { parts: Vec<CustomPart> },
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ItemPart {
+    pub payer: String,
+    pub item_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CustomPart {
+    pub payer: String,
+    pub amount: Money,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BillSplitWithItems {
+    #[serde(flatten)]
+    pub split: BillSplit,
+    pub item_ids: Vec<String>,
+}
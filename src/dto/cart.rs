@@ -0,0 +1,57 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::dto::combo::ComboComponentLine;
+use crate::entities::combo_slot::ComboComponent;
+use crate::entities::order::AppliedDiscount;
+use crate::entities::order_item::ItemModifier;
+use crate::promotion::Skipped;
+use crate::utils::money::Money;
+
+#[derive(Deserialize, Debug, Validate, ToSchema, Default)]
+pub struct AddCartItemData {
+    pub dish_id: String,
+    #[serde(default)]
+    pub variant_id: Option<String>,
+    #[serde(default)]
+    pub modifier_ids: Vec<String>,
+    #[serde(default)]
+    pub components: Vec<ComboComponent>,
+    #[validate(range(min = 1, max = 9999, message = "quantity must be between 1 and 9999"))]
+    pub quantity: i32,
+    #[validate(length(min = 1, message = "added_by can not be empty"))]
+    pub added_by: String,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema, Default)]
+pub struct UpdateCartItemData {
+    #[validate(range(min = 1, max = 9999, message = "quantity must be between 1 and 9999"))]
+    pub quantity: i32,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CartLine {
+    pub id: String,
+    pub dish_id: String,
+    pub dish_name: String,
+    pub variant_id: Option<String>,
+    pub variant_name: Option<String>,
+    pub modifiers: Vec<ItemModifier>,
+    pub components: Vec<ComboComponentLine>,
+    pub price: Money, // 包含选项和套餐加价的单价
+    pub quantity: i32,
+    pub added_by: String,
+    pub available: bool, // 菜品或规格被删除、下架，或者原料用完后为false
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CartView {
+    pub items: Vec<CartLine>,
+    pub subtotal: Money,
+    pub discount: Money,
+    pub discounts: Vec<AppliedDiscount>,
+    pub skipped: Vec<Skipped>, // 没有参加的活动和原因，例如 还差20.00才满100.00
+    pub service_charge: Money,
+    pub tax: Money,
+    pub total: Money, // 按堂食的服务费和税计算的应付金额
+}
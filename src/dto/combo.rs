@@ -0,0 +1,56 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use crate::entities::order_item::ItemModifier;
+use crate::entities::prelude::ComboSlot;
+use crate::utils::money::Money;
+
+/// 创建套餐时的一个部分，分类和单独列出的菜品至少要填一个
+#[derive(Deserialize, Debug, ToSchema, Default)]
+pub struct CreateComboSlotData {
+    pub name: String,
+    #[serde(default)]
+    pub category_id: Option<String>,
+    #[serde(default)]
+    pub dishes: Vec<ComboSlotDishData>,
+}
+#[derive(Deserialize, Debug, ToSchema, Default)]
+pub struct ComboSlotDishData {
+    pub dish_id: String,
+    #[serde(default)]
+    pub upcharge: Money,
+}
+
+/// 菜单上展示的套餐部分，分类下的菜品已经展开到choices里
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComboSlotDetail {
+    #[serde(flatten)]
+    pub slot: ComboSlot,
+    pub choices: Vec<ComboChoice>,
+}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComboChoice {
+    pub dish_id: String,
+    pub dish_name: String,
+    pub upcharge: Money,
+}
+
+/// 校验过的套餐内容，下单时展开成后厨制作的订单项
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct ComboComponentLine {
+    pub slot_id: String,
+    pub slot_name: String,
+    pub dish_id: String,
+    pub dish_name: String,
+    pub variant_id: Option<String>,
+    pub variant_name: Option<String>,
+    pub modifiers: Vec<ItemModifier>,
+    pub upcharge: Money,
+    #[serde(skip)]
+    pub station: Option<String>,
+}
+impl ComboComponentLine {
+    /// 这个部分让套餐价增加的金额，包括加价和选项的加价
+    pub fn extra(&self) -> Money {
+        self.upcharge + self.modifiers.iter().map(|modifier| modifier.price_delta).sum()
+    }
+}
@@ -0,0 +1,24 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::dining_table::Status;
+
+#[derive(Deserialize, Debug, Validate, ToSchema, Default)]
+pub struct DiningTableData {
+    #[validate(length(min = 1, message = "table name can not be empty"))]
+    pub name: String,
+    pub area: String,
+    #[validate(range(min = 1, message = "capacity must be greater than 0"))]
+    pub capacity: i32,
+    /// 只能在空闲和停用之间切换，有客状态由开台和结账维护，不传时保持不变
+    #[serde(default)]
+    pub status: Option<Status>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OpenSessionResult {
+    pub session_id: String,
+    pub table_id: String,
+    pub token: String,
+    pub exp: i64,
+}
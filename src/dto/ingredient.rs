@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::prelude::{Ingredient, RecipeLine};
+use crate::utils::money::{non_negative, Money};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct IngredientData {
+    #[validate(length(min = 1, message = "ingredient name can not be empty"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "unit can not be empty"))]
+    pub unit: String,
+    #[validate(range(min = 0.0, message = "quantity can not be negative"))]
+    pub quantity: f64,
+    #[validate(custom(function = "non_negative", message = "unit_cost can not be negative"))]
+    pub unit_cost: Money,
+    #[serde(default)]
+    #[validate(range(min = 0.0, message = "low_threshold can not be negative"))]
+    pub low_threshold: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct IngredientDetail {
+    #[serde(flatten)]
+    pub ingredient: Ingredient,
+    pub low: bool, // 库存不高于提醒阈值
+}
+
+/// 替换菜品的配方
+#[derive(Deserialize, Debug, Validate)]
+pub struct RecipeData {
+    #[validate(nested)]
+    pub lines: Vec<RecipeLineData>,
+}
+#[derive(Deserialize, Debug, Validate)]
+pub struct RecipeLineData {
+    pub ingredient_id: String,
+    #[validate(range(exclusive_min = 0.0, message = "quantity must be greater than 0"))]
+    pub quantity: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RecipeDetail {
+    #[serde(flatten)]
+    pub line: RecipeLine,
+    pub ingredient_name: String,
+    pub unit: String,
+    pub cost: Money, // 这一行原料的成本
+}
+
+/// 按配方计算的菜品成本，没有配方的菜品成本为0
+#[derive(Serialize, Debug)]
+pub struct DishCost {
+    pub dish_id: String,
+    pub dish_name: String,
+    pub price: Money,
+    pub cost: Money,
+    pub margin: Money, // 毛利 = 售价 - 成本
+}
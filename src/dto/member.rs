@@ -0,0 +1,35 @@
+use salvo::prelude::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct RegisterMemberData {
+    #[validate(custom(function = "phone", message = "phone must be 6 to 15 digits"))]
+    pub phone: String,
+    #[serde(default)]
+    pub name: String,
+    #[validate(length(min = 6, message = "password length must be greater than 5"))]
+    pub password: String,
+}
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MemberLoginData {
+    pub phone: String,
+    pub password: String,
+}
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MemberLoginResult {
+    pub id: String,
+    pub phone: String,
+    pub name: String,
+    pub points: i64,
+    pub token: String,
+    pub exp: i64,
+}
+/// 手机号只能是6到15位数字，可以带国际区号的+
+fn phone(phone: &str) -> Result<(), ValidationError> {
+    let digits = phone.strip_prefix('+').unwrap_or(phone);
+    if !(6..=15).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ValidationError::new("invalid_phone"));
+    }
+    Ok(())
+}
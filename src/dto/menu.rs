@@ -1,10 +1,18 @@
 use log::error;
 use salvo::oapi::ToSchema;
-use sea_orm::{EntityTrait, ModelTrait};
+use sea_orm::{EntityTrait, QueryOrder};
 use serde::{Deserialize, Serialize};
 use crate::config::db::get_db_coon;
-use crate::entities::category_dish_map::CategoryToDish;
-use crate::entities::prelude::{Categories, Category, Dish};
+use crate::das::category_dish_map::CategoryDishMapCurd;
+use crate::entities::{category, dish};
+use crate::das::dish::DishCurd;
+use crate::das::schedule::Availability;
+use crate::dto::combo::{ComboSlotDetail, CreateComboSlotData};
+use crate::dto::modifier::ModifierGroupWithModifiers;
+use crate::entities::prelude::{Categories, Category, Dish, DishVariant};
+use crate::error::AppResult;
+use salvo::http::StatusError;
+use crate::utils::money::Money;
 
 #[derive(Deserialize, Debug, ToSchema, Default)]
 pub struct CreateCategoryData {
@@ -15,30 +23,147 @@ pub struct CreateCategoryData {
 #[derive(Deserialize, Debug, ToSchema, Default)]
 pub struct CreateDishData {
     pub name: String,
-    pub price:f64,
+    pub price: Money,
     pub picture:String,
+    pub station:Option<String>,
     pub category_ids: Vec<String>,
+    /// 不填规格时使用price作为唯一价格，填了规格时price会被第一个规格的价格覆盖
+    #[serde(default)]
+    pub variants: Vec<CreateVariantData>,
+    /// 填了组成部分的菜品是套餐，price是套餐价
+    #[serde(default)]
+    pub combo_slots: Vec<CreateComboSlotData>,
+}
+
+/// 批量排序，ids为排好序的全部id
+#[derive(Deserialize, Debug, ToSchema, Default)]
+pub struct ReorderData {
+    pub ids: Vec<String>,
+}
+
+/// 修改分类，只修改填了的字段，填了dish_ids时替换分类下的全部菜品
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateCategoryData {
+    pub name: Option<String>,
+    pub dish_ids: Option<Vec<String>>,
+}
+
+/// 整体替换分类，所有字段都必须填写，dish_ids为分类下的全部菜品
+#[derive(Deserialize, Debug)]
+pub struct ReplaceCategoryData {
+    pub name: String,
+    pub dish_ids: Vec<String>,
+}
+impl From<ReplaceCategoryData> for UpdateCategoryData {
+    fn from(data: ReplaceCategoryData) -> Self {
+        UpdateCategoryData { name: Some(data.name), dish_ids: Some(data.dish_ids) }
+    }
+}
+
+/// 修改菜品，只修改填了的字段，填了category_ids时替换菜品所属的全部分类
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateDishData {
+    pub name: Option<String>,
+    /// 有规格的菜品不能直接改价格，要通过variants修改
+    pub price: Option<Money>,
+    pub picture: Option<String>,
+    /// 空字符串表示不再指定档口
+    pub station: Option<String>,
+    pub status: Option<dish::Status>,
+    pub category_ids: Option<Vec<String>>,
+    /// 替换全部规格，不为空时菜品价格改为第一个规格的价格，不能同时填price
+    pub variants: Option<Vec<VariantData>>,
+}
+
+/// 整体替换菜品，除station外所有字段都必须填写，没有规格时variants为空
+#[derive(Deserialize, Debug)]
+pub struct ReplaceDishData {
+    pub name: String,
+    /// 有规格时必须等于第一个规格的价格
+    pub price: Money,
+    pub picture: String,
+    /// 不填表示不指定档口
+    pub station: Option<String>,
+    pub status: dish::Status,
+    pub category_ids: Vec<String>,
+    pub variants: Vec<VariantData>,
+}
+
+#[derive(Deserialize, Debug, ToSchema, Default)]
+pub struct CreateVariantData {
+    pub name: String,
+    pub price: Money,
+}
+impl From<CreateVariantData> for VariantData {
+    fn from(data: CreateVariantData) -> Self {
+        VariantData { name: data.name, price: data.price, status: dish::Status::Normal }
+    }
+}
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VariantData {
+    pub name: String,
+    pub price: Money,
+    #[serde(default = "default_status")]
+    pub status: dish::Status,
+}
+
+#[derive(Debug,Deserialize,Serialize)]
+pub struct DishDetail{
+    #[serde(flatten)]
+    pub dish: Dish,
+    pub variants: Vec<DishVariant>,
+    pub modifier_groups: Vec<ModifierGroupWithModifiers>,
+    pub combo_slots: Vec<ComboSlotDetail>, // 不为空时是套餐
+    pub available: bool, // 已下架或者有原料用完时为false
+    pub available_now: bool, // 当前是否在供应时段内
 }
 
 #[derive(Debug,Deserialize,Serialize)]
 pub struct CategoryWithDishes{
     pub category: Category,
-    pub dish: Vec<Dish>,
+    pub available_now: bool,
+    pub dish: Vec<DishDetail>,
+}
+
+/// 管理端查看的分类，带上当前是否在供应时段内
+#[derive(Debug,Serialize)]
+pub struct CategoryDetail{
+    #[serde(flatten)]
+    pub category: Category,
+    pub available_now: bool,
 }
 
-pub async fn query_menu()->Vec<CategoryWithDishes>{
+/// 查询菜单，all为false时只返回当前供应时段内的分类和菜品
+pub async fn query_menu(all: bool)->Vec<CategoryWithDishes>{
     let db = get_db_coon();
     let categories = Categories::find()
+        .order_by_asc(category::Column::Index)
         .all(db)
         .await
         .unwrap();
+    let availability = match Availability::load(db).await {
+        Ok(availability) => availability,
+        Err(e) => {
+            error!("query menu error: {}", e);
+            return Vec::new();
+        }
+    };
     let mut result = Vec::new();
     for category in categories{
-        match category.find_linked(CategoryToDish).all(db).await{
+        let available_now = availability.category_open(&category.id);
+        if !all && !available_now {
+            continue;
+        }
+        let dishes = match CategoryDishMapCurd::query_dishes(db, category.id.clone()).await {
+            Ok(dishes) => DishCurd::with_details(dishes).await,
+            Err(e) => Err(e),
+        };
+        match dishes{
             Ok(dishes) => {
                 result.push(CategoryWithDishes{
                     category,
-                    dish: dishes,
+                    available_now,
+                    dish: dishes.into_iter().filter(|dish| all || dish.available_now).collect(),
                 });
             },
             Err(e) => {
@@ -47,4 +172,171 @@ pub async fn query_menu()->Vec<CategoryWithDishes>{
         }
     };
     result
-}
\ No newline at end of file
+}
+/// 菜单导入导出的数据，用名称而不是id关联，方便在不同门店之间复制
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MenuData {
+    #[serde(default)]
+    pub categories: Vec<MenuCategory>,
+    #[serde(default)]
+    pub dishes: Vec<MenuDish>,
+}
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MenuCategory {
+    pub name: String,
+}
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MenuDish {
+    pub name: String,
+    /// 有规格时必须等于第一个规格的价格
+    pub price: Money,
+    #[serde(default)]
+    pub picture: String,
+    #[serde(default = "default_status")]
+    pub status: dish::Status,
+    #[serde(default)]
+    pub station: Option<String>,
+    /// 所属分类的名称
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub variants: Vec<VariantData>,
+}
+fn default_status() -> dish::Status {
+    dish::Status::Normal
+}
+
+/// csv的一行，分类和菜品放在同一个表格里，用kind区分
+/// 菜品的多个分类用`|`分隔，规格写成`名称:价格`或者`名称:价格:Delist`，多个规格也用`|`分隔
+#[derive(Debug, Deserialize, Serialize)]
+struct MenuCsvRow {
+    kind: MenuKind,
+    name: String,
+    price: Option<Money>,
+    picture: Option<String>,
+    status: Option<dish::Status>,
+    station: Option<String>,
+    categories: Option<String>,
+    variants: Option<String>,
+}
+impl MenuData {
+    pub fn to_csv(&self) -> AppResult<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for category in &self.categories {
+            writer.serialize(MenuCsvRow {
+                kind: MenuKind::Category,
+                name: category.name.clone(),
+                price: None,
+                picture: None,
+                status: None,
+                station: None,
+                categories: None,
+                variants: None,
+            }).map_err(|e| anyhow::anyhow!("menu csv error: {}", e))?;
+        }
+        for dish in &self.dishes {
+            writer.serialize(MenuCsvRow {
+                kind: MenuKind::Dish,
+                name: dish.name.clone(),
+                price: Some(dish.price),
+                picture: Some(dish.picture.clone()),
+                status: Some(dish.status.clone()),
+                station: dish.station.clone(),
+                categories: Some(dish.categories.join("|")),
+                variants: Some(dish.variants.iter().map(VariantData::to_csv).collect::<Vec<_>>().join("|")),
+            }).map_err(|e| anyhow::anyhow!("menu csv error: {}", e))?;
+        }
+        let bytes = writer.into_inner().map_err(|e| anyhow::anyhow!("menu csv error: {}", e))?;
+        Ok(String::from_utf8(bytes).map_err(|e| anyhow::anyhow!("menu csv error: {}", e))?)
+    }
+    pub fn from_csv(text: &str) -> AppResult<Self> {
+        let mut menu = MenuData::default();
+        for (line, row) in csv::Reader::from_reader(text.as_bytes()).deserialize::<MenuCsvRow>().enumerate() {
+            //第一行是表头
+            let row = row.map_err(|e| {
+                StatusError::bad_request().brief(format!("第{}行格式错误: {}", line + 2, e))
+            })?;
+            match row.kind {
+                MenuKind::Category => menu.categories.push(MenuCategory { name: row.name }),
+                MenuKind::Dish => {
+                    let Some(price) = row.price else {
+                        return Err(StatusError::bad_request()
+                            .brief(format!("第{}行菜品没有价格", line + 2))
+                            .into());
+                    };
+                    let variants = row
+                        .variants
+                        .iter()
+                        .flat_map(|variants| variants.split('|'))
+                        .filter(|variant| !variant.trim().is_empty())
+                        .map(VariantData::from_csv)
+                        .collect::<Option<Vec<_>>>();
+                    let Some(variants) = variants else {
+                        return Err(StatusError::bad_request()
+                            .brief(format!("第{}行规格格式错误，应为 名称:价格", line + 2))
+                            .into());
+                    };
+                    menu.dishes.push(MenuDish {
+                        name: row.name,
+                        price,
+                        picture: row.picture.unwrap_or_default(),
+                        status: row.status.unwrap_or_else(default_status),
+                        station: row.station,
+                        categories: row
+                            .categories
+                            .map(|names| names.split('|').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect())
+                            .unwrap_or_default(),
+                        variants,
+                    });
+                }
+            }
+        }
+        Ok(menu)
+    }
+}
+
+impl VariantData {
+    fn to_csv(&self) -> String {
+        match self.status {
+            dish::Status::Normal => format!("{}:{}", self.name, self.price),
+            dish::Status::Delist => format!("{}:{}:Delist", self.name, self.price),
+        }
+    }
+    fn from_csv(text: &str) -> Option<Self> {
+        let mut parts = text.split(':').map(str::trim);
+        let name = parts.next()?.to_string();
+        let price = parts.next()?.parse().ok()?;
+        let status = match parts.next() {
+            None | Some("Normal") => dish::Status::Normal,
+            Some("Delist") => dish::Status::Delist,
+            Some(_) => return None,
+        };
+        Some(VariantData { name, price, status })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum MenuKind {
+    Category,
+    Dish,
+}
+#[derive(Debug, Serialize)]
+pub struct ImportEntry {
+    pub kind: MenuKind,
+    pub name: String,
+}
+#[derive(Debug, Serialize)]
+pub struct RejectedEntry {
+    pub kind: MenuKind,
+    pub name: String,
+    pub reason: String,
+}
+/// 导入结果，有被拒绝的条目时整个导入都不会生效
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub applied: bool,
+    pub created: Vec<ImportEntry>,
+    pub updated: Vec<ImportEntry>,
+    pub rejected: Vec<RejectedEntry>,
+}
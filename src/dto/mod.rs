@@ -1,10 +1,18 @@
-use log::error;
-use salvo::oapi::ToSchema;
-use sea_orm::{EntityTrait, ModelTrait};
-use serde::{Deserialize, Serialize};
-use crate::config::db::get_db_coon;
-use crate::entities::category_dish_map::CategoryToDish;
-use crate::entities::prelude::{Categories, Category, Dish};
-
 pub mod user;
-pub mod menu;
\ No newline at end of file
+pub mod menu;
+pub mod modifier;
+pub mod combo;
+pub mod order;
+pub mod dining_table;
+pub mod cart;
+pub mod bill_split;
+pub mod payment;
+pub mod adjustment;
+pub mod print;
+pub mod report;
+pub mod stock;
+pub mod ingredient;
+pub mod schedule;
+pub mod pricing;
+pub mod promotion;
+pub mod member;
\ No newline at end of file
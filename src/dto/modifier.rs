@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::prelude::{Modifier, ModifierGroup, ModifierGroupLink};
+use crate::utils::money::Money;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct CreateModifierGroupData {
+    #[validate(length(min = 1, message = "name can not be empty"))]
+    pub name: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    #[validate(range(min = 0, message = "min_select can not be negative"))]
+    pub min_select: i32,
+    #[validate(range(min = 1, message = "max_select must be greater than 0"))]
+    pub max_select: i32,
+    #[validate(length(min = 1, message = "modifiers can not be empty"))]
+    pub modifiers: Vec<CreateModifierData>,
+    #[serde(default)]
+    pub dish_ids: Vec<String>,
+    #[serde(default)]
+    pub category_ids: Vec<String>,
+}
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CreateModifierData {
+    pub name: String,
+    #[serde(default)]
+    pub price_delta: Money,
+}
+
+/// 菜单上展示的选项分组
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModifierGroupWithModifiers {
+    #[serde(flatten)]
+    pub group: ModifierGroup,
+    pub modifiers: Vec<Modifier>,
+}
+/// 管理端查看的选项分组，带上挂载的菜品和分类
+#[derive(Serialize, Debug)]
+pub struct ModifierGroupDetail {
+    #[serde(flatten)]
+    pub group: ModifierGroupWithModifiers,
+    pub links: Vec<ModifierGroupLink>,
+}
@@ -0,0 +1,91 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::combo_slot::ComboComponent;
+use crate::entities::order::{Channel, Status};
+use crate::entities::order_item::Status as ItemStatus;
+use crate::entities::prelude::{Order, OrderItem};
+use crate::utils::money::Money;
+
+#[derive(Deserialize, Debug, Validate, ToSchema, Default)]
+pub struct CreateOrderData {
+    // 服务员替某一桌下单时填写
+    pub table_session_id: Option<String>,
+    /// 不填时有桌台会话的是堂食，否则是外带
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    /// 顾客出示的优惠券码
+    #[serde(default)]
+    pub coupon_code: Option<String>,
+    /// 顾客出示的会员，可以用手机号查出会员id
+    #[serde(default)]
+    pub member_id: Option<String>,
+    /// 用来抵扣的积分，1积分抵扣1分钱，超过应付金额的部分不会扣除
+    #[serde(default)]
+    #[validate(range(min = 0, message = "redeem_points can not be negative"))]
+    pub redeem_points: i64,
+    #[validate(length(min = 1, message = "order must contain at least one item"), nested)]
+    pub items: Vec<OrderItemData>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema, Default)]
+pub struct OrderItemData {
+    pub dish_id: String,
+    /// 菜品有规格时必填
+    #[serde(default)]
+    pub variant_id: Option<String>,
+    /// 选择的加料和口味
+    #[serde(default)]
+    pub modifier_ids: Vec<String>,
+    /// 套餐每个部分选择的菜品
+    #[serde(default)]
+    pub components: Vec<ComboComponent>,
+    #[validate(range(min = 1, max = 9999, message = "quantity must be between 1 and 9999"))]
+    pub quantity: i32,
+}
+
+/// 顾客扫码下单，桌台由令牌决定
+#[derive(Deserialize, Debug, Validate, ToSchema, Default)]
+pub struct CustomerOrderData {
+    #[serde(default)]
+    pub coupon_code: Option<String>,
+    /// 会员登录后拿到的令牌，用来积分和抵扣
+    #[serde(default)]
+    pub member_token: Option<String>,
+    #[serde(default)]
+    #[validate(range(min = 0, message = "redeem_points can not be negative"))]
+    pub redeem_points: i64,
+    #[validate(length(min = 1, message = "order must contain at least one item"), nested)]
+    pub items: Vec<OrderItemData>,
+}
+
+/// 下单时除了菜品以外的选项
+#[derive(Debug, Default)]
+pub struct OrderOptions {
+    pub channel: Option<Channel>,
+    pub coupon_code: Option<String>,
+    pub member_id: Option<String>,
+    pub redeem_points: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CreateOrderResult {
+    pub id: String,
+    pub total: Money,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransitionOrderData {
+    pub status: Status,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransitionItemData {
+    pub status: ItemStatus,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OrderWithItems {
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+}
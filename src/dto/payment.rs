@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::payment::Method;
+use crate::utils::money::Money;
+
+/// 一次结账可以包含多种支付方式，例如一部分现金一部分微信
+#[derive(Deserialize, Debug, Validate)]
+pub struct PayData {
+    #[validate(length(min = 1, message = "at least one tender is required"))]
+    pub tenders: Vec<TenderData>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TenderData {
+    pub method: Method,
+    pub amount: Money,
+    pub bill_split_id: Option<String>, // 支付的是某一份分单时填写
+}
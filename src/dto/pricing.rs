@@ -0,0 +1,25 @@
+use serde::Deserialize;
+use validator::Validate;
+use crate::entities::order::Channel;
+use crate::entities::pricing_rule::Kind;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct PricingRuleData {
+    #[validate(length(min = 1, message = "rule name can not be empty"))]
+    pub name: String,
+    pub kind: Kind,
+    /// 费率，单位为万分之一，1000表示10%
+    #[validate(range(min = 0, max = 10000, message = "rate must be between 0 and 10000"))]
+    pub rate: i32,
+    #[serde(default)]
+    pub inclusive: bool,
+    #[validate(length(min = 1, message = "channels can not be empty"))]
+    pub channels: Vec<Channel>,
+    #[serde(default)]
+    pub exempt_category_ids: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+fn default_enabled() -> bool {
+    true
+}
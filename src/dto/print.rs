@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+/// 后厨单的纯文本预览
+#[derive(Serialize, Debug)]
+pub struct KitchenTicketPreview {
+    pub station: Option<String>,
+    pub text: String,
+}
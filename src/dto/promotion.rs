@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use validator::Validate;
+use crate::entities::promotion::{Kind, Target};
+use crate::utils::money::{non_negative, Money};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct PromotionData {
+    #[validate(length(min = 1, message = "promotion name can not be empty"))]
+    pub name: String,
+    pub kind: Kind,
+    pub target: Target,
+    #[serde(default)]
+    pub target_ids: Vec<String>,
+    /// 打折时减掉的比例，单位为万分之一，2000表示8折
+    #[serde(default)]
+    #[validate(range(min = 0, max = 10000, message = "rate must be between 0 and 10000"))]
+    pub rate: i32,
+    #[serde(default)]
+    #[validate(custom(function = "non_negative", message = "amount can not be negative"))]
+    pub amount: Money,
+    #[serde(default)]
+    #[validate(range(min = 0, message = "buy_quantity can not be negative"))]
+    pub buy_quantity: i32,
+    #[serde(default)]
+    #[validate(range(min = 0, message = "free_quantity can not be negative"))]
+    pub free_quantity: i32,
+    #[serde(default)]
+    #[validate(custom(function = "non_negative", message = "min_spend can not be negative"))]
+    pub min_spend: Money,
+    /// 形如2023-07-01的营业日
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub end_date: Option<String>,
+    #[serde(default)]
+    #[validate(range(min = 1, message = "usage_limit must be greater than 0"))]
+    pub usage_limit: Option<i32>,
+    #[serde(default)]
+    pub coupon_only: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+#[derive(Deserialize, Debug, Validate)]
+pub struct CouponData {
+    #[validate(length(min = 1, max = 32, message = "code length must be between 1 and 32"))]
+    pub code: String,
+    pub promotion_id: String,
+    #[serde(default)]
+    #[validate(range(min = 1, message = "usage_limit must be greater than 0"))]
+    pub usage_limit: Option<i32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+fn default_enabled() -> bool {
+    true
+}
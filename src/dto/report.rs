@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use crate::entities::prelude::ZReport;
+use crate::entities::z_report::PaymentSummary;
+use crate::utils::money::Money;
+
+/// 营业日报表，已日结的营业日返回日结时固定下来的数据
+#[derive(Serialize, Debug)]
+pub struct DailyReport {
+    pub business_date: String,
+    pub closed: bool,
+    pub gross_sales: Money,
+    pub voids: Money,
+    pub discounts: Money,
+    pub refunds: Money,
+    pub net_sales: Money,
+    pub service_charges: Money,
+    pub taxes: Money,
+    pub order_count: i32,
+    pub average_ticket: Money,
+    pub payments: Vec<PaymentSummary>,
+    pub open_orders: i32, // 还没有结账的订单数，日结前必须为0
+    pub closed_at: Option<String>,
+}
+impl From<ZReport> for DailyReport {
+    fn from(report: ZReport) -> Self {
+        DailyReport {
+            business_date: report.business_date,
+            closed: true,
+            gross_sales: report.gross_sales,
+            voids: report.voids,
+            discounts: report.discounts,
+            refunds: report.refunds,
+            net_sales: report.net_sales,
+            service_charges: report.service_charges,
+            taxes: report.taxes,
+            order_count: report.order_count,
+            average_ticket: report.average_ticket,
+            payments: report.payments.0,
+            open_orders: 0,
+            closed_at: Some(report.closed_at),
+        }
+    }
+}
+
+/// 日结，不填日期时日结当前营业日
+#[derive(Deserialize, Debug)]
+pub struct CloseDayData {
+    pub date: Option<String>,
+}
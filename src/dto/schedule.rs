@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::prelude::{ScheduleException, ScheduleWindow};
+
+/// 替换分类或菜品的全部供应时段和例外日期，都为空时恢复全天供应
+#[derive(Deserialize, Debug, Validate, Default)]
+pub struct ScheduleData {
+    #[serde(default)]
+    #[validate(nested)]
+    pub windows: Vec<WindowData>,
+    #[serde(default)]
+    pub exceptions: Vec<ExceptionData>,
+}
+#[derive(Deserialize, Debug, Validate)]
+pub struct WindowData {
+    #[validate(length(min = 1, message = "days can not be empty"))]
+    pub days: Vec<u8>,
+    pub start_time: String,
+    pub end_time: String,
+}
+#[derive(Deserialize, Debug)]
+pub struct ExceptionData {
+    pub start_date: String,
+    pub end_date: String,
+    pub available: bool,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduleView {
+    pub windows: Vec<ScheduleWindow>,
+    pub exceptions: Vec<ScheduleException>,
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::entities::prelude::Stock;
+use crate::entities::stock_movement::Reason;
+
+/// 开始管理库存或者盘点，直接设置库存数量和提醒阈值
+#[derive(Deserialize, Debug, Validate)]
+pub struct SetStockData {
+    pub dish_id: String,
+    #[serde(default)]
+    pub variant_id: Option<String>,
+    #[validate(range(min = 0, message = "quantity can not be negative"))]
+    pub quantity: i32,
+    #[serde(default)]
+    #[validate(range(min = 0, message = "low_threshold can not be negative"))]
+    pub low_threshold: i32,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// 补货、损耗等手动调整，delta为正数是增加
+#[derive(Deserialize, Debug)]
+pub struct AdjustStockData {
+    pub delta: i32,
+    pub reason: Reason,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StockDetail {
+    #[serde(flatten)]
+    pub stock: Stock,
+    pub dish_name: String,
+    pub variant_name: Option<String>,
+    pub low: bool, // 库存不高于提醒阈值
+}
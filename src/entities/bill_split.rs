@@ -0,0 +1,61 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 分单后的一份账单，每一份可以单独支付
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bill_split")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub order_id: String,
+    pub index: i32, // 第几份，从0开始
+    pub mode: Mode,
+    pub payer: String, // 付款人的称呼，平均分单时为空
+    pub amount: Money,
+    pub status: Status,
+    pub paid_at: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Mode {
+    #[sea_orm(string_value = "even")]
+    Even, // 平均分成N份
+    #[sea_orm(string_value = "item")]
+    Item, // 按菜品分给不同的人
+    #[sea_orm(string_value = "custom")]
+    Custom, // 自定义金额
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "unpaid")]
+    Unpaid, // 未支付
+    #[sea_orm(string_value = "paid")]
+    Paid, // 已支付
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id"
+    )]
+    Order,
+    #[sea_orm(has_many = "super::bill_split_item::Entity")]
+    BillSplitItem,
+}
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+impl Related<super::bill_split_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BillSplitItem.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
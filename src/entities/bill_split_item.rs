@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 按菜品分单时，每一份包含的订单项
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bill_split_item")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub bill_split_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub order_item_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::bill_split::Entity",
+        from = "Column::BillSplitId",
+        to = "super::bill_split::Column::Id"
+    )]
+    BillSplit,
+    #[sea_orm(
+        belongs_to = "super::order_item::Entity",
+        from = "Column::OrderItemId",
+        to = "super::order_item::Column::Id"
+    )]
+    OrderItem,
+}
+impl Related<super::bill_split::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BillSplit.def()
+    }
+}
+impl Related<super::order_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderItem.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,58 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::entities::combo_slot::ComboComponent;
+
+/// 桌台会话共享的购物车，同一桌的客人各自用手机往里加菜，最后由其中一人提交成订单
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "cart_item")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub table_session_id: String,
+    pub dish_id: String,
+    pub variant_id: Option<String>, // 菜品有规格时必填
+    #[sea_orm(column_type = "Json")]
+    pub modifier_ids: ModifierIds,
+    #[sea_orm(column_type = "Json")]
+    pub components: ComboComponents, // 点套餐时每个部分选择的菜品
+    pub quantity: i32,
+    pub added_by: String, // 加菜的客人昵称
+    pub created_at: String,
+}
+
+/// 选择的加料和口味选项id
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ModifierIds(pub Vec<String>);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ComboComponents(pub Vec<ComboComponent>);
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::table_session::Entity",
+        from = "Column::TableSessionId",
+        to = "super::table_session::Column::Id"
+    )]
+    TableSession,
+    #[sea_orm(
+        belongs_to = "super::dish::Entity",
+        from = "Column::DishId",
+        to = "super::dish::Column::Id"
+    )]
+    Dish,
+}
+impl Related<super::table_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TableSession.def()
+    }
+}
+impl Related<super::dish::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dish.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -4,7 +4,7 @@ use sea_orm::DerivePrimaryKey;
 use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
 use serde::{Deserialize, Serialize};
 use crate::entities::category_dish_map;
-use crate::entities::prelude::{Categories, Category, Dish, Dishes};
+use crate::entities::prelude::{Categories, Dishes};
 
 #[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "category_dish_map")]
@@ -13,6 +13,7 @@ pub struct Model {
     pub category_id: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub dish_id: String,
+    pub position: i32, // 菜品在这个分类里的位置，同一个菜品在不同分类里可以排在不同位置
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -43,19 +44,7 @@ impl Related<super::dish::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
-pub struct CategoryToDish;
-impl Linked for CategoryToDish {
-    type FromEntity = Categories;
-    type ToEntity = Dishes;
-
-    fn link(&self) -> Vec<RelationDef> {
-        vec![
-            category_dish_map::Relation::Category.def().rev(),
-            category_dish_map::Relation::Dish.def(),
-        ]
-    }
-}
-
+#[allow(dead_code)]
 pub struct DishToCategory;
 impl Linked for DishToCategory {
     type FromEntity = Dishes;
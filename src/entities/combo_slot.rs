@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// 套餐的一个组成部分，例如 主食、小菜、饮品，点套餐时每个部分选一个菜品
+/// 可选的菜品来自挂在这里的分类和单独列出的菜品，单独列出的菜品可以设置加价
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "combo_slot")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub combo_id: String, // 套餐本身也是一个菜品，价格就是套餐价
+    pub index: i32,
+    pub name: String,
+    pub category_id: Option<String>,
+}
+
+/// 下单时给套餐的一个部分选择的菜品
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ComboComponent {
+    pub slot_id: String,
+    pub dish_id: String,
+    #[serde(default)]
+    pub variant_id: Option<String>,
+    #[serde(default)]
+    pub modifier_ids: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dish::Entity",
+        from = "Column::ComboId",
+        to = "super::dish::Column::Id"
+    )]
+    Dish,
+}
+impl Related<super::dish::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dish.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
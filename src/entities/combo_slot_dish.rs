@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 套餐部分里单独列出的可选菜品，选择这个菜品时套餐价加上upcharge
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "combo_slot_dish")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub slot_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub dish_id: String,
+    pub upcharge: Money,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::combo_slot::Entity",
+        from = "Column::SlotId",
+        to = "super::combo_slot::Column::Id"
+    )]
+    ComboSlot,
+}
+impl Related<super::combo_slot::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ComboSlot.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// 餐桌
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dining_table")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub area: String, // 区域，例如 大厅、包间
+    pub capacity: i32,
+    pub status: Status,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter, ToSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "idle")]
+    Idle, // 空闲
+    #[sea_orm(string_value = "occupied")]
+    Occupied, // 有客人就座
+    #[sea_orm(string_value = "disabled")]
+    Disabled, // 停用
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::table_session::Entity")]
+    TableSession,
+}
+impl Related<super::table_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TableSession.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
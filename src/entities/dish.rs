@@ -1,5 +1,6 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
 
 #[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "dish")]
@@ -9,9 +10,10 @@ pub struct Model {
     pub index: i32,
     #[sea_orm(unique)]
     pub name: String,
-    pub price:f64,
+    pub price: Money, // 有规格时为默认(第一个)规格的价格
     pub picture:String,
     pub status:Status,
+    pub station:Option<String>, // 负责制作的后厨档口，例如 热菜、凉菜、饮品
     pub created_at:String,
 }
 
@@ -27,11 +29,18 @@ pub enum Status {
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::category_dish_map::Entity")]
-    CategoryDishMap
+    CategoryDishMap,
+    #[sea_orm(has_many = "super::dish_variant::Entity")]
+    DishVariant,
 }
 impl Related<super::category_dish_map::Entity> for Entity {
     fn to() -> RelationDef {
         crate::entities::category::Relation::CategoryDishMap.def()
     }
 }
+impl Related<super::dish_variant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DishVariant.def()
+    }
+}
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entities::dish::Status;
+use crate::utils::money::Money;
+
+/// 菜品规格，例如 小份/大份、半份/整份，每个规格有自己的价格和上下架状态
+/// 没有规格的菜品相当于只有一个默认规格，直接使用菜品上的价格，订单项的variant_id为空
+/// 有规格时菜品上的价格始终等于第一个规格的价格，见[crate::das::dish::DishCurd::replace_variants]
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dish_variant")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub dish_id: String,
+    pub index: i32,
+    pub name: String,
+    pub price: Money,
+    pub status: Status,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dish::Entity",
+        from = "Column::DishId",
+        to = "super::dish::Column::Id"
+    )]
+    Dish,
+}
+impl Related<super::dish::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dish.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
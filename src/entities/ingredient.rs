@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 原料，例如 猪肉(kg)、鸡蛋(个)，下单时按菜品的配方扣减原料库存
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ingredient")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub unit: String,
+    pub quantity: f64, // 当前库存，单位为unit
+    pub unit_cost: Money, // 每个单位的进价
+    pub low_threshold: f64, // 库存不高于这个数时提醒补货
+}
+impl Model {
+    pub fn is_low(&self) -> bool {
+        self.quantity <= self.low_threshold
+    }
+}
+
+/// 原料数量保留三位小数，避免浮点数累加误差
+pub fn round_quantity(quantity: f64) -> f64 {
+    (quantity * 1000.0).round() / 1000.0
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::recipe_line::Entity")]
+    RecipeLine,
+}
+impl Related<super::recipe_line::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecipeLine.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::ingredient::round_quantity;
+
+    #[test]
+    fn test_round_quantity() {
+        assert_eq!(round_quantity(0.1 + 0.2), 0.3);
+        assert_eq!(round_quantity(1.0 - 0.15 * 3.0), 0.55);
+    }
+}
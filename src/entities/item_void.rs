@@ -0,0 +1,52 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 支付前退菜的记录，订单项不会被删除，只累加已退数量
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "item_void")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub order_id: String,
+    pub order_item_id: String,
+    pub quantity: i32,
+    pub amount: Money,
+    pub reason: ReasonCode,
+    pub note: String,
+    pub uid: String, // 操作人id
+    pub approved_by: Option<String>, // 审批经理的id
+    pub created_at: String,
+}
+
+/// 退菜、退款的原因
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum ReasonCode {
+    #[sea_orm(string_value = "wrong_dish")]
+    WrongDish, // 上错菜
+    #[sea_orm(string_value = "customer_complaint")]
+    CustomerComplaint, // 顾客投诉
+    #[sea_orm(string_value = "quality_issue")]
+    QualityIssue, // 菜品质量问题
+    #[sea_orm(string_value = "out_of_stock")]
+    OutOfStock, // 沽清
+    #[sea_orm(string_value = "other")]
+    Other, // 其他，需要在备注中说明
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id"
+    )]
+    Order,
+}
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -6,4 +6,33 @@ pub mod users;
 pub mod table;
 pub mod category;
 pub mod dish;
+pub mod dish_variant;
+pub mod modifier_group;
+pub mod modifier;
+pub mod modifier_group_link;
+pub mod combo_slot;
+pub mod combo_slot_dish;
 pub mod category_dish_map;
+pub mod order;
+pub mod order_item;
+pub mod order_status_history;
+pub mod dining_table;
+pub mod table_session;
+pub mod cart_item;
+pub mod bill_split;
+pub mod bill_split_item;
+pub mod payment;
+pub mod item_void;
+pub mod refund;
+pub mod z_report;
+pub mod stock;
+pub mod stock_movement;
+pub mod ingredient;
+pub mod recipe_line;
+pub mod schedule_window;
+pub mod schedule_exception;
+pub mod pricing_rule;
+pub mod promotion;
+pub mod coupon;
+pub mod member;
+pub mod points_entry;
@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entities::dish::Status;
+use crate::utils::money::Money;
+
+/// 分组中的一个选项，例如 微辣、不要香菜、加蛋(+2元)
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "modifier")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub group_id: String,
+    pub index: i32,
+    pub name: String,
+    pub price_delta: Money, // 加价，可以为0
+    pub status: Status,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::modifier_group::Entity",
+        from = "Column::GroupId",
+        to = "super::modifier_group::Column::Id"
+    )]
+    ModifierGroup,
+}
+impl Related<super::modifier_group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ModifierGroup.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,64 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 加料和口味选项的分组，例如 辣度(必选，选1个)、加料(可选，最多选3个)
+/// 可以挂在单个菜品上，也可以挂在整个分类上
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "modifier_group")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub index: i32,
+    pub name: String,
+    pub required: bool, // 必选时至少选择max(min_select, 1)个
+    pub min_select: i32, // 可选的分组不选，或者至少选择这么多个
+    pub max_select: i32,
+}
+impl Model {
+    /// 检查选择的数量，返回不合法的原因
+    pub fn check_count(&self, count: i32) -> Result<(), String> {
+        if count > self.max_select {
+            return Err(format!("{}最多选择{}个", self.name, self.max_select));
+        }
+        let min = if self.required { self.min_select.max(1) } else { self.min_select };
+        if (self.required || count > 0) && count < min {
+            return Err(format!("{}至少选择{}个", self.name, min));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::modifier::Entity")]
+    Modifier,
+}
+impl Related<super::modifier::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Modifier.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use super::Model;
+
+    #[test]
+    fn test_check_count() {
+        let group = |required, min_select, max_select| Model {
+            id: String::new(),
+            index: 0,
+            name: "test".to_string(),
+            required,
+            min_select,
+            max_select,
+        };
+        assert!(group(true, 0, 1).check_count(0).is_err());
+        assert!(group(true, 0, 1).check_count(1).is_ok());
+        assert!(group(true, 0, 1).check_count(2).is_err());
+        assert!(group(false, 2, 3).check_count(0).is_ok());
+        assert!(group(false, 2, 3).check_count(1).is_err());
+        assert!(group(false, 2, 3).check_count(3).is_ok());
+    }
+}
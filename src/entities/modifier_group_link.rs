@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 选项分组挂在哪些菜品或分类上，挂在分类上时分类下的所有菜品都可以选
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "modifier_group_link")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub group_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub target: Target,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub target_id: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Target {
+    #[sea_orm(string_value = "dish")]
+    Dish,
+    #[sea_orm(string_value = "category")]
+    Category,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::modifier_group::Entity",
+        from = "Column::GroupId",
+        to = "super::modifier_group::Column::Id"
+    )]
+    ModifierGroup,
+}
+impl Related<super::modifier_group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ModifierGroup.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
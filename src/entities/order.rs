@@ -0,0 +1,155 @@
+use sea_orm::entity::prelude::*;
+use salvo::oapi::ToSchema;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::entities::pricing_rule::Kind;
+use crate::error::{AppError, AppResult};
+use crate::utils::money::Money;
+
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub uid: String, // 下单的用户id，顾客扫码下单时为桌台会话id
+    pub table_session_id: Option<String>,
+    pub channel: Channel,
+    pub subtotal: Money, // 菜品金额合计
+    pub discount: Money, // 优惠金额合计
+    #[sea_orm(column_type = "Json")]
+    pub discounts: AppliedDiscounts, // 参加的促销活动和优惠金额
+    pub member_id: Option<String>,
+    pub points_redeemed: i64, // 抵扣的积分，抵扣的金额计入discount
+    pub points_earned: i64, // 支付后获得的积分
+    pub service_charge: Money,
+    pub tax: Money, // 税额，包括菜价中已含的税
+    #[sea_orm(column_type = "Json")]
+    pub charges: AppliedCharges, // 下单时适用的服务费和税的规则及金额
+    pub total: Money, // 应付金额 = 菜品金额 - 优惠 + 服务费 + 价外税，退菜后会重新计算
+    pub refunded: Money, // 已退款金额
+    pub status: Status,
+    pub created_at: String,
+}
+
+/// 订单渠道，服务费和税的规则按渠道适用
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter, ToSchema)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Channel {
+    #[sea_orm(string_value = "dine_in")]
+    DineIn, // 堂食
+    #[sea_orm(string_value = "takeaway")]
+    Takeaway, // 外带
+    #[sea_orm(string_value = "delivery")]
+    Delivery, // 外卖
+}
+impl Channel {
+    /// 没有指定渠道时，有桌台会话的是堂食，否则是外带
+    pub fn default_for(table_session_id: Option<&String>) -> Channel {
+        if table_session_id.is_some() { Channel::DineIn } else { Channel::Takeaway }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct AppliedCharges(pub Vec<AppliedCharge>);
+/// 订单上的一项服务费或税，保存规则的副本，退菜后按同样的规则重新计算
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppliedCharge {
+    pub rule_id: String,
+    pub name: String,
+    pub kind: Kind,
+    pub rate: i32,
+    pub inclusive: bool,
+    pub exempt_category_ids: Vec<String>,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct AppliedDiscounts(pub Vec<AppliedDiscount>);
+/// 订单参加的一个促销活动
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AppliedDiscount {
+    pub promotion_id: String,
+    pub coupon_id: Option<String>,
+    pub name: String,
+    pub explanation: String, // 给顾客看的优惠说明，例如 满100.00减20.00
+    pub amount: Money,
+}
+
+/// 订单状态: pending → accepted → cooking → served → paid → closed
+/// 只有pending和accepted可以取消
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "pending")]
+    Pending, // 待接单
+    #[sea_orm(string_value = "accepted")]
+    Accepted, // 已接单
+    #[sea_orm(string_value = "cooking")]
+    Cooking, // 制作中
+    #[sea_orm(string_value = "served")]
+    Served, // 已上菜
+    #[sea_orm(string_value = "paid")]
+    Paid, // 已支付
+    #[sea_orm(string_value = "closed")]
+    Closed, // 已关闭
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled, // 已取消
+}
+impl Status {
+    pub fn can_transition_to(self, next: Status) -> bool {
+        use Status::*;
+        matches!(
+            (self, next),
+            (Pending, Accepted)
+                | (Accepted, Cooking)
+                | (Cooking, Served)
+                | (Served, Paid)
+                | (Paid, Closed)
+                | (Pending | Accepted, Cancelled)
+        )
+    }
+    /// 所有状态变更都必须经过这里，非法的流转返回409
+    pub fn transition(self, next: Status) -> AppResult<Status> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(AppError::conflict(format!("订单状态不能从{:?}变为{:?}", self, next)))
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::order_item::Entity")]
+    OrderItem,
+    #[sea_orm(has_many = "super::order_status_history::Entity")]
+    OrderStatusHistory,
+}
+impl Related<super::order_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderItem.def()
+    }
+}
+impl Related<super::order_status_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderStatusHistory.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use super::Status::*;
+
+    #[test]
+    fn test_status_transition() {
+        assert_eq!(Pending.transition(Accepted).unwrap(), Accepted);
+        assert_eq!(Paid.transition(Closed).unwrap(), Closed);
+        assert_eq!(Accepted.transition(Cancelled).unwrap(), Cancelled);
+        assert!(Cooking.transition(Cancelled).is_err());
+        assert!(Pending.transition(Served).is_err());
+        assert!(Closed.transition(Pending).is_err());
+    }
+}
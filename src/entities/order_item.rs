@@ -0,0 +1,107 @@
+use sea_orm::entity::prelude::*;
+use salvo::oapi::ToSchema;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::error::{AppError, AppResult};
+use crate::utils::money::Money;
+
+/// 订单中的一项，菜名和单价在下单时从菜品复制过来，之后菜品改价不影响已有订单
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_item")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub order_id: String,
+    pub dish_id: String,
+    pub dish_name: String,
+    pub variant_id: Option<String>, // 没有规格的菜品为空，按菜品上的价格下单
+    pub variant_name: Option<String>,
+    #[sea_orm(column_type = "Json")]
+    pub modifiers: ItemModifiers, // 下单时选择的加料和口味，单价已经包含了加价
+    pub price: Money,
+    pub quantity: i32,
+    pub voided_quantity: i32, // 已退菜的数量
+    pub station: Option<String>,
+    pub status: Status,
+    pub combo: bool, // 套餐这一行只记价格，后厨制作的是展开后的套餐内容
+    pub combo_item_id: Option<String>, // 套餐内容指向所属的套餐行，单价为0
+}
+
+/// 菜品制作状态: pending → cooking → served，上菜之前都可以取消
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "pending")]
+    Pending, // 待制作
+    #[sea_orm(string_value = "cooking")]
+    Cooking, // 制作中
+    #[sea_orm(string_value = "served")]
+    Served, // 已上菜
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled, // 已取消
+}
+impl Status {
+    pub fn can_transition_to(self, next: Status) -> bool {
+        use Status::*;
+        matches!(
+            (self, next),
+            (Pending, Cooking) | (Cooking, Served) | (Pending | Cooking, Cancelled)
+        )
+    }
+    pub fn transition(self, next: Status) -> AppResult<Status> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(AppError::conflict(format!("菜品状态不能从{:?}变为{:?}", self, next)))
+        }
+    }
+    /// 后厨还需要处理的状态
+    pub fn is_open(self) -> bool {
+        matches!(self, Status::Pending | Status::Cooking)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ItemModifiers(pub Vec<ItemModifier>);
+/// 复制到订单项上的选项，之后修改或删除选项不影响已下的单
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ItemModifier {
+    pub modifier_id: String,
+    pub group_name: String,
+    pub name: String,
+    pub price_delta: Money,
+}
+
+impl Model {
+    /// 后厨单和小票上显示的选项，例如 微辣、加蛋
+    pub fn modifier_names(&self) -> Option<String> {
+        if self.modifiers.0.is_empty() {
+            return None;
+        }
+        Some(self.modifiers.0.iter().map(|modifier| modifier.name.as_str()).collect::<Vec<_>>().join("、"))
+    }
+    /// 小票和后厨单上显示的菜名，带上规格
+    pub fn display_name(&self) -> String {
+        match &self.variant_name {
+            Some(variant) => format!("{}({})", self.dish_name, variant),
+            None => self.dish_name.clone(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id"
+    )]
+    Order,
+}
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
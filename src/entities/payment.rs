@@ -0,0 +1,61 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 支付流水，一个订单可以有多笔(混合支付、分单支付)
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payment")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub order_id: String,
+    pub bill_split_id: Option<String>, // 支付的是某一份分单时填写
+    pub method: Method,
+    pub provider: String, // 处理这笔支付的渠道名称
+    pub provider_ref: Option<String>, // 渠道返回的交易号
+    pub amount: Money,
+    pub status: Status,
+    pub uid: String, // 收银员id
+    pub created_at: String,
+    pub captured_at: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Method {
+    #[sea_orm(string_value = "cash")]
+    Cash, // 现金
+    #[sea_orm(string_value = "card")]
+    Card, // 刷卡
+    #[sea_orm(string_value = "wechat_pay")]
+    WechatPay, // 微信支付
+    #[sea_orm(string_value = "alipay")]
+    Alipay, // 支付宝
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "pending")]
+    Pending, // 等待渠道回调
+    #[sea_orm(string_value = "captured")]
+    Captured, // 已到账
+    #[sea_orm(string_value = "failed")]
+    Failed, // 支付失败
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id"
+    )]
+    Order,
+}
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 积分流水，每次积分变动记录一条
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "points_entry")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub member_id: String,
+    pub order_id: Option<String>,
+    pub reason: Reason,
+    pub delta: i64, // 增加为正，减少为负
+    pub balance: i64, // 变动后的余额
+    pub created_at: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Reason {
+    #[sea_orm(string_value = "earn")]
+    Earn, // 订单支付后获得
+    #[sea_orm(string_value = "redeem")]
+    Redeem, // 下单时抵扣
+    #[sea_orm(string_value = "refund")]
+    Refund, // 订单取消或者退菜后退回抵扣的积分，退款后扣回发放的积分
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
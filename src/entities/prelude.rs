@@ -9,5 +9,92 @@ pub use super::category::Model as Category;
 pub use super::dish::Entity as Dishes;
 pub use super::dish::Model as Dish;
 
+pub use super::dish_variant::Entity as DishVariants;
+pub use super::dish_variant::Model as DishVariant;
+
+pub use super::modifier_group::Entity as ModifierGroups;
+pub use super::modifier_group::Model as ModifierGroup;
+
+pub use super::modifier::Entity as Modifiers;
+pub use super::modifier::Model as Modifier;
+
+pub use super::modifier_group_link::Entity as ModifierGroupLinks;
+pub use super::modifier_group_link::Model as ModifierGroupLink;
+
+pub use super::combo_slot::Entity as ComboSlots;
+pub use super::combo_slot::Model as ComboSlot;
+
+pub use super::combo_slot_dish::Entity as ComboSlotDishes;
+pub use super::combo_slot_dish::Model as ComboSlotDish;
+
 pub use super::category_dish_map::Entity as CategoryDishMaps;
 pub use super::category_dish_map::Model as CategoryDishMap;
+
+pub use super::order::Entity as Orders;
+pub use super::order::Model as Order;
+
+pub use super::order_item::Entity as OrderItems;
+pub use super::order_item::Model as OrderItem;
+
+pub use super::order_status_history::Entity as OrderStatusHistories;
+pub use super::order_status_history::Model as OrderStatusHistory;
+
+pub use super::dining_table::Entity as DiningTables;
+pub use super::dining_table::Model as DiningTable;
+
+pub use super::table_session::Entity as TableSessions;
+pub use super::table_session::Model as TableSession;
+
+pub use super::cart_item::Entity as CartItems;
+pub use super::cart_item::Model as CartItem;
+
+pub use super::bill_split::Entity as BillSplits;
+pub use super::bill_split::Model as BillSplit;
+
+pub use super::bill_split_item::Entity as BillSplitItems;
+pub use super::bill_split_item::Model as BillSplitItem;
+
+pub use super::payment::Entity as Payments;
+pub use super::payment::Model as Payment;
+
+pub use super::item_void::Entity as ItemVoids;
+pub use super::item_void::Model as ItemVoid;
+
+pub use super::refund::Entity as Refunds;
+pub use super::refund::Model as Refund;
+
+pub use super::z_report::Entity as ZReports;
+pub use super::z_report::Model as ZReport;
+
+pub use super::stock::Entity as Stocks;
+pub use super::stock::Model as Stock;
+
+pub use super::stock_movement::Entity as StockMovements;
+pub use super::stock_movement::Model as StockMovement;
+
+pub use super::ingredient::Entity as Ingredients;
+pub use super::ingredient::Model as Ingredient;
+
+pub use super::recipe_line::Entity as RecipeLines;
+pub use super::recipe_line::Model as RecipeLine;
+
+pub use super::schedule_window::Entity as ScheduleWindows;
+pub use super::schedule_window::Model as ScheduleWindow;
+
+pub use super::schedule_exception::Entity as ScheduleExceptions;
+pub use super::schedule_exception::Model as ScheduleException;
+
+pub use super::pricing_rule::Entity as PricingRules;
+pub use super::pricing_rule::Model as PricingRule;
+
+pub use super::promotion::Entity as Promotions;
+pub use super::promotion::Model as Promotion;
+
+pub use super::coupon::Entity as Coupons;
+pub use super::coupon::Model as Coupon;
+
+pub use super::member::Entity as Members;
+pub use super::member::Model as Member;
+
+pub use super::points_entry::Entity as PointsEntries;
+pub use super::points_entry::Model as PointsEntry;
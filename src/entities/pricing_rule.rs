@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::entities::order::Channel;
+
+/// 服务费和税的规则，例如 堂食收10%服务费、菜价含6%增值税
+/// 下单时按订单渠道匹配启用的规则，计算结果和用到的规则一起保存在订单上，之后修改规则不影响已有订单
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pricing_rule")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub kind: Kind,
+    pub rate: i32, // 费率，单位为万分之一，1000表示10%
+    pub inclusive: bool, // 只对税有效，菜价已经含税时为true，税额只显示不另外收取
+    #[sea_orm(column_type = "Json")]
+    pub channels: Channels, // 适用的订单渠道
+    #[sea_orm(column_type = "Json")]
+    pub exempt_category_ids: CategoryIds, // 属于这些分类的菜品不计入
+    pub enabled: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Kind {
+    #[sea_orm(string_value = "service_charge")]
+    ServiceCharge, // 服务费
+    #[sea_orm(string_value = "tax")]
+    Tax, // 税
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct Channels(pub Vec<Channel>);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct CategoryIds(pub Vec<String>);
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,61 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::utils::money::Money;
+
+/// 促销活动，例如 满100减20、酒水8折、啤酒买二送一
+/// 不需要券码的活动下单时自动参加，需要券码的活动只有使用了对应的优惠券才能参加
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "promotion")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub kind: Kind,
+    pub target: Target,
+    #[sea_orm(column_type = "Json")]
+    pub target_ids: TargetIds, // target为dish或category时参加活动的菜品或分类
+    pub rate: i32, // 打折时减掉的比例，单位为万分之一，2000表示8折
+    pub amount: Money, // 减价金额，target为order时整单减，否则每份减
+    pub buy_quantity: i32, // 买几份
+    pub free_quantity: i32, // 送几份，送的是同一组里最便宜的
+    pub min_spend: Money, // 菜品金额满多少才能参加，0表示不限
+    pub start_date: Option<String>, // 活动开始的营业日，形如2023-07-01，为空表示不限
+    pub end_date: Option<String>, // 活动结束的营业日，包含这一天
+    pub usage_limit: Option<i32>, // 总共可以使用的次数，为空表示不限
+    pub used_count: i32,
+    pub coupon_only: bool, // 只能通过优惠券参加
+    pub enabled: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Kind {
+    #[sea_orm(string_value = "percent")]
+    Percent, // 打折
+    #[sea_orm(string_value = "fixed")]
+    Fixed, // 减价
+    #[sea_orm(string_value = "buy_x_get_y")]
+    BuyXGetY, // 买X送Y
+}
+
+/// 活动作用的范围
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Target {
+    #[sea_orm(string_value = "order")]
+    Order, // 整单
+    #[sea_orm(string_value = "dish")]
+    Dish, // 指定菜品
+    #[sea_orm(string_value = "category")]
+    Category, // 指定分类的菜品
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct TargetIds(pub Vec<String>);
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
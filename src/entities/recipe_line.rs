@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 配方的一行，做一份菜品需要多少原料
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "recipe_line")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub dish_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub ingredient_id: String,
+    pub quantity: f64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dish::Entity",
+        from = "Column::DishId",
+        to = "super::dish::Column::Id"
+    )]
+    Dish,
+    #[sea_orm(
+        belongs_to = "super::ingredient::Entity",
+        from = "Column::IngredientId",
+        to = "super::ingredient::Column::Id"
+    )]
+    Ingredient,
+}
+impl Related<super::dish::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dish.def()
+    }
+}
+impl Related<super::ingredient::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Ingredient.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,59 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entities::item_void::ReasonCode;
+use crate::utils::money::Money;
+
+/// 支付后的退款记录，退款金额不能超过对应支付流水的到账金额
+/// 先记录为处理中再交给渠道退款，渠道处理完成后改为已退款，处理中的也计入已退金额，避免重复退款
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "refund")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub order_id: String,
+    pub payment_id: String,
+    pub amount: Money,
+    pub reason: ReasonCode,
+    pub note: String,
+    pub uid: String, // 操作人id
+    pub approved_by: Option<String>, // 审批经理的id
+    pub late: bool, // 订单所在的营业日已经日结，这笔退款计入退款当天的报表
+    pub status: Status,
+    pub created_at: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Status {
+    #[sea_orm(string_value = "pending")]
+    Pending, // 渠道处理中，渠道没有返回结果时需要人工核对
+    #[sea_orm(string_value = "done")]
+    Done, // 已退款
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id"
+    )]
+    Order,
+    #[sea_orm(
+        belongs_to = "super::payment::Entity",
+        from = "Column::PaymentId",
+        to = "super::payment::Column::Id"
+    )]
+    Payment,
+}
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+impl Related<super::payment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Payment.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entities::schedule_window::Target;
+
+/// 供应时段的例外日期，例如 春节期间早餐暂停供应，或者节日当天全天供应
+/// 在日期范围内时忽略供应时段，直接按available决定是否供应
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "schedule_exception")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub target: Target,
+    pub target_id: String,
+    pub start_date: String, // 形如2023-07-01，包含起止两天
+    pub end_date: String,
+    pub available: bool,
+    pub note: String,
+}
+impl Model {
+    pub fn covers(&self, date: &str) -> bool {
+        self.start_date.as_str() <= date && date <= self.end_date.as_str()
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
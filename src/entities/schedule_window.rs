@@ -0,0 +1,88 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+
+/// 分类或菜品的供应时段，例如 周一到周五 07:00-10:00 供应早餐
+/// 结束时间不晚于开始时间时表示跨过午夜，例如 22:00-02:00 的夜宵，星期按开始的那天算
+/// 没有设置供应时段的分类和菜品全天供应
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "schedule_window")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub target: Target,
+    pub target_id: String,
+    #[sea_orm(column_type = "Json")]
+    pub days: Weekdays,
+    pub start_time: String, // 形如07:00
+    pub end_time: String,
+}
+
+/// 星期几，1是周一，7是周日
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct Weekdays(pub Vec<u8>);
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Target {
+    #[sea_orm(string_value = "category")]
+    Category,
+    #[sea_orm(string_value = "dish")]
+    Dish,
+}
+
+/// 解析形如07:00的时间，返回一天中的第几分钟
+pub fn clock_minutes(clock: &str) -> Option<u16> {
+    let (hour, minute) = clock.split_once(':')?;
+    let (hour, minute): (u16, u16) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60 && clock.len() == 5).then_some(hour * 60 + minute)
+}
+
+impl Model {
+    /// 星期weekday的第minute分钟是否在这个时段内
+    pub fn contains(&self, weekday: u8, minute: u16) -> bool {
+        let (Some(start), Some(end)) = (clock_minutes(&self.start_time), clock_minutes(&self.end_time)) else {
+            return false;
+        };
+        let previous = if weekday == 1 { 7 } else { weekday - 1 };
+        if start < end {
+            self.days.0.contains(&weekday) && (start..end).contains(&minute)
+        } else {
+            (self.days.0.contains(&weekday) && minute >= start) || (self.days.0.contains(&previous) && minute < end)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::schedule_window::{clock_minutes, Model, Target, Weekdays};
+
+    #[test]
+    fn test_contains() {
+        assert_eq!(clock_minutes("07:30"), Some(450));
+        assert_eq!(clock_minutes("24:00"), None);
+        assert_eq!(clock_minutes("7:30"), None);
+        let window = |days: Vec<u8>, start: &str, end: &str| Model {
+            id: String::new(),
+            target: Target::Category,
+            target_id: String::new(),
+            days: Weekdays(days),
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+        };
+        let breakfast = window(vec![1, 2, 3, 4, 5], "07:00", "10:00");
+        assert!(breakfast.contains(1, 7 * 60));
+        assert!(!breakfast.contains(1, 10 * 60));
+        assert!(!breakfast.contains(6, 8 * 60));
+        let late_night = window(vec![5, 7], "22:00", "02:00");
+        assert!(late_night.contains(5, 23 * 60));
+        assert!(late_night.contains(6, 60));
+        assert!(late_night.contains(1, 60));
+        assert!(!late_night.contains(5, 60));
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 菜品或者某个规格的库存，没有库存记录的菜品不限量
+/// 库存减到0时自动下架，补货后只重新上架被自动下架的菜品，手动下架的不受影响
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stock")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub dish_id: String,
+    pub variant_id: Option<String>, // 为空时是整个菜品的库存，所有规格共用
+    pub quantity: i32,
+    pub low_threshold: i32, // 库存不高于这个数时提醒补货
+    pub auto_delisted: bool, // 是否因为库存为0被自动下架
+    pub updated_at: String,
+}
+impl Model {
+    pub fn is_low(&self) -> bool {
+        self.quantity <= self.low_threshold
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dish::Entity",
+        from = "Column::DishId",
+        to = "super::dish::Column::Id"
+    )]
+    Dish,
+}
+impl Related<super::dish::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dish.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
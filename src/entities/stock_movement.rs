@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 库存的每一次变动，下单、取消和手动调整都会记录谁在什么时候改了多少
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stock_movement")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub stock_id: String,
+    pub delta: i32,
+    pub quantity: i32, // 变动后的库存
+    pub reason: Reason,
+    pub order_id: Option<String>,
+    pub note: String,
+    pub uid: String, // 操作人id，顾客下单时是桌台会话id
+    pub created_at: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, DeriveActiveEnum, EnumIter)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum Reason {
+    #[sea_orm(string_value = "order")]
+    Order, // 下单扣减
+    #[sea_orm(string_value = "cancel")]
+    Cancel, // 取消订单或退菜退回
+    #[sea_orm(string_value = "restock")]
+    Restock, // 补货
+    #[sea_orm(string_value = "count")]
+    Count, // 盘点，直接设置库存
+    #[sea_orm(string_value = "waste")]
+    Waste, // 损耗
+    #[sea_orm(string_value = "other")]
+    Other, // 其他，需要在备注中说明
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::stock::Entity",
+        from = "Column::StockId",
+        to = "super::stock::Column::Id"
+    )]
+    Stock,
+}
+impl Related<super::stock::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Stock.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,6 +1,6 @@
 use log::{error, info};
-use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, Schema};
-use crate::entities::prelude::{Categories, CategoryDishMaps, Dishes, Users};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Schema, Statement, TransactionTrait};
+use crate::entities::prelude::{BillSplitItems, BillSplits, CartItems, Categories, CategoryDishMaps, ComboSlotDishes, ComboSlots, DiningTables, Dishes, DishVariants, Ingredients, ItemVoids, ModifierGroupLinks, ModifierGroups, Modifiers, OrderItems, OrderStatusHistories, Orders, Payments, PricingRules, Promotions, Coupons, Members, PointsEntries, RecipeLines, Refunds, ScheduleExceptions, ScheduleWindows, StockMovements, Stocks, TableSessions, Users, ZReports};
 
 async fn create_table<E>(db_connection: &sea_orm::DatabaseConnection, entity: E)
 where
@@ -8,20 +8,152 @@ where
 {
     let backend = db_connection.get_database_backend();
     let schema = Schema::new(backend);
-    let execution = db_connection.execute(backend.build(&schema.create_table_from_entity(entity)));
+    let execution = db_connection.execute(backend.build(schema.create_table_from_entity(entity).if_not_exists()));
     match execution.await {
-        Ok(_) => info!("Created {} table.", entity.table_name()),
+        Ok(_) => info!("Checked {} table.", entity.table_name()),
         Err(e) => {
             error!("create data table error: {}", e);
             panic!("create data table error: {}", e);
         }
     }
 }
+/// 表中已有的字段名和声明的类型
+async fn table_columns(db: &DatabaseConnection, table: &str) -> Result<Vec<(String, String)>, DbErr> {
+    let backend = db.get_database_backend();
+    let rows = db
+        .query_all(Statement::from_string(backend, format!("PRAGMA table_info(\"{}\")", table)))
+        .await?;
+    rows.into_iter()
+        .map(|row| Ok((row.try_get("", "name")?, row.try_get("", "type")?)))
+        .collect()
+}
+/// 金额字段从元(浮点数)改成分(整数)之前建的表和字段
+const MONEY_COLUMNS: &[(&str, &[&str])] = &[
+    ("dish", &["price"]),
+    ("dish_variant", &["price"]),
+    ("modifier", &["price_delta"]),
+    ("combo_slot_dish", &["upcharge"]),
+    ("order", &["total", "refunded"]),
+    ("order_item", &["price"]),
+    ("item_void", &["amount"]),
+    ("bill_split", &["amount"]),
+    ("payment", &["amount"]),
+    ("refund", &["amount"]),
+    ("z_report", &["gross_sales", "voids", "discounts", "refunds", "net_sales", "average_ticket"]),
+    ("ingredient", &["unit_cost"]),
+];
+/// 把旧数据库里以元保存的金额转换成分，已经是整数的字段不做处理
+async fn migrate_money_columns(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    for (table, columns) in MONEY_COLUMNS {
+        let mut real_columns = Vec::new();
+        for (name, kind) in table_columns(db, table).await? {
+            //按SQLite的类型亲和规则，声明为double、float、real的都是浮点数
+            let kind = kind.to_ascii_uppercase();
+            let float = ["REAL", "FLOA", "DOUB"].iter().any(|affinity| kind.contains(affinity));
+            if columns.contains(&name.as_str()) && float {
+                real_columns.push(name);
+            }
+        }
+        if real_columns.is_empty() {
+            continue;
+        }
+        let txn = db.begin().await?;
+        for column in &real_columns {
+            for sql in [
+                format!("ALTER TABLE \"{table}\" ADD COLUMN \"{column}__fen\" bigint NOT NULL DEFAULT 0"),
+                format!("UPDATE \"{table}\" SET \"{column}__fen\" = CAST(ROUND(\"{column}\" * 100) AS INTEGER)"),
+                format!("ALTER TABLE \"{table}\" DROP COLUMN \"{column}\""),
+                format!("ALTER TABLE \"{table}\" RENAME COLUMN \"{column}__fen\" TO \"{column}\""),
+            ] {
+                txn.execute(Statement::from_string(backend, sql)).await?;
+            }
+        }
+        txn.commit().await?;
+        info!("Migrated money columns {:?} of {} table to fen.", real_columns, table);
+    }
+    Ok(())
+}
+/// 建表之后新增的字段：表名、字段名、字段定义和填充旧数据的表达式
+const ADDED_COLUMNS: &[(&str, &str, &str, Option<&str>)] = &[
+    ("dish", "station", "varchar", None),
+    //分类里的菜品原来没有单独的顺序，按菜品的全局顺序排列
+    ("category_dish_map", "position", "integer NOT NULL DEFAULT 0", Some("COALESCE((SELECT \"index\" FROM dish WHERE dish.id = category_dish_map.dish_id), 0)")),
+    ("order", "channel", "varchar NOT NULL DEFAULT 'takeaway'", Some("CASE WHEN table_session_id IS NULL THEN 'takeaway' ELSE 'dine_in' END")),
+    ("order", "subtotal", "bigint NOT NULL DEFAULT 0", Some("total")),
+    ("order", "service_charge", "bigint NOT NULL DEFAULT 0", None),
+    ("order", "tax", "bigint NOT NULL DEFAULT 0", None),
+    ("order", "charges", "json_text NOT NULL DEFAULT '[]'", None),
+    ("order", "discount", "bigint NOT NULL DEFAULT 0", None),
+    ("order", "discounts", "json_text NOT NULL DEFAULT '[]'", None),
+    ("order", "member_id", "varchar", None),
+    ("order", "points_redeemed", "bigint NOT NULL DEFAULT 0", None),
+    ("order", "points_earned", "bigint NOT NULL DEFAULT 0", None),
+    ("refund", "status", "varchar NOT NULL DEFAULT 'done'", None),
+    ("z_report", "service_charges", "bigint NOT NULL DEFAULT 0", None),
+    ("z_report", "taxes", "bigint NOT NULL DEFAULT 0", None),
+];
+/// 给旧数据库的表补上新增的字段
+async fn add_missing_columns(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    for (table, column, definition, fill) in ADDED_COLUMNS {
+        if table_columns(db, table).await?.iter().any(|(name, _)| name == column) {
+            continue;
+        }
+        let txn = db.begin().await?;
+        let sql = format!("ALTER TABLE \"{table}\" ADD COLUMN \"{column}\" {definition}");
+        txn.execute(Statement::from_string(backend, sql)).await?;
+        if let Some(fill) = fill {
+            let sql = format!("UPDATE \"{table}\" SET \"{column}\" = {fill}");
+            txn.execute(Statement::from_string(backend, sql)).await?;
+        }
+        txn.commit().await?;
+        info!("Added column {} to {} table.", column, table);
+    }
+    Ok(())
+}
 pub async fn create_all_need_table(db: &DatabaseConnection) {
     create_table(db, Users).await;
     create_table(db, Categories).await;
     create_table(db, Dishes).await;
+    create_table(db, DishVariants).await;
+    create_table(db, ModifierGroups).await;
+    create_table(db, Modifiers).await;
+    create_table(db, ModifierGroupLinks).await;
+    create_table(db, ComboSlots).await;
+    create_table(db, ComboSlotDishes).await;
     create_table(db, CategoryDishMaps).await;
+    create_table(db, Orders).await;
+    create_table(db, OrderItems).await;
+    create_table(db, OrderStatusHistories).await;
+    create_table(db, DiningTables).await;
+    create_table(db, TableSessions).await;
+    create_table(db, CartItems).await;
+    create_table(db, BillSplits).await;
+    create_table(db, BillSplitItems).await;
+    create_table(db, Payments).await;
+    create_table(db, ItemVoids).await;
+    create_table(db, Refunds).await;
+    create_table(db, ZReports).await;
+    create_table(db, Stocks).await;
+    create_table(db, StockMovements).await;
+    create_table(db, Ingredients).await;
+    create_table(db, RecipeLines).await;
+    create_table(db, ScheduleWindows).await;
+    create_table(db, ScheduleExceptions).await;
+    create_table(db, PricingRules).await;
+    create_table(db, Promotions).await;
+    create_table(db, Coupons).await;
+    create_table(db, Members).await;
+    create_table(db, PointsEntries).await;
+    if let Err(e) = migrate_money_columns(db).await {
+        error!("migrate money columns error: {}", e);
+        panic!("migrate money columns error: {}", e);
+    }
+    if let Err(e) = add_missing_columns(db).await {
+        error!("add missing columns error: {}", e);
+        panic!("add missing columns error: {}", e);
+    }
 }
 #[cfg(test)]
 mod test {
@@ -31,7 +163,7 @@ mod test {
     async fn test_create_table() {
         init_db_coon().await;
         let db = get_db_coon();
-        create_all_need_table(&db).await;
+        create_all_need_table(db).await;
         // init_db_coon().await;
         // let db = DB.get().unwrap();
         // // create_table(db, DocAndTags).await;
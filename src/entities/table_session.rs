@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 桌台会话，客人就座时开启，结账后关闭
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "table_session")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub table_id: String,
+    pub uid: String, // 开台的用户id
+    pub opened_at: String,
+    pub closed_at: Option<String>, // 为空表示会话仍在进行
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dining_table::Entity",
+        from = "Column::TableId",
+        to = "super::dining_table::Column::Id"
+    )]
+    DiningTable,
+}
+impl Related<super::dining_table::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DiningTable.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
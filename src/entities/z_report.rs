@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+use crate::entities::payment::Method;
+use crate::utils::money::Money;
+
+/// 日结报表(Z报表)，日结时把当天的营业数据固定下来，之后不再重新计算
+#[derive(Clone, Debug, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "z_report")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    #[sea_orm(unique)]
+    pub business_date: String, // 营业日，形如2023-07-01
+    pub gross_sales: Money, // 菜品原价合计
+    pub voids: Money, // 退菜金额
+    pub discounts: Money, // 优惠金额
+    pub refunds: Money, // 当天发生的退款
+    pub net_sales: Money, // 营业额 = 菜品原价 - 退菜 - 优惠 - 退款
+    pub service_charges: Money, // 服务费合计，不计入营业额
+    pub taxes: Money, // 税额合计，包括菜价中已含的税
+    pub order_count: i32,
+    pub average_ticket: Money, // 客单价 = 营业额 / 订单数
+    #[sea_orm(column_type = "Json")]
+    pub payments: PaymentSummaries,
+    pub uid: String, // 日结操作人id
+    pub closed_at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct PaymentSummaries(pub Vec<PaymentSummary>);
+/// 按支付方式汇总的收款和退款
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentSummary {
+    pub method: Method,
+    pub amount: Money,
+    pub refunded: Money,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+impl ActiveModelBehavior for ActiveModel {}
@@ -11,6 +11,9 @@ pub enum AppError {
     Public(String),
     #[error("internal: `{0}`")]
     Internal(String),
+    /// 请求与资源当前状态冲突，例如非法的订单状态流转
+    #[error("conflict: `{0}`")]
+    Conflict(String),
     #[error("salvo internal error: `{0}`")]
     Salvo(#[from] ::salvo::Error),
     #[error("http status error: `{0}`")]
@@ -34,6 +37,10 @@ impl AppError {
     pub fn internal<S: Into<String>>(msg: S) -> Self {
         Self::Internal(msg.into())
     }
+
+    pub fn conflict<S: Into<String>>(msg: S) -> Self {
+        Self::Conflict(msg.into())
+    }
 }
 
 #[async_trait]
@@ -41,6 +48,7 @@ impl Writer for AppError {
     async fn write(mut self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
         let code = match &self {
             Self::HttpStatus(e) => e.code,
+            Self::Conflict(_) => StatusCode::CONFLICT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         res.status_code(code);
@@ -50,6 +58,7 @@ impl Writer for AppError {
                 StatusError::internal_server_error().brief("Unknown error happened in salvo.")
             }
             Self::Public(msg) => StatusError::internal_server_error().brief(msg),
+            Self::Conflict(msg) => StatusError::conflict().brief(msg),
             Self::Internal(msg) => {
                 tracing::error!(msg = msg, "internal error");
                 StatusError::internal_server_error()
@@ -75,6 +84,11 @@ impl EndpointOutRegister for AppError {
             oapi::Response::new("Not found")
                 .add_content("application/json", StatusError::to_schema(components)),
         );
+        operation.responses.insert(
+            StatusCode::CONFLICT.as_str(),
+            oapi::Response::new("Conflict")
+                .add_content("application/json", StatusError::to_schema(components)),
+        );
         operation.responses.insert(
             StatusCode::BAD_REQUEST.as_str(),
             oapi::Response::new("Bad request")
@@ -0,0 +1,58 @@
+//! 进程内的订单事件广播，所有修改订单的代码都在事务提交后发布事件，
+//! 后厨大屏和顾客端的实时推送都从这里订阅
+use std::sync::LazyLock;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use crate::entities::prelude::{Order, OrderItem};
+
+/// 订阅者处理不过来时最多缓存的事件数，超出后订阅者会丢失最早的事件
+const CAPACITY: usize = 1024;
+
+static HUB: LazyLock<broadcast::Sender<OrderEvent>> = LazyLock::new(|| broadcast::channel(CAPACITY).0);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum OrderEvent {
+    OrderCreated(Order),
+    OrderStatusChanged(Order),
+    ItemCreated(OrderItem),
+    ItemStatusChanged(OrderItem),
+    ItemCancelled(OrderItem),
+}
+impl OrderEvent {
+    /// 事件名，和序列化后的`type`字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrderEvent::OrderCreated(_) => "order_created",
+            OrderEvent::OrderStatusChanged(_) => "order_status_changed",
+            OrderEvent::ItemCreated(_) => "item_created",
+            OrderEvent::ItemStatusChanged(_) => "item_status_changed",
+            OrderEvent::ItemCancelled(_) => "item_cancelled",
+        }
+    }
+    /// 菜品相关的事件返回对应的订单项
+    pub fn item(&self) -> Option<&OrderItem> {
+        match self {
+            OrderEvent::ItemCreated(item)
+            | OrderEvent::ItemStatusChanged(item)
+            | OrderEvent::ItemCancelled(item) => Some(item),
+            _ => None,
+        }
+    }
+    pub fn order_id(&self) -> &str {
+        match self {
+            OrderEvent::OrderCreated(order) | OrderEvent::OrderStatusChanged(order) => &order.id,
+            OrderEvent::ItemCreated(item)
+            | OrderEvent::ItemStatusChanged(item)
+            | OrderEvent::ItemCancelled(item) => &item.order_id,
+        }
+    }
+}
+
+/// 广播事件，应该在事务提交之后调用，没有订阅者时事件直接丢弃
+pub fn publish(event: OrderEvent) {
+    let _ = HUB.send(event);
+}
+pub fn subscribe() -> broadcast::Receiver<OrderEvent> {
+    HUB.subscribe()
+}
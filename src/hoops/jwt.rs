@@ -1,10 +1,7 @@
 use anyhow::Result;
 use jsonwebtoken::{decode, Algorithm, DecodingKey, EncodingKey, Validation};
 use log::info;
-use salvo::http::headers::Authorization;
-use salvo::http::HeaderValue;
 use salvo::jwt_auth::{ConstDecoder, CookieFinder, HeaderFinder, QueryFinder};
-use salvo::oapi::extract::HeaderParam;
 use salvo::prelude::*;
 use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
@@ -14,7 +11,26 @@ use crate::JsonResult;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JwtClaims {
-    uid: String,
+    pub uid: String,
+    exp: i64,
+}
+
+/// 顾客扫码点餐使用的令牌，只能访问对应桌台的菜单和下单接口
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableClaims {
+    pub table_id: String,
+    pub session_id: String,
+    exp: i64,
+}
+
+/// 会员令牌的aud，员工和桌台的令牌没有aud，带aud的令牌在[auth_hoop]和[table_auth_hoop]里校验不通过
+pub const MEMBER_AUDIENCE: &str = "member";
+
+/// 会员登录后使用的令牌，只能访问会员自己的积分接口，也可以在扫码下单时证明会员身份
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberClaims {
+    pub member_id: String,
+    aud: String,
     exp: i64,
 }
 
@@ -35,6 +51,40 @@ pub fn auth_hoop(config: &JwtConfig) -> JwtAuth<JwtClaims, ConstDecoder> {
     .force_passed(false)
 }
 
+/// 顾客使用的认证中间件，和[auth_hoop]共用密钥，但是只接受[TableClaims]，
+/// 所以顾客的令牌不能访问员工接口，员工的令牌也不能冒充某一桌。
+pub fn table_auth_hoop(config: &JwtConfig) -> JwtAuth<TableClaims, ConstDecoder> {
+    info!("TableJwtAuth init");
+    JwtAuth::new(ConstDecoder::from_secret(
+        config.secret.to_owned().as_bytes(),
+    ))
+    .finders(vec![
+        Box::new(HeaderFinder::new()),
+        Box::new(QueryFinder::new("token")),
+    ])
+    .force_passed(false)
+}
+
+/// 会员使用的认证中间件，只接受aud为[MEMBER_AUDIENCE]的令牌
+pub fn member_auth_hoop(config: &JwtConfig) -> JwtAuth<MemberClaims, ConstDecoder> {
+    info!("MemberJwtAuth init");
+    JwtAuth::new(ConstDecoder::with_validation(
+        DecodingKey::from_secret(config.secret.as_bytes()),
+        member_validation(),
+    ))
+    .finders(vec![
+        Box::new(HeaderFinder::new()),
+        Box::new(QueryFinder::new("token")),
+    ])
+    .force_passed(false)
+}
+fn member_validation() -> Validation {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[MEMBER_AUDIENCE]);
+    validation.set_required_spec_claims(&["exp", "aud"]);
+    validation
+}
+
 /// 这段代码的功能是生成一个带有过期时间的JWT令牌，具体逻辑如下：  
 /// 1. 获取当前UTC时间并加上配置的过期时间，计算出令牌的有效期。  
 /// 2. 构造`JwtClaims`结构体，包含用户ID (`uid`) 和过期时间戳 (`exp`)。  
@@ -54,6 +104,48 @@ pub fn get_token(uid: impl Into<String>) -> Result<(String, i64)> {
     Ok((token, exp.unix_timestamp()))
 }
 
+/// 生成桌台会话的令牌，有效期为配置中的`table_expiry`
+pub fn get_table_token(table_id: impl Into<String>, session_id: impl Into<String>) -> Result<(String, i64)> {
+    let exp = OffsetDateTime::now_utc() + Duration::seconds(get_config().jwt.table_expiry);
+    let claim = TableClaims {
+        table_id: table_id.into(),
+        session_id: session_id.into(),
+        exp: exp.unix_timestamp(),
+    };
+    let token: String = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claim,
+        &EncodingKey::from_secret(get_config().jwt.secret.as_bytes()),
+    )?;
+    Ok((token, exp.unix_timestamp()))
+}
+
+/// 生成会员的令牌，有效期为配置中的`member_expiry`
+pub fn get_member_token(member_id: impl Into<String>) -> Result<(String, i64)> {
+    let exp = OffsetDateTime::now_utc() + Duration::seconds(get_config().jwt.member_expiry);
+    let claim = MemberClaims {
+        member_id: member_id.into(),
+        aud: MEMBER_AUDIENCE.to_string(),
+        exp: exp.unix_timestamp(),
+    };
+    let token: String = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claim,
+        &EncodingKey::from_secret(get_config().jwt.secret.as_bytes()),
+    )?;
+    Ok((token, exp.unix_timestamp()))
+}
+/// 扫码下单时顾客另外带上的会员令牌，校验通过后返回会员id
+pub fn decode_member_token(token: &str) -> Option<String> {
+    decode::<MemberClaims>(
+        token,
+        &DecodingKey::from_secret(get_config().jwt.secret.as_bytes()),
+        &member_validation(),
+    )
+    .ok()
+    .map(|data| data.claims.member_id)
+}
+
 pub fn decode_token(token: &str) -> bool {
     let validation = Validation::new(Algorithm::HS256);
     decode::<JwtClaims>(
@@ -67,10 +159,8 @@ pub fn decode_token(token: &str) -> bool {
 pub async fn validate_token(req: &Request)->JsonResult<bool>{
     let option = req.header::<String>("authorization");
     info!("{:?}",option);
-    if let Some(token) = req.headers().get("authorization").and_then(|c| c.to_str().ok()).map(|s| s.trim_start_matches("Bearer ")){
-        if decode_token(token){
-            return Ok(Json(true));
-        }
+    if let Some(token) = req.headers().get("authorization").and_then(|c| c.to_str().ok()).map(|s| s.trim_start_matches("Bearer ")) && decode_token(token){
+        return Ok(Json(true));
     }
     Ok(Json(false))
 }
\ No newline at end of file
@@ -0,0 +1,163 @@
+//! 菜品图片的存储
+//! 原图和缩略图都用内容的sha256命名，相同的图片只存一份，菜品的picture字段保存原图的访问路径
+use std::collections::HashSet;
+use std::io::{Cursor, ErrorKind};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+use image::ImageFormat;
+use log::{info, warn};
+use salvo::http::StatusError;
+use salvo::oapi::ToSchema;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use crate::config::{get_config, CURRENT_DIR};
+use crate::entities::dish;
+use crate::entities::prelude::Dishes;
+use crate::error::{AppError, AppResult};
+
+/// 图片的访问路径前缀，和静态文件路由一致
+pub const URL_PREFIX: &str = "/images/";
+const THUMBNAIL_DIR: &str = "thumbs";
+/// 上传后超过这个时间还没有被菜品引用的图片才会被清理，避免删掉刚上传还没保存到菜品的图片
+const ORPHAN_GRACE: Duration = Duration::from_secs(3600);
+
+pub static IMAGE_DIR: LazyLock<PathBuf> = LazyLock::new(|| CURRENT_DIR.join("data").join("images"));
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct StoredImage {
+    pub url: String,
+    pub thumbnail_url: String,
+}
+
+/// 只接受jpeg、png和webp
+fn format_of(content_type: &str) -> Option<ImageFormat> {
+    match content_type {
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/png" => Some(ImageFormat::Png),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+/// 检查并保存上传的图片，同时生成缩略图
+pub async fn save(data: Vec<u8>, content_type: Option<&str>) -> AppResult<StoredImage> {
+    let config = &get_config().image;
+    if data.len() as u64 > config.max_size {
+        return Err(StatusError::payload_too_large()
+            .brief(format!("图片不能超过{}KB", config.max_size / 1024))
+            .into());
+    }
+    let Some(format) = content_type.and_then(format_of) else {
+        return Err(StatusError::unsupported_media_type()
+            .brief("只支持jpeg、png和webp格式的图片")
+            .into());
+    };
+    if image::guess_format(&data).ok() != Some(format) {
+        return Err(StatusError::bad_request()
+            .brief("图片内容和文件类型不符")
+            .into());
+    }
+    let name = format!("{:x}.{}", Sha256::digest(&data), format.extensions_str()[0]);
+    let path = IMAGE_DIR.join(&name);
+    let thumbnail_path = IMAGE_DIR.join(THUMBNAIL_DIR).join(&name);
+    if !tokio::fs::try_exists(&path).await? || !tokio::fs::try_exists(&thumbnail_path).await? {
+        let size = config.thumbnail_size;
+        tokio::task::spawn_blocking(move || -> AppResult<()> {
+            let image = image::load_from_memory_with_format(&data, format)
+                .map_err(|_| StatusError::bad_request().brief("无法解析图片"))?;
+            let mut thumbnail = Cursor::new(Vec::new());
+            image
+                .thumbnail(size, size)
+                .write_to(&mut thumbnail, format)
+                .map_err(|e| AppError::internal(format!("生成缩略图失败: {}", e)))?;
+            std::fs::create_dir_all(IMAGE_DIR.join(THUMBNAIL_DIR))?;
+            std::fs::write(&path, &data)?;
+            std::fs::write(&thumbnail_path, thumbnail.into_inner())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))??;
+    }
+    Ok(StoredImage {
+        url: format!("{}{}", URL_PREFIX, name),
+        thumbnail_url: format!("{}{}/{}", URL_PREFIX, THUMBNAIL_DIR, name),
+    })
+}
+/// 本地存储的图片返回文件名，外部链接返回None
+fn local_name(picture: &str) -> Option<&str> {
+    picture
+        .strip_prefix(URL_PREFIX)
+        .filter(|name| !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.'))
+}
+/// 菜品删除或者换图后调用，没有其他菜品再用这张图片时删除原图和缩略图
+pub async fn remove_if_orphan<C: ConnectionTrait>(db: &C, picture: &str) -> AppResult<()> {
+    let Some(name) = local_name(picture) else {
+        return Ok(());
+    };
+    let used = Dishes::find()
+        .filter(dish::Column::Picture.eq(picture))
+        .count(db)
+        .await?;
+    if used == 0 {
+        remove_file(name).await?;
+    }
+    Ok(())
+}
+async fn remove_file(name: &str) -> AppResult<()> {
+    for path in [IMAGE_DIR.join(name), IMAGE_DIR.join(THUMBNAIL_DIR).join(name)] {
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+/// 启动时清理上传了但一直没有被菜品引用的图片
+pub async fn cleanup_orphans<C: ConnectionTrait>(db: &C) -> AppResult<()> {
+    let mut entries = match tokio::fs::read_dir(IMAGE_DIR.as_path()).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let used: HashSet<String> = Dishes::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|dish| local_name(&dish.picture).map(str::to_string))
+        .collect();
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !metadata.is_file() || used.contains(&name) {
+            continue;
+        }
+        let age = metadata.modified().ok().and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        if age.is_some_and(|age| age > ORPHAN_GRACE) {
+            if let Err(e) = remove_file(&name).await {
+                warn!("清理图片{}失败: {}", name, e);
+                continue;
+            }
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        info!("清理了{}张没有被菜品使用的图片", removed);
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use crate::images::local_name;
+
+    #[test]
+    fn test_local_name() {
+        assert_eq!(local_name("/images/abc.png"), Some("abc.png"));
+        assert_eq!(local_name("/images/../data.db"), None);
+        assert_eq!(local_name("/images/thumbs/abc.png"), None);
+        assert_eq!(local_name("https://example.com/abc.png"), None);
+    }
+}
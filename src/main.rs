@@ -3,10 +3,11 @@ use salvo::{Listener, Server, Service};
 use salvo::cors::Cors;
 use salvo::http::Method;
 use salvo::logging::Logger;
+use log::error;
 use serde::Serialize;
 use tracing_appender::non_blocking::WorkerGuard;
 use crate::config::{get_config, load_config};
-use crate::config::db::init_db_coon;
+use crate::config::db::{get_db_coon, init_db_coon};
 use crate::config::log_config::init_logger;
 use crate::error::AppError;
 
@@ -18,6 +19,12 @@ mod routers;
 mod hoops;
 mod das;
 mod dto;
+mod events;
+mod payment;
+mod print;
+mod images;
+mod pricing;
+mod promotion;
 
 pub type JsonResult<T> = Result<Json<T>, AppError>;
 pub type EmptyResult = Result<Json<Empty>, AppError>;
@@ -30,10 +37,10 @@ pub fn empty_ok() -> JsonResult<Empty> {
 
 #[tokio::main]
 async fn main() {
-    let (_log_guard) = init_all().await;
+    let _log_guard = init_all().await;
     let cors = Cors::new()
         .allow_origin("http://localhost:5173")
-        .allow_methods(vec![Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_methods(vec![Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
         .allow_headers(vec!["Content-Type","Authorization"]) // 添加允许的请求头
         .into_handler();
     let service = Service::new(routers::root()).hoop(Logger::new()).hoop(cors);
@@ -42,9 +49,12 @@ async fn main() {
     let server = Server::new(acceptor);
     server.serve(service).await;
 }
-async fn init_all()->(WorkerGuard){
+async fn init_all()->WorkerGuard{
     let config = load_config();
     let log_guard = init_logger(&config.log);
     init_db_coon().await;
-    (log_guard)
+    if let Err(e) = images::cleanup_orphans(get_db_coon()).await {
+        error!("清理图片失败: {}", e);
+    }
+    log_guard
 }
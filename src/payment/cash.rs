@@ -0,0 +1,21 @@
+use salvo::async_trait;
+use crate::entities::prelude::Payment;
+use crate::error::AppResult;
+use crate::payment::{ChargeOutcome, PaymentProvider};
+use crate::utils::money::Money;
+
+/// 收银员当面收款，记账即到账
+pub struct CashProvider;
+
+#[async_trait]
+impl PaymentProvider for CashProvider {
+    fn name(&self) -> &'static str {
+        "cash"
+    }
+    async fn charge(&self, _payment: &Payment) -> AppResult<ChargeOutcome> {
+        Ok(ChargeOutcome::Captured { provider_ref: None })
+    }
+    async fn refund(&self, _payment: &Payment, _amount: Money) -> AppResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+use log::{error, info};
+use salvo::async_trait;
+use ulid::Ulid;
+use crate::das::payment::PaymentCurd;
+use crate::entities::prelude::Payment;
+use crate::error::AppResult;
+use crate::payment::{ChargeOutcome, PaymentProvider};
+use crate::utils::money::Money;
+
+/// 模拟扫码支付，本地测试用
+/// 发起支付后立即返回pending，稍后在后台模拟渠道的成功回调
+pub struct MockQrProvider;
+
+/// 模拟顾客扫码付款需要的时间
+const CALLBACK_DELAY: Duration = Duration::from_secs(2);
+
+#[async_trait]
+impl PaymentProvider for MockQrProvider {
+    fn name(&self) -> &'static str {
+        "mock_qr"
+    }
+    async fn charge(&self, payment: &Payment) -> AppResult<ChargeOutcome> {
+        let provider_ref = format!("mock-{}", Ulid::new());
+        let payment_id = payment.id.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(CALLBACK_DELAY).await;
+            if let Err(e) = PaymentCurd::complete(payment_id.clone(), true).await {
+                error!("mock qr callback for payment {} failed: {}", payment_id, e);
+            }
+        });
+        Ok(ChargeOutcome::Pending { provider_ref })
+    }
+    async fn refund(&self, payment: &Payment, amount: Money) -> AppResult<()> {
+        info!("mock qr refund {} for payment {:?}", amount, payment.provider_ref);
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+//! 支付渠道，每种支付方式由一个[PaymentProvider]处理
+//! 接入真实的微信支付、支付宝时实现这个trait并在[provider_for]中替换即可
+use salvo::async_trait;
+use crate::entities::payment::Method;
+use crate::entities::prelude::Payment;
+use crate::error::AppResult;
+use crate::utils::money::Money;
+
+pub mod cash;
+pub mod mock_qr;
+
+/// 渠道处理一笔支付的结果
+#[derive(Debug)]
+pub enum ChargeOutcome {
+    /// 已经到账
+    Captured { provider_ref: Option<String> },
+    /// 等待渠道异步回调，回调时调用[crate::das::payment::PaymentCurd::complete]
+    Pending { provider_ref: String },
+    /// 支付失败，内置的渠道不会同步失败
+    Failed { reason: String },
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// 发起支付，payment已经以pending状态保存到数据库
+    async fn charge(&self, payment: &Payment) -> AppResult<ChargeOutcome>;
+    /// 原路退款，调用方保证amount不超过这笔支付还可以退的金额
+    async fn refund(&self, payment: &Payment, amount: Money) -> AppResult<()>;
+}
+
+pub fn provider_for(method: Method) -> &'static dyn PaymentProvider {
+    match method {
+        //刷卡由收银员在外部刷卡机上完成，和现金一样直接记账
+        Method::Cash | Method::Card => &cash::CashProvider,
+        Method::WechatPay | Method::Alipay => &mock_qr::MockQrProvider,
+    }
+}
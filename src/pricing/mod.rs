@@ -0,0 +1,153 @@
+//! 订单金额的计算：菜品金额、优惠、服务费、税和应付金额
+//! 服务费按不免收的菜品优惠后的金额计算；税按不免税的菜品优惠后的金额加上服务费计算
+//! 价外税加到应付金额里，价内税只是从已含税的金额中拆出来显示
+use crate::entities::order::{AppliedCharge, AppliedDiscount};
+use crate::entities::pricing_rule::Kind;
+use crate::utils::money::Money;
+
+/// 费率的单位，万分之一
+const RATE_BASE: i64 = 10000;
+
+/// 一行菜品的金额、分到的优惠和菜品所属的分类
+pub struct Line {
+    pub amount: Money,
+    pub discount: Money,
+    pub category_ids: Vec<String>,
+}
+
+/// 金额明细
+#[derive(Debug, Default, PartialEq)]
+pub struct Breakdown {
+    pub subtotal: Money,
+    pub discount: Money, // 包含积分抵扣的金额
+    pub points_discount: Money,
+    pub service_charge: Money,
+    pub tax: Money,
+    pub total: Money,
+    pub charges: Vec<AppliedCharge>,
+    pub discounts: Vec<AppliedDiscount>,
+}
+
+/// 按费率计算，四舍五入到分
+pub fn apply_rate(amount: Money, rate: i32) -> Money {
+    let fen = amount.fen() as i128 * rate as i128;
+    let half = RATE_BASE as i128 / 2;
+    let rounded = if fen >= 0 { (fen + half) / RATE_BASE as i128 } else { (fen - half) / RATE_BASE as i128 };
+    Money::from_fen(rounded as i64)
+}
+/// 显示用的费率，例如1000显示为10%，650显示为6.5%
+pub fn format_rate(rate: i32) -> String {
+    let percent = format!("{}.{:02}", rate / 100, rate % 100);
+    format!("{}%", percent.trim_end_matches('0').trim_end_matches('.'))
+}
+/// 从已含税的金额中拆出税额
+pub fn included_tax(amount: Money, rate: i32) -> Money {
+    let base = RATE_BASE as i128;
+    let fen = amount.fen() as i128;
+    let net = (fen * base * 2 + base + rate as i128) / ((base + rate as i128) * 2);
+    Money::from_fen((fen - net) as i64)
+}
+/// 优惠后的菜品金额中不属于免收分类的部分
+fn base_of(charge: &AppliedCharge, lines: &[Line]) -> Money {
+    lines
+        .iter()
+        .filter(|line| !line.category_ids.iter().any(|id| charge.exempt_category_ids.contains(id)))
+        .map(|line| line.amount - line.discount)
+        .sum()
+}
+/// 按订单上的规则计算金额明细，charges中原有的金额会被重新计算
+/// discounts是已经按行分摊到lines上的优惠，只用于保存和显示
+pub fn compute(charges: Vec<AppliedCharge>, discounts: Vec<AppliedDiscount>, lines: &[Line]) -> Breakdown {
+    let subtotal: Money = lines.iter().map(|line| line.amount).sum();
+    let discount: Money = lines.iter().map(|line| line.discount).sum();
+    let (service_charges, taxes): (Vec<_>, Vec<_>) = charges.into_iter().partition(|charge| charge.kind == Kind::ServiceCharge);
+    let mut result = Breakdown { subtotal, discount, discounts, ..Default::default() };
+    for mut charge in service_charges {
+        charge.amount = apply_rate(base_of(&charge, lines), charge.rate);
+        result.service_charge += charge.amount;
+        result.charges.push(charge);
+    }
+    let mut exclusive_tax = Money::ZERO;
+    for mut charge in taxes {
+        let base = base_of(&charge, lines) + result.service_charge;
+        if charge.inclusive {
+            charge.amount = included_tax(base, charge.rate);
+        } else {
+            charge.amount = apply_rate(base, charge.rate);
+            exclusive_tax += charge.amount;
+        }
+        result.tax += charge.amount;
+        result.charges.push(charge);
+    }
+    result.total = subtotal - discount + result.service_charge + exclusive_tax;
+    result
+}
+/// 把金额按权重分摊，分不尽的零头给权重最大的那份，用于分摊整单优惠以及按菜品分单时分摊服务费和税
+pub fn allocate(amount: Money, weights: &[Money]) -> Vec<Money> {
+    let total: i64 = weights.iter().map(|weight| weight.fen()).sum();
+    if total == 0 {
+        return weights.iter().map(|_| Money::ZERO).collect();
+    }
+    let mut result: Vec<Money> = weights
+        .iter()
+        .map(|weight| Money::from_fen((amount.fen() as i128 * weight.fen() as i128 / total as i128) as i64))
+        .collect();
+    let rest = amount - result.iter().sum();
+    if let Some(index) = (0..weights.len()).max_by_key(|&index| weights[index]) {
+        result[index] += rest;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::order::AppliedCharge;
+    use crate::entities::pricing_rule::Kind;
+    use crate::pricing::{allocate, compute, format_rate, Line};
+    use crate::utils::money::Money;
+
+    fn charge(kind: Kind, rate: i32, inclusive: bool, exempt: &[&str]) -> AppliedCharge {
+        AppliedCharge {
+            rule_id: String::new(),
+            name: String::new(),
+            kind,
+            rate,
+            inclusive,
+            exempt_category_ids: exempt.iter().map(|id| id.to_string()).collect(),
+            amount: Money::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let mut lines = vec![
+            Line { amount: Money::from_fen(10000), discount: Money::ZERO, category_ids: vec!["food".to_string()] },
+            Line { amount: Money::from_fen(2000), discount: Money::ZERO, category_ids: vec!["drink".to_string()] },
+        ];
+        //服务费10%，酒水不收服务费，价外税6%
+        let charges = vec![charge(Kind::Tax, 600, false, &[]), charge(Kind::ServiceCharge, 1000, false, &["drink"])];
+        let breakdown = compute(charges, Vec::new(), &lines);
+        assert_eq!(breakdown.subtotal, Money::from_fen(12000));
+        assert_eq!(breakdown.service_charge, Money::from_fen(1000));
+        assert_eq!(breakdown.tax, Money::from_fen(780));
+        assert_eq!(breakdown.total, Money::from_fen(13780));
+        assert_eq!(breakdown.charges[0].kind, Kind::ServiceCharge);
+        //价内税只拆分不加价
+        let breakdown = compute(vec![charge(Kind::Tax, 600, true, &[])], Vec::new(), &lines);
+        assert_eq!(breakdown.tax, Money::from_fen(679));
+        assert_eq!(breakdown.total, Money::from_fen(12000));
+        //服务费按优惠后的金额计算
+        lines[0].discount = Money::from_fen(2000);
+        let breakdown = compute(vec![charge(Kind::ServiceCharge, 1000, false, &[])], Vec::new(), &lines);
+        assert_eq!((breakdown.discount, breakdown.service_charge), (Money::from_fen(2000), Money::from_fen(1000)));
+        assert_eq!(breakdown.total, Money::from_fen(11000));
+    }
+    #[test]
+    fn test_allocate() {
+        let weights = [Money::from_fen(100), Money::from_fen(200), Money::from_fen(300)];
+        let parts = allocate(Money::from_fen(100), &weights);
+        assert_eq!(parts, vec![Money::from_fen(16), Money::from_fen(33), Money::from_fen(51)]);
+        assert_eq!(allocate(Money::from_fen(5), &[Money::ZERO]), vec![Money::ZERO]);
+        assert_eq!((format_rate(1000), format_rate(650), format_rate(5)), ("10%".to_string(), "6.5%".to_string(), "0.05%".to_string()));
+    }
+}
@@ -0,0 +1,180 @@
+use encoding_rs::GBK;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Normal,
+    Bold,
+    /// 倍宽倍高加粗，用于店名、档口名等标题，一行能放的字数减半
+    Title,
+    /// 倍高加粗，用于后厨单上的菜名，不影响一行的字数
+    Tall,
+}
+#[derive(Clone, Debug)]
+enum Line {
+    Text { text: String, align: Align, style: Style },
+    /// 左右两端对齐的一行，放不下时右边的内容换到下一行
+    Columns { left: String, right: String, style: Style },
+    Separator,
+    Feed(u8),
+}
+
+/// 与打印机无关的单据排版
+#[derive(Clone, Debug)]
+pub struct Document {
+    width: usize,
+    lines: Vec<Line>,
+}
+impl Document {
+    pub fn new(width: usize) -> Self {
+        Document { width, lines: Vec::new() }
+    }
+    pub fn text(&mut self, text: impl Into<String>, align: Align, style: Style) -> &mut Self {
+        self.lines.push(Line::Text { text: text.into(), align, style });
+        self
+    }
+    pub fn columns(&mut self, left: impl Into<String>, right: impl Into<String>, style: Style) -> &mut Self {
+        self.lines.push(Line::Columns { left: left.into(), right: right.into(), style });
+        self
+    }
+    pub fn separator(&mut self) -> &mut Self {
+        self.lines.push(Line::Separator);
+        self
+    }
+    pub fn feed(&mut self, lines: u8) -> &mut Self {
+        self.lines.push(Line::Feed(lines));
+        self
+    }
+    /// 渲染为纯文本，用于预览
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Text { text, align, .. } => {
+                    out.push_str(&pad(text, *align, self.width));
+                    out.push('\n');
+                }
+                Line::Columns { left, right, .. } => {
+                    for row in columns(left, right, self.width) {
+                        out.push_str(&row);
+                        out.push('\n');
+                    }
+                }
+                Line::Separator => {
+                    out.push_str(&"-".repeat(self.width));
+                    out.push('\n');
+                }
+                Line::Feed(lines) => out.push_str(&"\n".repeat(*lines as usize)),
+            }
+        }
+        out
+    }
+    /// 渲染为ESC/POS指令，中文使用GBK编码，结尾走纸并切纸
+    pub fn to_escpos(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(INIT);
+        out.extend_from_slice(CHINESE_MODE);
+        for line in &self.lines {
+            match line {
+                Line::Text { text, align, style } => {
+                    set_style(&mut out, *style);
+                    out.extend_from_slice(&[ESC, b'a', *align as u8]);
+                    out.extend_from_slice(&encode(text));
+                    out.push(b'\n');
+                    out.extend_from_slice(&[ESC, b'a', Align::Left as u8]);
+                    set_style(&mut out, Style::Normal);
+                }
+                Line::Columns { left, right, style } => {
+                    set_style(&mut out, *style);
+                    for row in columns(left, right, self.width) {
+                        out.extend_from_slice(&encode(&row));
+                        out.push(b'\n');
+                    }
+                    set_style(&mut out, Style::Normal);
+                }
+                Line::Separator => {
+                    out.extend_from_slice("-".repeat(self.width).as_bytes());
+                    out.push(b'\n');
+                }
+                Line::Feed(lines) => out.extend_from_slice(&[ESC, b'd', *lines]),
+            }
+        }
+        out.extend_from_slice(&[ESC, b'd', 4]);
+        out.extend_from_slice(CUT);
+        out
+    }
+}
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const INIT: &[u8] = &[ESC, b'@'];
+/// FS &，进入汉字模式，国产热敏打印机需要
+const CHINESE_MODE: &[u8] = &[0x1c, b'&'];
+/// GS V 66 0，走纸到切刀位置后半切
+const CUT: &[u8] = &[GS, b'V', 66, 0];
+
+fn set_style(out: &mut Vec<u8>, style: Style) {
+    let (bold, size) = match style {
+        Style::Normal => (0, 0x00),
+        Style::Bold => (1, 0x00),
+        Style::Title => (1, 0x11),
+        Style::Tall => (1, 0x01),
+    };
+    out.extend_from_slice(&[ESC, b'E', bold, GS, b'!', size]);
+}
+/// GBK编码，无法编码的字符用`?`代替
+fn encode(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        let (bytes, _, had_errors) = GBK.encode(c.encode_utf8(&mut buf));
+        if had_errors {
+            out.push(b'?');
+        } else {
+            out.extend_from_slice(&bytes);
+        }
+    }
+    out
+}
+/// 打印宽度，全角字符占两个半角字符的宽度
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+fn pad(text: &str, align: Align, width: usize) -> String {
+    let space = width.saturating_sub(display_width(text));
+    let left = match align {
+        Align::Left => 0,
+        Align::Center => space / 2,
+        Align::Right => space,
+    };
+    format!("{}{}", " ".repeat(left), text)
+}
+fn columns(left: &str, right: &str, width: usize) -> Vec<String> {
+    let used = display_width(left) + display_width(right);
+    if used < width {
+        vec![format!("{}{}{}", left, " ".repeat(width - used), right)]
+    } else {
+        vec![left.to_string(), pad(right, Align::Right, width)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut document = Document::new(16);
+        document.text("小店", Align::Center, Style::Title).columns("宫保鸡丁 x2", "56.00", Style::Normal);
+        assert_eq!(document.to_text(), "      小店\n宫保鸡丁 x2\n           56.00\n");
+        let bytes = document.to_escpos();
+        //"小店"的GBK编码
+        assert!(bytes.windows(4).any(|w| w == [0xd0, 0xa1, 0xb5, 0xea]));
+        assert!(bytes.ends_with(CUT));
+    }
+}
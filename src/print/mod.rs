@@ -0,0 +1,62 @@
+//! 结账小票和后厨出单
+//! 先把订单排版成[Document]，再渲染成ESC/POS指令发给打印机，或者渲染成纯文本用于预览
+use crate::config::db::get_db_coon;
+use crate::config::PrintConfig;
+use crate::das::order::OrderCurd;
+use crate::das::payment::PaymentCurd;
+use crate::das::table_session::TableSessionCurd;
+use crate::entities::prelude::{Order, OrderItem, Payment};
+use crate::error::AppResult;
+use crate::print::printer::printer_for;
+use salvo::http::StatusError;
+
+pub mod document;
+pub mod printer;
+pub mod ticket;
+
+/// 打印一张单据需要的订单数据
+pub struct OrderTicketData {
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+    pub payments: Vec<Payment>,
+    pub table_name: Option<String>,
+}
+impl OrderTicketData {
+    pub async fn load(order_id: String) -> AppResult<Self> {
+        let Some(order) = OrderCurd::query_by_id(order_id.clone()).await? else {
+            return Err(StatusError::not_found()
+                .brief(format!("id为{}的订单不存在", order_id))
+                .into());
+        };
+        let table_name = match order.table_session_id.clone() {
+            Some(session_id) => TableSessionCurd::query_table(session_id).await?.map(|table| table.name),
+            None => None,
+        };
+        let payments = PaymentCurd::query_by_order(get_db_coon(), order_id).await?;
+        let mut orders = OrderCurd::query_with_items(vec![order]).await?;
+        let order = orders.remove(0);
+        Ok(OrderTicketData { order: order.order, items: order.items, payments, table_name })
+    }
+}
+/// 打印结账小票
+pub async fn print_receipt(config: &PrintConfig, data: &OrderTicketData) -> AppResult<()> {
+    let Some(printer) = config.receipt.as_ref() else {
+        return Err(StatusError::bad_request().brief("没有配置结账小票打印机").into());
+    };
+    let document = ticket::receipt(config, data);
+    printer_for(printer).print(&document.to_escpos()).await
+}
+/// 按档口打印后厨单，返回打印了的档口
+pub async fn print_kitchen_tickets(config: &PrintConfig, data: &OrderTicketData) -> AppResult<Vec<Option<String>>> {
+    let mut printed = Vec::new();
+    for (station, document) in ticket::kitchen_tickets(config, data) {
+        let Some(printer) = config.station_printer(station.as_deref()) else {
+            return Err(StatusError::bad_request()
+                .brief(format!("档口{}没有配置出单打印机", station.unwrap_or_default()))
+                .into());
+        };
+        printer_for(printer).print(&document.to_escpos()).await?;
+        printed.push(station);
+    }
+    Ok(printed)
+}
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::time::Duration;
+use salvo::async_trait;
+use salvo::http::StatusError;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use crate::config::{PrinterConfig, CURRENT_DIR};
+use crate::error::AppResult;
+
+/// 打印机只负责把渲染好的ESC/POS指令原样发出去
+#[async_trait]
+pub trait Printer: Send + Sync {
+    async fn print(&self, data: &[u8]) -> AppResult<()>;
+}
+
+/// 网口打印机的raw协议，连上9100端口直接写入数据
+pub struct TcpPrinter {
+    pub addr: String,
+}
+#[async_trait]
+impl Printer for TcpPrinter {
+    async fn print(&self, data: &[u8]) -> AppResult<()> {
+        let send = async {
+            let mut stream = TcpStream::connect(&self.addr).await?;
+            stream.write_all(data).await?;
+            stream.shutdown().await
+        };
+        match timeout(Duration::from_secs(5), send).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(StatusError::bad_gateway()
+                .brief(format!("打印机{}连接失败: {}", self.addr, e))
+                .into()),
+            Err(_) => Err(StatusError::gateway_timeout()
+                .brief(format!("打印机{}连接超时", self.addr))
+                .into()),
+        }
+    }
+}
+
+/// 写入文件，用于本地调试，文件内容可以直接`cat`到打印机设备或假的打印机上
+pub struct FilePrinter {
+    pub path: String,
+}
+#[async_trait]
+impl Printer for FilePrinter {
+    async fn print(&self, data: &[u8]) -> AppResult<()> {
+        let path = CURRENT_DIR.join(Path::new(&self.path));
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+        file.write_all(data).await?;
+        Ok(())
+    }
+}
+
+pub fn printer_for(config: &PrinterConfig) -> Box<dyn Printer> {
+    match config {
+        PrinterConfig::Tcp { addr } => Box::new(TcpPrinter { addr: addr.clone() }),
+        PrinterConfig::File { path } => Box::new(FilePrinter { path: path.clone() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tcp_printer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            socket.read_to_end(&mut received).await.unwrap();
+            received
+        });
+        TcpPrinter { addr }.print(b"\x1b@hello").await.unwrap();
+        assert_eq!(server.await.unwrap(), b"\x1b@hello");
+    }
+}
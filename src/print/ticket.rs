@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+use crate::config::PrintConfig;
+use crate::entities::payment;
+use crate::print::document::{Align, Document, Style};
+use crate::print::OrderTicketData;
+use crate::pricing::format_rate;
+use crate::utils::money::Money;
+
+fn method_name(method: payment::Method) -> &'static str {
+    match method {
+        payment::Method::Cash => "现金",
+        payment::Method::Card => "刷卡",
+        payment::Method::WechatPay => "微信",
+        payment::Method::Alipay => "支付宝",
+    }
+}
+fn header(document: &mut Document, data: &OrderTicketData) {
+    if let Some(table_name) = &data.table_name {
+        document.text(format!("桌台: {}", table_name), Align::Left, Style::Bold);
+    }
+    document
+        .text(format!("单号: {}", data.order.id), Align::Left, Style::Normal)
+        .text(format!("时间: {}", data.order.created_at), Align::Left, Style::Normal)
+        .separator();
+}
+/// 结账小票，退掉的菜不显示，只列出已到账的支付，套餐内容列在套餐下面
+/// 有优惠、积分抵扣、服务费或税时先列出小计和每一项的金额，价内税标为其中含有
+pub fn receipt(config: &PrintConfig, data: &OrderTicketData) -> Document {
+    let mut document = Document::new(config.width);
+    if !config.shop_name.is_empty() {
+        document.text(&config.shop_name, Align::Center, Style::Title);
+    }
+    document.text("结账单", Align::Center, Style::Bold);
+    header(&mut document, data);
+    for item in data.items.iter().filter(|item| item.combo_item_id.is_none()) {
+        let quantity = item.quantity - item.voided_quantity;
+        if quantity > 0 {
+            document.columns(
+                format!("{} x{}", item.display_name(), quantity),
+                (item.price * quantity).to_string(),
+                Style::Normal,
+            );
+            if let Some(modifiers) = item.modifier_names() {
+                document.text(format!("  {}", modifiers), Align::Left, Style::Normal);
+            }
+            for component in data.items.iter().filter(|component| component.combo_item_id.as_ref() == Some(&item.id)) {
+                let name = match component.modifier_names() {
+                    Some(modifiers) => format!("  - {} {}", component.display_name(), modifiers),
+                    None => format!("  - {}", component.display_name()),
+                };
+                document.text(name, Align::Left, Style::Normal);
+            }
+        }
+    }
+    document.separator();
+    if !data.order.charges.0.is_empty() || data.order.discount > Money::ZERO {
+        document.columns("小计", data.order.subtotal.to_string(), Style::Normal);
+        for discount in &data.order.discounts.0 {
+            document.columns(&discount.name, (-discount.amount).to_string(), Style::Normal);
+        }
+        if data.order.points_redeemed > 0 {
+            let name = format!("积分抵扣({}积分)", data.order.points_redeemed);
+            document.columns(name, (-Money::from_fen(data.order.points_redeemed)).to_string(), Style::Normal);
+        }
+        for charge in &data.order.charges.0 {
+            let name = format!("{}({})", charge.name, format_rate(charge.rate));
+            let name = if charge.inclusive { format!("其中{}", name) } else { name };
+            document.columns(name, charge.amount.to_string(), Style::Normal);
+        }
+    }
+    document.columns("合计", data.order.total.to_string(), Style::Bold);
+    for payment in data.payments.iter().filter(|payment| payment.status == payment::Status::Captured) {
+        document.columns(method_name(payment.method), payment.amount.to_string(), Style::Normal);
+    }
+    if data.order.refunded > Money::ZERO {
+        document.columns("已退款", data.order.refunded.to_string(), Style::Normal);
+    }
+    if data.order.points_earned > 0 {
+        document.columns("本单获得积分", data.order.points_earned.to_string(), Style::Normal);
+    }
+    document.feed(1).text("谢谢惠顾", Align::Center, Style::Normal);
+    document
+}
+/// 按档口拆分的后厨单，没有档口的菜品放在一起，已取消的菜品不出单
+/// 套餐本身不出单，套餐内容按各自的档口出单
+pub fn kitchen_tickets(config: &PrintConfig, data: &OrderTicketData) -> Vec<(Option<String>, Document)> {
+    let mut stations = BTreeMap::new();
+    for item in data.items.iter().filter(|item| !item.combo) {
+        let quantity = item.quantity - item.voided_quantity;
+        if quantity > 0 && item.status.is_open() {
+            stations.entry(item.station.clone()).or_insert_with(Vec::new).push((item, quantity));
+        }
+    }
+    stations
+        .into_iter()
+        .map(|(station, items)| {
+            let mut document = Document::new(config.width);
+            document.text(station.as_deref().unwrap_or("后厨"), Align::Center, Style::Title);
+            header(&mut document, data);
+            for (item, quantity) in items {
+                document.columns(item.display_name(), format!("x{}", quantity), Style::Tall);
+                if let Some(modifiers) = item.modifier_names() {
+                    document.text(format!("  {}", modifiers), Align::Left, Style::Bold);
+                }
+            }
+            (station, document)
+        })
+        .collect()
+}
@@ -0,0 +1,244 @@
+//! 促销活动的计算：按购物车或订单里的菜品算出每个活动优惠多少，以及没有参加的原因
+//! 多个活动依次计算，后面的活动按前面优惠后剩下的金额计算，每行菜品优惠后不会小于0
+//! 整单的优惠按各行剩下的金额分摊到菜品上，服务费和税按分摊后的金额计算
+use salvo::oapi::ToSchema;
+use serde::Serialize;
+use crate::entities::order::AppliedDiscount;
+use crate::entities::prelude::{Coupon, Promotion};
+use crate::entities::promotion::{Kind, Target};
+use crate::pricing::{allocate, apply_rate};
+use crate::utils::money::Money;
+
+/// 一行菜品
+pub struct Line {
+    pub dish_id: String,
+    pub category_ids: Vec<String>,
+    pub price: Money, // 单价
+    pub quantity: i32,
+}
+
+/// 参与计算的活动，通过优惠券参加时带上优惠券
+pub struct Candidate {
+    pub promotion: Promotion,
+    pub coupon: Option<Coupon>,
+}
+
+/// 没有参加的活动和原因
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Skipped {
+    pub name: String,
+    pub coupon_code: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Evaluation {
+    pub discounts: Vec<AppliedDiscount>,
+    pub skipped: Vec<Skipped>,
+    pub line_discounts: Vec<Money>, // 每行菜品分到的优惠，和lines一一对应
+}
+
+/// 显示用的折扣，例如减2000显示为8折，减1500显示为8.5折
+pub fn format_off(rate: i32) -> String {
+    let rest = 10000 - rate;
+    let text = format!("{}.{:03}", rest / 1000, rest % 1000);
+    format!("{}折", text.trim_end_matches('0').trim_end_matches('.'))
+}
+/// 给顾客看的活动规则，例如 满100.00减20.00、指定菜品买2送1
+pub fn describe(promotion: &Promotion) -> String {
+    let scope = match promotion.target {
+        Target::Order => "整单",
+        Target::Dish => "指定菜品",
+        Target::Category => "指定分类",
+    };
+    let threshold = if promotion.min_spend > Money::ZERO { format!("满{}", promotion.min_spend) } else { String::new() };
+    match (promotion.kind, promotion.target) {
+        (Kind::Percent, _) => format!("{}{}{}", threshold, scope, format_off(promotion.rate)),
+        (Kind::Fixed, Target::Order) if threshold.is_empty() => format!("整单减{}", promotion.amount),
+        (Kind::Fixed, Target::Order) => format!("{}减{}", threshold, promotion.amount),
+        (Kind::Fixed, _) => format!("{}{}每份减{}", threshold, scope, promotion.amount),
+        (Kind::BuyXGetY, _) => format!("{}{}买{}送{}", threshold, scope, promotion.buy_quantity, promotion.free_quantity),
+    }
+}
+fn matches(promotion: &Promotion, line: &Line) -> bool {
+    match promotion.target {
+        Target::Order => true,
+        Target::Dish => promotion.target_ids.0.contains(&line.dish_id),
+        Target::Category => line.category_ids.iter().any(|id| promotion.target_ids.0.contains(id)),
+    }
+}
+/// 活动和优惠券当前能不能使用，today为营业日
+fn check(candidate: &Candidate, today: &str) -> Result<(), String> {
+    let promotion = &candidate.promotion;
+    if !promotion.enabled {
+        return Err("活动已停用".to_string());
+    }
+    if promotion.start_date.as_deref().is_some_and(|start| today < start) {
+        return Err("活动还没有开始".to_string());
+    }
+    if promotion.end_date.as_deref().is_some_and(|end| today > end) {
+        return Err("活动已经结束".to_string());
+    }
+    if promotion.usage_limit.is_some_and(|limit| promotion.used_count >= limit) {
+        return Err("活动名额已经用完".to_string());
+    }
+    if let Some(coupon) = &candidate.coupon {
+        if !coupon.enabled {
+            return Err("优惠券已停用".to_string());
+        }
+        if coupon.usage_limit.is_some_and(|limit| coupon.used_count >= limit) {
+            return Err("优惠券已经用完".to_string());
+        }
+    }
+    Ok(())
+}
+/// 按活动规则计算每行的优惠，remaining为每行还剩下的金额
+fn discount_lines(promotion: &Promotion, lines: &[Line], remaining: &[Money]) -> Result<Vec<Money>, String> {
+    let matched: Vec<usize> = (0..lines.len()).filter(|&index| matches(promotion, &lines[index])).collect();
+    if matched.is_empty() {
+        return Err("没有参加活动的菜品".to_string());
+    }
+    let spend: Money = matched.iter().map(|&index| lines[index].price * lines[index].quantity).sum();
+    if spend < promotion.min_spend {
+        return Err(format!("还差{}才满{}", promotion.min_spend - spend, promotion.min_spend));
+    }
+    let mut result = vec![Money::ZERO; lines.len()];
+    let weights: Vec<Money> = matched.iter().map(|&index| remaining[index]).collect();
+    let base: Money = weights.iter().sum();
+    let shares = match (promotion.kind, promotion.target) {
+        (Kind::Percent, _) => allocate(apply_rate(base, promotion.rate), &weights),
+        (Kind::Fixed, Target::Order) => allocate(promotion.amount.min(base), &weights),
+        (Kind::Fixed, _) => matched.iter().map(|&index| promotion.amount * lines[index].quantity).collect(),
+        (Kind::BuyXGetY, _) => {
+            //从贵到便宜排列，每买够一组，组里最便宜的几份免费
+            let mut units: Vec<(usize, Money)> = matched
+                .iter()
+                .enumerate()
+                .flat_map(|(position, &index)| (0..lines[index].quantity.max(0)).map(move |_| (position, lines[index].price)))
+                .collect();
+            units.sort_by_key(|unit| std::cmp::Reverse(unit.1));
+            let group = (promotion.buy_quantity + promotion.free_quantity).max(1) as usize;
+            if units.len() < group {
+                return Err(format!("参加活动的菜品不足{}份", group));
+            }
+            let mut shares = vec![Money::ZERO; matched.len()];
+            for chunk in units.chunks_exact(group) {
+                for (position, price) in &chunk[promotion.buy_quantity.max(0) as usize..] {
+                    shares[*position] += *price;
+                }
+            }
+            shares
+        }
+    };
+    for (&index, share) in matched.iter().zip(shares) {
+        result[index] = share.max(Money::ZERO).min(remaining[index]);
+    }
+    Ok(result)
+}
+/// 计算可以参加的活动，granted为true时表示订单已经参加过这些活动，只按菜品重新计算优惠，
+/// 不再检查活动是否启用、有效期和使用次数，用于退菜后重新计算
+pub fn evaluate(candidates: Vec<Candidate>, lines: &[Line], today: &str, granted: bool) -> Evaluation {
+    let mut remaining: Vec<Money> = lines.iter().map(|line| line.price * line.quantity).collect();
+    let mut result = Evaluation { line_discounts: vec![Money::ZERO; lines.len()], ..Default::default() };
+    for candidate in candidates {
+        let checked = if granted { Ok(()) } else { check(&candidate, today) };
+        let discounts = checked.and_then(|_| discount_lines(&candidate.promotion, lines, &remaining));
+        let amount: Money = discounts.as_ref().map(|discounts| discounts.iter().sum()).unwrap_or_default();
+        let coupon_code = candidate.coupon.as_ref().map(|coupon| coupon.code.clone());
+        match discounts {
+            Ok(discounts) if amount > Money::ZERO => {
+                for (index, discount) in discounts.into_iter().enumerate() {
+                    remaining[index] -= discount;
+                    result.line_discounts[index] += discount;
+                }
+                result.discounts.push(AppliedDiscount {
+                    explanation: describe(&candidate.promotion),
+                    promotion_id: candidate.promotion.id,
+                    coupon_id: candidate.coupon.map(|coupon| coupon.id),
+                    name: candidate.promotion.name,
+                    amount,
+                });
+            }
+            Ok(_) => result.skipped.push(Skipped {
+                name: candidate.promotion.name,
+                coupon_code,
+                reason: "参加活动的菜品已经没有可以优惠的金额".to_string(),
+            }),
+            Err(reason) => result.skipped.push(Skipped { name: candidate.promotion.name, coupon_code, reason }),
+        }
+    }
+    result
+}
+/// 会员积分抵扣，1积分抵扣1分钱，在活动之后计算，按各行剩下的金额分摊，返回抵扣的金额
+pub fn redeem(evaluation: &mut Evaluation, lines: &[Line], points: i64) -> Money {
+    let remaining: Vec<Money> = lines
+        .iter()
+        .zip(&evaluation.line_discounts)
+        .map(|(line, discount)| line.price * line.quantity - *discount)
+        .collect();
+    let amount = Money::from_fen(points.max(0)).min(remaining.iter().sum());
+    for (discount, share) in evaluation.line_discounts.iter_mut().zip(allocate(amount, &remaining)) {
+        *discount += share;
+    }
+    amount
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::prelude::Promotion;
+    use crate::entities::promotion::{Kind, Target, TargetIds};
+    use crate::promotion::{evaluate, Candidate, Line};
+    use crate::utils::money::Money;
+
+    fn promotion(name: &str, kind: Kind, target: Target, target_ids: &[&str]) -> Promotion {
+        Promotion {
+            id: name.to_string(),
+            name: name.to_string(),
+            kind,
+            target,
+            target_ids: TargetIds(target_ids.iter().map(|id| id.to_string()).collect()),
+            rate: 0,
+            amount: Money::ZERO,
+            buy_quantity: 0,
+            free_quantity: 0,
+            min_spend: Money::ZERO,
+            start_date: None,
+            end_date: None,
+            usage_limit: None,
+            used_count: 0,
+            coupon_only: false,
+            enabled: true,
+        }
+    }
+    fn line(dish_id: &str, category: &str, price: i64, quantity: i32) -> Line {
+        Line { dish_id: dish_id.to_string(), category_ids: vec![category.to_string()], price: Money::from_fen(price), quantity }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let lines = vec![line("steak", "food", 8000, 1), line("beer", "drink", 1500, 3), line("cola", "drink", 500, 1)];
+        //啤酒买二送一，酒水8折，满100减20
+        let mut beer = promotion("beer", Kind::BuyXGetY, Target::Dish, &["beer"]);
+        (beer.buy_quantity, beer.free_quantity) = (2, 1);
+        let mut drinks = promotion("drinks", Kind::Percent, Target::Category, &["drink"]);
+        drinks.rate = 2000;
+        let mut order = promotion("order", Kind::Fixed, Target::Order, &[]);
+        (order.amount, order.min_spend) = (Money::from_fen(2000), Money::from_fen(10000));
+        let candidates = [beer, drinks, order].into_iter().map(|promotion| Candidate { promotion, coupon: None }).collect();
+        let result = evaluate(candidates, &lines, "2023-07-01", false);
+        let amounts: Vec<Money> = result.discounts.iter().map(|discount| discount.amount).collect();
+        assert_eq!(amounts, vec![Money::from_fen(1500), Money::from_fen(700), Money::from_fen(2000)]);
+        assert_eq!(result.discounts[2].explanation, "满100.00减20.00");
+        assert_eq!(result.line_discounts.iter().sum::<Money>(), Money::from_fen(4200));
+        //活动过期、没达到门槛的不参加
+        let mut expired = promotion("expired", Kind::Percent, Target::Order, &[]);
+        expired.end_date = Some("2023-06-30".to_string());
+        let mut order = promotion("order", Kind::Fixed, Target::Order, &[]);
+        (order.amount, order.min_spend) = (Money::from_fen(2000), Money::from_fen(20000));
+        let candidates = [expired, order].into_iter().map(|promotion| Candidate { promotion, coupon: None }).collect();
+        let result = evaluate(candidates, &lines, "2023-07-01", false);
+        assert!(result.discounts.is_empty());
+        assert_eq!(result.skipped[0].reason, "活动已经结束");
+        assert_eq!(result.skipped[1].reason, "还差70.00才满200.00");
+    }
+}
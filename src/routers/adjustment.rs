@@ -0,0 +1,32 @@
+use salvo::{handler, Depot, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::adjustment::AdjustmentCurd;
+use crate::dto::adjustment::{OrderAdjustments, RefundData, VoidItemData};
+use crate::entities::prelude::{ItemVoid, Refund};
+use crate::routers::order::current_uid;
+use crate::JsonResult;
+
+/// 支付前退菜
+#[handler]
+pub async fn void_item(id: PathParam<String>, data: JsonBody<VoidItemData>, depot: &mut Depot) -> JsonResult<ItemVoid> {
+    let data = data.into_inner();
+    data.validate()?;
+    let uid = current_uid(depot)?;
+    let record = AdjustmentCurd::void_item(id.into_inner(), data, uid).await?;
+    Ok(Json(record))
+}
+/// 支付后退款
+#[handler]
+pub async fn refund(id: PathParam<String>, data: JsonBody<RefundData>, depot: &mut Depot) -> JsonResult<Refund> {
+    let data = data.into_inner();
+    let uid = current_uid(depot)?;
+    let record = AdjustmentCurd::refund(id.into_inner(), data, uid).await?;
+    Ok(Json(record))
+}
+#[handler]
+pub async fn get_adjustments(id: PathParam<String>) -> JsonResult<OrderAdjustments> {
+    let adjustments = AdjustmentCurd::query_by_order(id.into_inner()).await?;
+    Ok(Json(adjustments))
+}
@@ -23,7 +23,6 @@ pub struct LoginOutData {
 #[endpoint(tags("auth"))]
 pub async fn post_login(
     in_data: JsonBody<LoginInData>,
-    res: &mut Response,
 ) -> JsonResult<LoginOutData> {
     let idata = in_data.into_inner();
     info!("login:{:?}",idata);
@@ -0,0 +1,17 @@
+use salvo::{handler, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use crate::das::bill_split::BillSplitCurd;
+use crate::dto::bill_split::{BillSplitWithItems, SplitBillData};
+use crate::JsonResult;
+
+#[handler]
+pub async fn split_bill(id: PathParam<String>, data: JsonBody<SplitBillData>) -> JsonResult<Vec<BillSplitWithItems>> {
+    let splits = BillSplitCurd::split(id.into_inner(), data.into_inner()).await?;
+    Ok(Json(splits))
+}
+#[handler]
+pub async fn get_bill_splits(id: PathParam<String>) -> JsonResult<Vec<BillSplitWithItems>> {
+    let splits = BillSplitCurd::query_by_order(id.into_inner()).await?;
+    Ok(Json(splits))
+}
@@ -0,0 +1,102 @@
+use salvo::http::StatusError;
+use salvo::{handler, Depot, Request, Writer};
+use salvo::jwt_auth::JwtAuthDepotExt;
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::config::db::get_db_coon;
+use crate::das::cart::CartCurd;
+use crate::das::order::OrderCurd;
+use crate::das::table_session::TableSessionCurd;
+use crate::dto::cart::{AddCartItemData, CartView, UpdateCartItemData};
+use crate::dto::menu::{query_menu, CategoryWithDishes};
+use crate::dto::order::{CreateOrderResult, CustomerOrderData, OrderOptions};
+use crate::entities::prelude::{CartItem, TableSession};
+use crate::error::{AppError, AppResult};
+use crate::hoops::jwt::{self, TableClaims};
+use crate::JsonResult;
+
+/// 取出令牌对应的桌台会话，会话关闭后令牌即使没过期也不能再使用
+pub async fn current_session(depot: &Depot) -> AppResult<TableSession> {
+    let claims = depot
+        .jwt_auth_data::<TableClaims>()
+        .map(|data| &data.claims)
+        .ok_or_else(|| AppError::internal("table auth data not found in depot"))?;
+    let session = TableSessionCurd::query_open_by_id(get_db_coon(), claims.session_id.clone()).await?;
+    if session.table_id != claims.table_id {
+        return Err(AppError::conflict("令牌与桌台不匹配"));
+    }
+    Ok(session)
+}
+
+/// 顾客同时登录了会员时，校验会员令牌并取出会员id
+fn member_of(member_token: Option<String>) -> AppResult<Option<String>> {
+    member_token
+        .map(|token| {
+            jwt::decode_member_token(&token).ok_or_else(|| StatusError::unauthorized().brief("会员令牌无效或已过期").into())
+        })
+        .transpose()
+}
+
+#[handler]
+pub async fn get_menu(depot: &mut Depot) -> JsonResult<Vec<CategoryWithDishes>> {
+    current_session(depot).await?;
+    Ok(Json(query_menu(false).await))
+}
+#[handler]
+pub async fn create_order(data: JsonBody<CustomerOrderData>, depot: &mut Depot) -> JsonResult<CreateOrderResult> {
+    let data = data.into_inner();
+    data.validate()?;
+    let session = current_session(depot).await?;
+    let options = OrderOptions {
+        coupon_code: data.coupon_code,
+        member_id: member_of(data.member_token)?,
+        redeem_points: data.redeem_points,
+        ..Default::default()
+    };
+    let (id, total) = OrderCurd::insert(session.id.clone(), Some(session.id), options, data.items).await?;
+    Ok(Json(CreateOrderResult { id, total }))
+}
+/// 查询购物车，带上coupon参数时预览使用优惠券后的金额
+#[handler]
+pub async fn get_cart(req: &mut Request, depot: &mut Depot) -> JsonResult<CartView> {
+    let session = current_session(depot).await?;
+    let coupon_code = req.query::<String>("coupon");
+    Ok(Json(CartCurd::query_by_session(session.id, coupon_code).await?))
+}
+#[handler]
+pub async fn add_cart_item(data: JsonBody<AddCartItemData>, depot: &mut Depot) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let session = current_session(depot).await?;
+    let id = CartCurd::insert(session.id, data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_cart_item(id: PathParam<String>, data: JsonBody<UpdateCartItemData>, depot: &mut Depot) -> JsonResult<CartItem> {
+    let data = data.into_inner();
+    data.validate()?;
+    let session = current_session(depot).await?;
+    let item = CartCurd::update_quantity(session.id, id.into_inner(), data.quantity).await?;
+    Ok(Json(item))
+}
+#[handler]
+pub async fn delete_cart_item(id: PathParam<String>, depot: &mut Depot) -> JsonResult<()> {
+    let session = current_session(depot).await?;
+    CartCurd::delete_by_id(session.id, id.into_inner()).await?;
+    Ok(Json(()))
+}
+/// 同一桌任何一个人都可以把购物车提交成订单，带上coupon参数时使用优惠券
+/// 带上member_token参数时订单计入会员积分，redeem_points是用来抵扣的积分
+#[handler]
+pub async fn submit_cart(req: &mut Request, depot: &mut Depot) -> JsonResult<CreateOrderResult> {
+    let session = current_session(depot).await?;
+    let options = OrderOptions {
+        coupon_code: req.query::<String>("coupon"),
+        member_id: member_of(req.query::<String>("member_token"))?,
+        redeem_points: req.query::<i64>("redeem_points").unwrap_or(0).max(0),
+        ..Default::default()
+    };
+    let (id, total) = CartCurd::submit(session.id.clone(), session.id, options).await?;
+    Ok(Json(CreateOrderResult { id, total }))
+}
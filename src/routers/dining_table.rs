@@ -0,0 +1,54 @@
+use salvo::{handler, Depot, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::dining_table::DiningTableCurd;
+use crate::das::table_session::TableSessionCurd;
+use crate::dto::dining_table::{DiningTableData, OpenSessionResult};
+use crate::entities::prelude::{DiningTable, TableSession};
+use crate::hoops::jwt;
+use crate::routers::order::current_uid;
+use crate::JsonResult;
+
+#[handler]
+pub async fn create_dining_table(data: JsonBody<DiningTableData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = DiningTableCurd::insert(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_dining_table(id: PathParam<String>, data: JsonBody<DiningTableData>) -> JsonResult<DiningTable> {
+    let data = data.into_inner();
+    data.validate()?;
+    let table = DiningTableCurd::update(id.into_inner(), data).await?;
+    Ok(Json(table))
+}
+#[handler]
+pub async fn delete_dining_table(id: PathParam<String>) -> JsonResult<()> {
+    DiningTableCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_dining_tables() -> JsonResult<Vec<DiningTable>> {
+    let models = DiningTableCurd::query_all().await?;
+    Ok(Json(models))
+}
+/// 开台，返回给顾客扫码使用的令牌
+#[handler]
+pub async fn open_session(id: PathParam<String>, depot: &mut Depot) -> JsonResult<OpenSessionResult> {
+    let uid = current_uid(depot)?;
+    let session = TableSessionCurd::open(id.into_inner(), uid).await?;
+    let (token, exp) = jwt::get_table_token(&session.table_id, &session.id)?;
+    Ok(Json(OpenSessionResult {
+        session_id: session.id,
+        table_id: session.table_id,
+        token,
+        exp,
+    }))
+}
+#[handler]
+pub async fn close_session(id: PathParam<String>) -> JsonResult<TableSession> {
+    let session = TableSessionCurd::close(id.into_inner()).await?;
+    Ok(Json(session))
+}
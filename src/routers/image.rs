@@ -0,0 +1,59 @@
+use salvo::http::header::{HeaderValue, CACHE_CONTROL};
+use salvo::http::{ReqBody, StatusError};
+use salvo::oapi::extract::PathParam;
+use salvo::prelude::Json;
+use salvo::{handler, Depot, FlowCtrl, Request, Response, Writer};
+use crate::config::get_config;
+use crate::das::dish::DishCurd;
+use crate::dto::menu::UpdateDishData;
+use crate::entities::prelude::Dish;
+use crate::error::AppResult;
+use crate::images::{self, StoredImage};
+use crate::JsonResult;
+
+/// 表单里除了图片之外的边界、字段头等内容的大小上限
+const FORM_OVERHEAD: u64 = 64 * 1024;
+/// 读取表单里file字段上传的图片并保存
+/// 解析表单会把整个请求体写到临时文件，先限制大小读到内存里再解析
+async fn save_upload(req: &mut Request) -> AppResult<StoredImage> {
+    let max_size = get_config().image.max_size;
+    let too_large = || StatusError::payload_too_large().brief(format!("图片不能超过{}KB", max_size / 1024));
+    let body = req
+        .payload_with_max_size((max_size + FORM_OVERHEAD) as usize)
+        .await
+        .map_err(|_| too_large())?
+        .clone();
+    req.replace_body(ReqBody::Once(body));
+    let Some(file) = req.file("file").await else {
+        return Err(StatusError::bad_request().brief("请用file字段上传图片").into());
+    };
+    if file.size() > max_size {
+        return Err(too_large().into());
+    }
+    let content_type = file.content_type().map(|mime| mime.essence_str().to_string());
+    let data = tokio::fs::read(file.path()).await?;
+    images::save(data, content_type.as_deref()).await
+}
+/// 上传图片，返回的url可以填到菜品的picture里
+#[handler]
+pub async fn upload_image(req: &mut Request) -> JsonResult<StoredImage> {
+    let image = save_upload(req).await?;
+    Ok(Json(image))
+}
+/// 上传并替换菜品的图片，旧图片没有其他菜品使用时删除
+#[handler]
+pub async fn upload_dish_picture(id: PathParam<String>, req: &mut Request) -> JsonResult<Dish> {
+    let image = save_upload(req).await?;
+    let data = UpdateDishData { picture: Some(image.url), ..Default::default() };
+    let dish = DishCurd::update(id.into_inner(), data).await?;
+    Ok(Json(dish))
+}
+/// 图片按内容命名，内容不会变，可以让浏览器一直缓存
+#[handler]
+pub async fn cache_forever(req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+    ctrl.call_next(req, depot, res).await;
+    if res.status_code.is_none_or(|code| code.is_success()) {
+        res.headers_mut()
+            .insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=31536000, immutable"));
+    }
+}
@@ -0,0 +1,51 @@
+use salvo::{handler, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::ingredient::IngredientCurd;
+use crate::dto::ingredient::{DishCost, IngredientData, IngredientDetail, RecipeData, RecipeDetail};
+use crate::entities::prelude::Ingredient;
+use crate::JsonResult;
+
+#[handler]
+pub async fn create_ingredient(data: JsonBody<IngredientData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = IngredientCurd::insert(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_ingredient(id: PathParam<String>, data: JsonBody<IngredientData>) -> JsonResult<Ingredient> {
+    let data = data.into_inner();
+    data.validate()?;
+    let ingredient = IngredientCurd::update(id.into_inner(), data).await?;
+    Ok(Json(ingredient))
+}
+#[handler]
+pub async fn delete_ingredient(id: PathParam<String>) -> JsonResult<()> {
+    IngredientCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_ingredients() -> JsonResult<Vec<IngredientDetail>> {
+    let ingredients = IngredientCurd::query_all().await?;
+    Ok(Json(ingredients))
+}
+#[handler]
+pub async fn get_recipe(id: PathParam<String>) -> JsonResult<Vec<RecipeDetail>> {
+    let recipe = IngredientCurd::query_recipe(id.into_inner()).await?;
+    Ok(Json(recipe))
+}
+#[handler]
+pub async fn set_recipe(id: PathParam<String>, data: JsonBody<RecipeData>) -> JsonResult<Vec<RecipeDetail>> {
+    let data = data.into_inner();
+    data.validate()?;
+    let recipe = IngredientCurd::replace_recipe(id.into_inner(), data).await?;
+    Ok(Json(recipe))
+}
+/// 每个菜品按配方计算的成本和毛利
+#[handler]
+pub async fn get_dish_costs() -> JsonResult<Vec<DishCost>> {
+    let costs = IngredientCurd::query_costs().await?;
+    Ok(Json(costs))
+}
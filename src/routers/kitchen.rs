@@ -0,0 +1,28 @@
+use futures_util::{future, stream, StreamExt};
+use salvo::{handler, Request, Response};
+use salvo::sse::{SseEvent, SseKeepAlive};
+use tokio_stream::wrappers::BroadcastStream;
+use crate::das::order::OrderCurd;
+use crate::error::AppResult;
+use crate::events;
+
+/// 后厨大屏的实时推送，可以用`station`参数只看某个档口
+/// 连接后先发送一个`snapshot`事件包含所有待处理的菜品，之后每次有菜品新增、状态变化或取消都会推送一个事件
+#[handler]
+pub async fn kitchen_feed(req: &mut Request, res: &mut Response) -> AppResult<()> {
+    let station = req.query::<String>("station");
+    //先订阅再查询，避免漏掉查询期间产生的事件
+    let receiver = events::subscribe();
+    let items = OrderCurd::query_open_items(station.clone()).await?;
+    let snapshot = SseEvent::default().name("snapshot").json(&items);
+    let updates = BroadcastStream::new(receiver).filter_map(move |event| {
+        //订阅者落后太多时会丢失部分事件，这里直接跳过，前端可以重连拿新的snapshot
+        //后厨只关心菜品相关的事件
+        let event = event.ok().filter(|event| {
+            event.item().is_some_and(|item| station.is_none() || item.station == station)
+        });
+        future::ready(event.map(|event| SseEvent::default().name(event.name()).json(&event)))
+    });
+    SseKeepAlive::new(stream::once(future::ready(snapshot)).chain(updates)).stream(res);
+    Ok(())
+}
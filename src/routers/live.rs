@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+use salvo::{handler, Depot, Request, Response, Writer};
+use salvo::oapi::extract::PathParam;
+use salvo::http::StatusError;
+use salvo::websocket::{Message, WebSocketUpgrade};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use crate::das::order::OrderCurd;
+use crate::dto::order::OrderWithItems;
+use crate::error::AppResult;
+use crate::events::{self, OrderEvent};
+use crate::routers::customer::current_session;
+
+/// 一个连接只接收某个订单或者某个桌台会话下所有订单的事件
+enum Scope {
+    Order(String),
+    Session {
+        id: String,
+        order_ids: HashSet<String>,
+    },
+}
+impl Scope {
+    fn matches(&mut self, event: &OrderEvent) -> bool {
+        match self {
+            Scope::Order(id) => event.order_id() == id,
+            Scope::Session { id, order_ids } => {
+                //会话中新下的订单也要加入推送范围，订单事件总是先于菜品事件发布
+                if let OrderEvent::OrderCreated(order) = event
+                    && order.table_session_id.as_deref() == Some(id.as_str())
+                {
+                    order_ids.insert(order.id.clone());
+                }
+                order_ids.contains(event.order_id())
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum Snapshot {
+    Snapshot(Vec<OrderWithItems>),
+}
+
+/// 连接后先发送一条`snapshot`消息包含范围内的订单和菜品，之后推送[OrderEvent]的JSON
+async fn serve(req: &mut Request, res: &mut Response, mut scope: Scope) -> AppResult<()> {
+    //先订阅再查询，避免漏掉查询期间产生的事件
+    let mut receiver = events::subscribe();
+    let orders = match &mut scope {
+        Scope::Order(id) => {
+            let order = OrderCurd::query_by_id(id.clone()).await?
+                .ok_or_else(|| StatusError::not_found().brief(format!("id为{}的订单不存在", id)))?;
+            vec![order]
+        }
+        Scope::Session { id, order_ids } => {
+            let orders = OrderCurd::query_by_session(id.clone()).await?;
+            order_ids.extend(orders.iter().map(|order| order.id.clone()));
+            orders
+        }
+    };
+    let snapshot = serde_json::to_string(&Snapshot::Snapshot(OrderCurd::query_with_items(orders).await?))
+        .map_err(|e| anyhow::anyhow!("snapshot序列化错误:{}", e))?;
+    WebSocketUpgrade::new()
+        .upgrade(req, res, move |mut ws| async move {
+            if ws.send(Message::text(snapshot)).await.is_err() {
+                return;
+            }
+            loop {
+                tokio::select! {
+                    event = receiver.recv() => match event {
+                        Ok(event) if scope.matches(&event) => {
+                            let Ok(text) = serde_json::to_string(&event) else { continue };
+                            if ws.send(Message::text(text)).await.is_err() {
+                                return;
+                            }
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return,
+                    },
+                    msg = ws.recv() => match msg {
+                        Some(Ok(msg)) if !msg.is_close() => continue,
+                        _ => return,
+                    },
+                }
+            }
+        })
+        .await?;
+    Ok(())
+}
+
+#[handler]
+pub async fn order_ws(id: PathParam<String>, req: &mut Request, res: &mut Response) -> AppResult<()> {
+    serve(req, res, Scope::Order(id.into_inner())).await
+}
+#[handler]
+pub async fn table_session_ws(id: PathParam<String>, req: &mut Request, res: &mut Response) -> AppResult<()> {
+    let scope = Scope::Session { id: id.into_inner(), order_ids: HashSet::new() };
+    serve(req, res, scope).await
+}
+/// 顾客只能订阅自己这一桌的订单
+#[handler]
+pub async fn customer_ws(req: &mut Request, depot: &mut Depot, res: &mut Response) -> AppResult<()> {
+    let session = current_session(depot).await?;
+    let scope = Scope::Session { id: session.id, order_ids: HashSet::new() };
+    serve(req, res, scope).await
+}
@@ -0,0 +1,66 @@
+use salvo::{handler, Depot, Request, Writer};
+use salvo::jwt_auth::JwtAuthDepotExt;
+use salvo::oapi::extract::JsonBody;
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::config::db::get_db_coon;
+use crate::das::member::MemberCurd;
+use crate::dto::member::{MemberLoginData, MemberLoginResult, RegisterMemberData};
+use crate::entities::prelude::{Member, PointsEntry};
+use crate::error::AppError;
+use crate::hoops::jwt::{self, MemberClaims};
+use crate::JsonResult;
+
+/// 从depot中取出当前登录会员的id
+fn current_member_id(depot: &Depot) -> Result<String, AppError> {
+    depot
+        .jwt_auth_data::<MemberClaims>()
+        .map(|data| data.claims.member_id.clone())
+        .ok_or_else(|| AppError::internal("member auth data not found in depot"))
+}
+fn login_result(member: Member) -> JsonResult<MemberLoginResult> {
+    let (token, exp) = jwt::get_member_token(&member.id)?;
+    Ok(Json(MemberLoginResult { id: member.id, phone: member.phone, name: member.name, points: member.points, token, exp }))
+}
+
+/// 顾客用手机号注册会员，注册后直接登录
+#[handler]
+pub async fn register(data: JsonBody<RegisterMemberData>) -> JsonResult<MemberLoginResult> {
+    let data = data.into_inner();
+    data.validate()?;
+    let member = MemberCurd::register(data).await?;
+    login_result(member)
+}
+#[handler]
+pub async fn login(data: JsonBody<MemberLoginData>) -> JsonResult<MemberLoginResult> {
+    let data = data.into_inner();
+    let member = MemberCurd::login(data.phone, data.password).await?;
+    login_result(member)
+}
+/// 会员查看自己的信息和积分余额
+#[handler]
+pub async fn get_me(depot: &mut Depot) -> JsonResult<Member> {
+    let member = MemberCurd::query_by_id(get_db_coon(), current_member_id(depot)?).await?;
+    Ok(Json(member))
+}
+/// 会员查看自己的积分流水
+#[handler]
+pub async fn get_points(depot: &mut Depot) -> JsonResult<Vec<PointsEntry>> {
+    let entries = MemberCurd::query_entries(current_member_id(depot)?).await?;
+    Ok(Json(entries))
+}
+/// 员工用手机号查会员，下单时填写会员id
+#[handler]
+pub async fn get_member(req: &mut Request) -> JsonResult<Member> {
+    let phone = req.query::<String>("phone").unwrap_or_default();
+    let member = MemberCurd::query_by_phone(phone).await?;
+    Ok(Json(member))
+}
+/// 员工查看会员的积分流水
+#[handler]
+pub async fn get_member_points(req: &mut Request) -> JsonResult<Vec<PointsEntry>> {
+    let phone = req.query::<String>("phone").unwrap_or_default();
+    let member = MemberCurd::query_by_phone(phone).await?;
+    let entries = MemberCurd::query_entries(member.id).await?;
+    Ok(Json(entries))
+}
@@ -1,12 +1,20 @@
-use salvo::Writer;
+use salvo::{Request, Response, Writer};
 use log::info;
 use salvo::handler;
+use salvo::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use salvo::http::StatusError;
 use salvo::oapi::extract::{JsonBody, PathParam};
 use salvo::prelude::Json;
 use crate::das::category::CategoryCurd;
 use crate::das::category_dish_map::CategoryDishMapCurd;
-use crate::das::dish::DishCurd;
-use crate::dto::menu::{query_menu, CategoryWithDishes, CreateCategoryData, CreateDishData};
+use crate::config::db::get_db_coon;
+use crate::das::combo::{check_combo_slots, ComboCurd};
+use crate::das::dish::{check_variants, DishCurd};
+use crate::das::menu::MenuCurd;
+use crate::das::schedule::Availability;
+use crate::dto::menu::{query_menu, CategoryDetail, CategoryWithDishes, CreateCategoryData, CreateDishData, DishDetail, ImportReport, MenuData, ReorderData, ReplaceCategoryData, ReplaceDishData, UpdateCategoryData, UpdateDishData, VariantData};
+use crate::error::AppResult;
+use crate::entities::dish::Status;
 use crate::entities::prelude::{Category, Dish};
 use crate::JsonResult;
 
@@ -22,13 +30,70 @@ pub async fn create_category(data:JsonBody<CreateCategoryData>)->JsonResult<Stri
 #[handler]
 pub async fn create_dish(data:JsonBody<CreateDishData>)->JsonResult<String>{
     let data = data.into_inner();
-    let id = DishCurd::insert(data.name, data.price, data.picture).await?;
+    let variants: Vec<VariantData> = data.variants.into_iter().map(Into::into).collect();
+    check_variants(&variants).map_err(|reason| StatusError::bad_request().brief(reason))?;
+    check_combo_slots(&data.combo_slots).map_err(|reason| StatusError::bad_request().brief(reason))?;
+    let price = variants.first().map(|variant| variant.price).unwrap_or(data.price);
+    let id = DishCurd::insert(data.name, price, data.picture, data.station).await?;
+    if !variants.is_empty() {
+        DishCurd::replace_variants(get_db_coon(), id.clone(), variants).await?;
+    }
+    if !data.combo_slots.is_empty() {
+        ComboCurd::replace_slots(get_db_coon(), id.clone(), data.combo_slots).await?;
+    }
     for category_id in data.category_ids{
         CategoryDishMapCurd::insert(category_id, id.clone()).await?;
     }
     Ok(Json(id))
 }
 #[handler]
+pub async fn update_category(id:PathParam<String>, data:JsonBody<UpdateCategoryData>)->JsonResult<Category>{
+    let category = CategoryCurd::update(id.into_inner(), data.into_inner()).await?;
+    Ok(Json(category))
+}
+/// 整体替换分类，部分修改使用PATCH
+#[handler]
+pub async fn replace_category(id:PathParam<String>, data:JsonBody<ReplaceCategoryData>)->JsonResult<Category>{
+    let category = CategoryCurd::update(id.into_inner(), data.into_inner().into()).await?;
+    Ok(Json(category))
+}
+/// 整体替换菜品，部分修改使用PATCH
+#[handler]
+pub async fn replace_dish(id:PathParam<String>, data:JsonBody<ReplaceDishData>)->JsonResult<Dish>{
+    let dish = DishCurd::replace(id.into_inner(), data.into_inner()).await?;
+    Ok(Json(dish))
+}
+#[handler]
+pub async fn update_dish(id:PathParam<String>, data:JsonBody<UpdateDishData>)->JsonResult<Dish>{
+    let dish = DishCurd::update(id.into_inner(), data.into_inner()).await?;
+    Ok(Json(dish))
+}
+#[handler]
+pub async fn delist_dish(id:PathParam<String>)->JsonResult<Dish>{
+    let dish = DishCurd::set_status(id.into_inner(), Status::Delist).await?;
+    Ok(Json(dish))
+}
+#[handler]
+pub async fn relist_dish(id:PathParam<String>)->JsonResult<Dish>{
+    let dish = DishCurd::set_status(id.into_inner(), Status::Normal).await?;
+    Ok(Json(dish))
+}
+#[handler]
+pub async fn reorder_categories(data:JsonBody<ReorderData>)->JsonResult<()>{
+    CategoryCurd::reorder(data.into_inner().ids).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn reorder_dishes(data:JsonBody<ReorderData>)->JsonResult<()>{
+    DishCurd::reorder(data.into_inner().ids).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn reorder_category_dishes(id:PathParam<String>, data:JsonBody<ReorderData>)->JsonResult<()>{
+    CategoryDishMapCurd::reorder(id.into_inner(), data.into_inner().ids).await?;
+    Ok(Json(()))
+}
+#[handler]
 pub async fn delete_category(id:PathParam<String>)->JsonResult<()>{
     let id = id.into_inner();
     CategoryDishMapCurd::delete_by_category_id(id.clone()).await?;
@@ -45,16 +110,20 @@ pub async fn delete_dish(id:PathParam<String>)->JsonResult<()>{
 #[handler]
 pub async fn get_menu()->Json<Vec<CategoryWithDishes>>{
     info!("get menu");
-    Json(query_menu().await)
+    Json(query_menu(true).await)
 }
 #[handler]
-pub async fn get_all_categories()->JsonResult<Vec<Category>>{
-    let models = CategoryCurd::query_all().await?;
+pub async fn get_all_categories()->JsonResult<Vec<CategoryDetail>>{
+    let availability = Availability::load(get_db_coon()).await?;
+    let models = CategoryCurd::query_all().await?
+        .into_iter()
+        .map(|category| CategoryDetail{ available_now: availability.category_open(&category.id), category })
+        .collect();
     Ok(Json(models))
 }
 #[handler]
-pub async fn get_all_dishes()->JsonResult<Vec<Dish>>{
-    let models = DishCurd::query_all().await?;
+pub async fn get_all_dishes()->JsonResult<Vec<DishDetail>>{
+    let models = DishCurd::with_details(DishCurd::query_all().await?).await?;
     Ok(Json(models))
 }
 #[handler]
@@ -62,4 +131,36 @@ pub async fn get_dishes_by_category(id:PathParam<String>) ->JsonResult<Vec<Dish>
     let id = id.into_inner();
     let models = CategoryCurd::query_related_dishes(id).await?;
     Ok(Json(models))
-}
\ No newline at end of file
+}
+/// 导出菜单，`format`为json(默认)或csv
+#[handler]
+pub async fn export_menu(req: &mut Request, res: &mut Response) -> AppResult<()> {
+    let menu = MenuCurd::export().await?;
+    if req.query::<String>("format").as_deref() == Some("csv") {
+        res.add_header(CONTENT_DISPOSITION, "attachment; filename=\"menu.csv\"", true)?;
+        res.add_header(CONTENT_TYPE, "text/csv; charset=utf-8", true)?;
+        res.write_body(menu.to_csv()?)?;
+    } else {
+        res.render(Json(menu));
+    }
+    Ok(())
+}
+/// 导入的菜单最大4MB
+const IMPORT_MAX_SIZE: usize = 4 * 1024 * 1024;
+/// 导入菜单，请求体为json或csv(`format=csv`)，`dry_run=true`时只返回会发生的变化
+#[handler]
+pub async fn import_menu(req: &mut Request) -> JsonResult<ImportReport> {
+    let dry_run = req.query::<bool>("dry_run").unwrap_or(false);
+    let csv = req.query::<String>("format").as_deref() == Some("csv");
+    let body = req.payload_with_max_size(IMPORT_MAX_SIZE).await?;
+    let text = std::str::from_utf8(body)
+        .map_err(|_| StatusError::bad_request().brief("导入的内容必须是UTF-8编码"))?;
+    let menu = if csv {
+        MenuData::from_csv(text)?
+    } else {
+        serde_json::from_str(text)
+            .map_err(|e| StatusError::bad_request().brief(format!("菜单格式错误: {}", e)))?
+    };
+    let report = MenuCurd::import(menu, dry_run).await?;
+    Ok(Json(report))
+}
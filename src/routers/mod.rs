@@ -1,14 +1,34 @@
 use salvo::prelude::*;
+use salvo::serve_static::StaticDir;
 use crate::config::get_config;
-use crate::hoops::jwt::auth_hoop;
+use crate::hoops::jwt::{auth_hoop, member_auth_hoop, table_auth_hoop};
 use crate::hoops::jwt;
+use crate::images::IMAGE_DIR;
 
 mod auth;
 mod user;
 mod menu;
+mod order;
+mod dining_table;
+mod customer;
+mod kitchen;
+mod live;
+mod bill_split;
+mod payment;
+mod adjustment;
+mod print;
+mod report;
+mod modifier;
+mod stock;
+mod ingredient;
+mod schedule;
+mod image;
+mod pricing;
+mod promotion;
+mod member;
 
 pub fn root() -> Router {
-    let router = Router::new()
+    Router::new()
         .push(
             Router::with_path("validate_token")
                 .get(jwt::validate_token)
@@ -21,6 +41,64 @@ pub fn root() -> Router {
             Router::with_path("login")
                 .post(auth::post_login)
         )
+        .push(
+            Router::with_path("images/{**path}")
+                .hoop(image::cache_forever)
+                .get(StaticDir::new(IMAGE_DIR.clone()))
+        )
+        .push(
+            Router::with_path("member")
+                .push(
+                    Router::with_path("register")
+                        .post(member::register)
+                )
+                .push(
+                    Router::with_path("login")
+                        .post(member::login)
+                )
+                .push(
+                    Router::new()
+                        .hoop(member_auth_hoop(&get_config().jwt))
+                        .push(
+                            Router::with_path("me")
+                                .get(member::get_me)
+                        )
+                        .push(
+                            Router::with_path("points")
+                                .get(member::get_points)
+                        )
+                )
+        )
+        .push(
+            Router::with_path("customer")
+                .hoop(table_auth_hoop(&get_config().jwt))
+                .push(
+                    Router::with_path("menu")
+                        .get(customer::get_menu)
+                )
+                .push(
+                    Router::with_path("order")
+                        .post(customer::create_order)
+                )
+                .push(
+                    Router::with_path("ws")
+                        .goal(live::customer_ws)
+                )
+                .push(
+                    Router::with_path("cart")
+                        .get(customer::get_cart)
+                        .post(customer::add_cart_item)
+                        .push(
+                            Router::with_path("submit")
+                                .post(customer::submit_cart)
+                        )
+                        .push(
+                            Router::with_path("{id}")
+                                .post(customer::update_cart_item)
+                                .delete(customer::delete_cart_item)
+                        )
+                )
+        )
         .push(
             Router::new()
                 .hoop(auth_hoop(&get_config().jwt))
@@ -34,6 +112,86 @@ pub fn root() -> Router {
                             Router::with_path("dish")
                                 .post(menu::create_dish)
                         )
+                        .push(
+                            Router::with_path("order")
+                                .post(order::create_order)
+                        )
+                        .push(
+                            Router::with_path("dining_table")
+                                .post(dining_table::create_dining_table)
+                        )
+                        .push(
+                            Router::with_path("modifier_group")
+                                .post(modifier::create_modifier_group)
+                        )
+                        .push(
+                            Router::with_path("stock")
+                                .post(stock::set_stock)
+                        )
+                        .push(
+                            Router::with_path("ingredient")
+                                .post(ingredient::create_ingredient)
+                        )
+                        .push(
+                            Router::with_path("pricing_rule")
+                                .post(pricing::create_pricing_rule)
+                        )
+                        .push(
+                            Router::with_path("promotion")
+                                .post(promotion::create_promotion)
+                        )
+                        .push(
+                            Router::with_path("coupon")
+                                .post(promotion::create_coupon)
+                        )
+                )
+                .push(
+                    Router::with_path("update")
+                        .push(
+                            Router::with_path("dining_table/{id}")
+                                .post(dining_table::update_dining_table)
+                        )
+                        .push(
+                            Router::with_path("ingredient/{id}")
+                                .post(ingredient::update_ingredient)
+                        )
+                        .push(
+                            Router::with_path("pricing_rule/{id}")
+                                .post(pricing::update_pricing_rule)
+                        )
+                        .push(
+                            Router::with_path("promotion/{id}")
+                                .post(promotion::update_promotion)
+                        )
+                        .push(
+                            Router::with_path("coupon/{id}")
+                                .post(promotion::update_coupon)
+                        )
+                        .push(
+                            Router::with_path("category/{id}")
+                                .put(menu::replace_category)
+                                .patch(menu::update_category)
+                        )
+                        .push(
+                            Router::with_path("dish/{id}")
+                                .put(menu::replace_dish)
+                                .patch(menu::update_dish)
+                        )
+                )
+                .push(
+                    Router::with_path("reorder")
+                        .push(
+                            Router::with_path("categories")
+                                .post(menu::reorder_categories)
+                        )
+                        .push(
+                            Router::with_path("dishes")
+                                .post(menu::reorder_dishes)
+                        )
+                        .push(
+                            Router::with_path("category/{id}/dishes")
+                                .post(menu::reorder_category_dishes)
+                        )
                 )
                 .push(
                     Router::with_path("delete")
@@ -45,6 +203,173 @@ pub fn root() -> Router {
                             Router::with_path("dish/{id}")
                                 .delete(menu::delete_dish)
                         )
+                        .push(
+                            Router::with_path("dining_table/{id}")
+                                .delete(dining_table::delete_dining_table)
+                        )
+                        .push(
+                            Router::with_path("modifier_group/{id}")
+                                .delete(modifier::delete_modifier_group)
+                        )
+                        .push(
+                            Router::with_path("stock/{id}")
+                                .delete(stock::delete_stock)
+                        )
+                        .push(
+                            Router::with_path("ingredient/{id}")
+                                .delete(ingredient::delete_ingredient)
+                        )
+                        .push(
+                            Router::with_path("pricing_rule/{id}")
+                                .delete(pricing::delete_pricing_rule)
+                        )
+                        .push(
+                            Router::with_path("promotion/{id}")
+                                .delete(promotion::delete_promotion)
+                        )
+                        .push(
+                            Router::with_path("coupon/{id}")
+                                .delete(promotion::delete_coupon)
+                        )
+                )
+                .push(
+                    Router::with_path("order/{id}/transition")
+                        .post(order::transition_order)
+                )
+                .push(
+                    Router::with_path("order/{id}/split")
+                        .get(bill_split::get_bill_splits)
+                        .post(bill_split::split_bill)
+                )
+                .push(
+                    Router::with_path("order/{id}/pay")
+                        .post(payment::pay_order)
+                )
+                .push(
+                    Router::with_path("order/{id}/payments")
+                        .get(payment::get_payments)
+                )
+                .push(
+                    Router::with_path("bill_split/{id}/pay")
+                        .post(payment::pay_bill_split)
+                )
+                .push(
+                    Router::with_path("order/{id}/refund")
+                        .post(adjustment::refund)
+                )
+                .push(
+                    Router::with_path("order/{id}/adjustments")
+                        .get(adjustment::get_adjustments)
+                )
+                .push(
+                    Router::with_path("order/{id}/receipt")
+                        .get(print::get_receipt)
+                )
+                .push(
+                    Router::with_path("order/{id}/kitchen_tickets")
+                        .get(print::get_kitchen_tickets)
+                )
+                .push(
+                    Router::with_path("order/{id}/print/receipt")
+                        .post(print::print_receipt)
+                )
+                .push(
+                    Router::with_path("order/{id}/print/kitchen")
+                        .post(print::print_kitchen_tickets)
+                )
+                .push(
+                    Router::with_path("menu/export")
+                        .get(menu::export_menu)
+                )
+                .push(
+                    Router::with_path("menu/import")
+                        .post(menu::import_menu)
+                )
+                .push(
+                    Router::with_path("report/daily")
+                        .get(report::get_daily_report)
+                        .push(
+                            Router::with_path("close")
+                                .post(report::close_day)
+                        )
+                )
+                .push(
+                    Router::with_path("upload/image")
+                        .post(image::upload_image)
+                )
+                .push(
+                    Router::with_path("dish/{id}/picture")
+                        .post(image::upload_dish_picture)
+                )
+                .push(
+                    Router::with_path("dish/{id}/delist")
+                        .post(menu::delist_dish)
+                )
+                .push(
+                    Router::with_path("dish/{id}/relist")
+                        .post(menu::relist_dish)
+                )
+                .push(
+                    Router::with_path("dish/{id}/recipe")
+                        .get(ingredient::get_recipe)
+                        .post(ingredient::set_recipe)
+                )
+                .push(
+                    Router::with_path("category/{id}/schedule")
+                        .get(schedule::get_category_schedule)
+                        .post(schedule::set_category_schedule)
+                )
+                .push(
+                    Router::with_path("dish/{id}/schedule")
+                        .get(schedule::get_dish_schedule)
+                        .post(schedule::set_dish_schedule)
+                )
+                .push(
+                    Router::with_path("report/dish_costs")
+                        .get(ingredient::get_dish_costs)
+                )
+                .push(
+                    Router::with_path("stock/alerts")
+                        .get(stock::get_stock_alerts)
+                )
+                .push(
+                    Router::with_path("stock/{id}/adjust")
+                        .post(stock::adjust_stock)
+                )
+                .push(
+                    Router::with_path("stock/{id}/movements")
+                        .get(stock::get_stock_movements)
+                )
+                .push(
+                    Router::with_path("order_item/{id}/void")
+                        .post(adjustment::void_item)
+                )
+                .push(
+                    Router::with_path("order_item/{id}/transition")
+                        .post(order::transition_item)
+                )
+                .push(
+                    Router::with_path("kitchen/feed")
+                        .get(kitchen::kitchen_feed)
+                )
+                .push(
+                    Router::with_path("ws")
+                        .push(
+                            Router::with_path("order/{id}")
+                                .goal(live::order_ws)
+                        )
+                        .push(
+                            Router::with_path("table_session/{id}")
+                                .goal(live::table_session_ws)
+                        )
+                )
+                .push(
+                    Router::with_path("dining_table/{id}/open_session")
+                        .post(dining_table::open_session)
+                )
+                .push(
+                    Router::with_path("table_session/{id}/close")
+                        .post(dining_table::close_session)
                 )
                 .push(
                     Router::with_path("get")
@@ -64,7 +389,42 @@ pub fn root() -> Router {
                             Router::with_path("dish_by_category/{id}")
                                 .get(menu::get_dishes_by_category)
                         )
+                        .push(
+                            Router::with_path("all_dining_tables")
+                                .get(dining_table::get_all_dining_tables)
+                        )
+                        .push(
+                            Router::with_path("all_modifier_groups")
+                                .get(modifier::get_all_modifier_groups)
+                        )
+                        .push(
+                            Router::with_path("all_stocks")
+                                .get(stock::get_all_stocks)
+                        )
+                        .push(
+                            Router::with_path("all_ingredients")
+                                .get(ingredient::get_all_ingredients)
+                        )
+                        .push(
+                            Router::with_path("all_pricing_rules")
+                                .get(pricing::get_all_pricing_rules)
+                        )
+                        .push(
+                            Router::with_path("all_promotions")
+                                .get(promotion::get_all_promotions)
+                        )
+                        .push(
+                            Router::with_path("all_coupons")
+                                .get(promotion::get_all_coupons)
+                        )
+                        .push(
+                            Router::with_path("member")
+                                .get(member::get_member)
+                        )
+                        .push(
+                            Router::with_path("member_points")
+                                .get(member::get_member_points)
+                        )
                 )
-        );
-    router
+        )
 }
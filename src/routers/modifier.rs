@@ -0,0 +1,25 @@
+use salvo::{handler, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::modifier::ModifierCurd;
+use crate::dto::modifier::{CreateModifierGroupData, ModifierGroupDetail};
+use crate::JsonResult;
+
+#[handler]
+pub async fn create_modifier_group(data: JsonBody<CreateModifierGroupData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = ModifierCurd::insert(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn delete_modifier_group(id: PathParam<String>) -> JsonResult<()> {
+    ModifierCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_modifier_groups() -> JsonResult<Vec<ModifierGroupDetail>> {
+    let groups = ModifierCurd::query_all().await?;
+    Ok(Json(groups))
+}
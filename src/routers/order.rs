@@ -0,0 +1,47 @@
+use salvo::{handler, Depot, Writer};
+use salvo::jwt_auth::JwtAuthDepotExt;
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::order::OrderCurd;
+use crate::dto::order::{CreateOrderData, CreateOrderResult, OrderOptions, TransitionItemData, TransitionOrderData};
+use crate::entities::prelude::{Order, OrderItem};
+use crate::error::AppError;
+use crate::hoops::jwt::JwtClaims;
+use crate::JsonResult;
+
+/// 从depot中取出当前登录用户的id
+pub fn current_uid(depot: &Depot) -> Result<String, AppError> {
+    depot
+        .jwt_auth_data::<JwtClaims>()
+        .map(|data| data.claims.uid.clone())
+        .ok_or_else(|| AppError::internal("jwt auth data not found in depot"))
+}
+
+#[handler]
+pub async fn create_order(data: JsonBody<CreateOrderData>, depot: &mut Depot) -> JsonResult<CreateOrderResult> {
+    let data = data.into_inner();
+    data.validate()?;
+    let uid = current_uid(depot)?;
+    let options = OrderOptions {
+        channel: data.channel,
+        coupon_code: data.coupon_code,
+        member_id: data.member_id,
+        redeem_points: data.redeem_points,
+    };
+    let (id, total) = OrderCurd::insert(uid, data.table_session_id, options, data.items).await?;
+    Ok(Json(CreateOrderResult { id, total }))
+}
+/// 后厨、服务员、收银都通过这个接口变更订单状态
+#[handler]
+pub async fn transition_order(id: PathParam<String>, data: JsonBody<TransitionOrderData>, depot: &mut Depot) -> JsonResult<Order> {
+    let uid = current_uid(depot)?;
+    let order = OrderCurd::transition(id.into_inner(), data.into_inner().status, uid).await?;
+    Ok(Json(order))
+}
+/// 后厨开始制作、上菜或者退菜
+#[handler]
+pub async fn transition_item(id: PathParam<String>, data: JsonBody<TransitionItemData>) -> JsonResult<OrderItem> {
+    let item = OrderCurd::transition_item(id.into_inner(), data.into_inner().status).await?;
+    Ok(Json(item))
+}
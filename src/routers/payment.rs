@@ -0,0 +1,38 @@
+use salvo::{handler, Depot, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::config::db::get_db_coon;
+use crate::das::bill_split::BillSplitCurd;
+use crate::das::payment::PaymentCurd;
+use crate::dto::payment::PayData;
+use crate::entities::prelude::Payment;
+use crate::routers::order::current_uid;
+use crate::JsonResult;
+
+#[handler]
+pub async fn pay_order(id: PathParam<String>, data: JsonBody<PayData>, depot: &mut Depot) -> JsonResult<Vec<Payment>> {
+    let data = data.into_inner();
+    data.validate()?;
+    let uid = current_uid(depot)?;
+    let payments = PaymentCurd::pay(id.into_inner(), data.tenders, uid).await?;
+    Ok(Json(payments))
+}
+/// 支付其中一份分单，所有支付方式都计入这一份
+#[handler]
+pub async fn pay_bill_split(id: PathParam<String>, data: JsonBody<PayData>, depot: &mut Depot) -> JsonResult<Vec<Payment>> {
+    let mut data = data.into_inner();
+    data.validate()?;
+    let uid = current_uid(depot)?;
+    let split = BillSplitCurd::query_by_id(get_db_coon(), id.into_inner()).await?;
+    for tender in data.tenders.iter_mut() {
+        tender.bill_split_id = Some(split.id.clone());
+    }
+    let payments = PaymentCurd::pay(split.order_id, data.tenders, uid).await?;
+    Ok(Json(payments))
+}
+#[handler]
+pub async fn get_payments(id: PathParam<String>) -> JsonResult<Vec<Payment>> {
+    let payments = PaymentCurd::query_by_order(get_db_coon(), id.into_inner()).await?;
+    Ok(Json(payments))
+}
@@ -0,0 +1,33 @@
+use salvo::{handler, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::pricing::PricingCurd;
+use crate::dto::pricing::PricingRuleData;
+use crate::entities::prelude::PricingRule;
+use crate::JsonResult;
+
+#[handler]
+pub async fn create_pricing_rule(data: JsonBody<PricingRuleData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = PricingCurd::insert(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_pricing_rule(id: PathParam<String>, data: JsonBody<PricingRuleData>) -> JsonResult<PricingRule> {
+    let data = data.into_inner();
+    data.validate()?;
+    let rule = PricingCurd::update(id.into_inner(), data).await?;
+    Ok(Json(rule))
+}
+#[handler]
+pub async fn delete_pricing_rule(id: PathParam<String>) -> JsonResult<()> {
+    PricingCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_pricing_rules() -> JsonResult<Vec<PricingRule>> {
+    let rules = PricingCurd::query_all().await?;
+    Ok(Json(rules))
+}
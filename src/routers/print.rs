@@ -0,0 +1,45 @@
+use salvo::{handler, Request, Response, Writer};
+use salvo::http::header::CONTENT_TYPE;
+use salvo::oapi::extract::PathParam;
+use salvo::prelude::{Json, Text};
+use crate::config::get_config;
+use crate::dto::print::KitchenTicketPreview;
+use crate::error::AppResult;
+use crate::print::{self, ticket, OrderTicketData};
+use crate::JsonResult;
+
+/// 结账小票，默认返回纯文本预览，`format=escpos`时返回发给打印机的原始字节
+#[handler]
+pub async fn get_receipt(id: PathParam<String>, req: &mut Request, res: &mut Response) -> AppResult<()> {
+    let data = OrderTicketData::load(id.into_inner()).await?;
+    let document = ticket::receipt(&get_config().print, &data);
+    if req.query::<String>("format").as_deref() == Some("escpos") {
+        res.add_header(CONTENT_TYPE, "application/octet-stream", true)?;
+        res.write_body(document.to_escpos())?;
+    } else {
+        res.render(Text::Plain(document.to_text()));
+    }
+    Ok(())
+}
+#[handler]
+pub async fn get_kitchen_tickets(id: PathParam<String>) -> JsonResult<Vec<KitchenTicketPreview>> {
+    let data = OrderTicketData::load(id.into_inner()).await?;
+    let previews = ticket::kitchen_tickets(&get_config().print, &data)
+        .into_iter()
+        .map(|(station, document)| KitchenTicketPreview { station, text: document.to_text() })
+        .collect();
+    Ok(Json(previews))
+}
+#[handler]
+pub async fn print_receipt(id: PathParam<String>) -> JsonResult<()> {
+    let data = OrderTicketData::load(id.into_inner()).await?;
+    print::print_receipt(&get_config().print, &data).await?;
+    Ok(Json(()))
+}
+/// 重新打印后厨单，返回打印了的档口
+#[handler]
+pub async fn print_kitchen_tickets(id: PathParam<String>) -> JsonResult<Vec<Option<String>>> {
+    let data = OrderTicketData::load(id.into_inner()).await?;
+    let stations = print::print_kitchen_tickets(&get_config().print, &data).await?;
+    Ok(Json(stations))
+}
@@ -0,0 +1,58 @@
+use salvo::{handler, Request, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::promotion::PromotionCurd;
+use crate::dto::promotion::{CouponData, PromotionData};
+use crate::entities::prelude::{Coupon, Promotion};
+use crate::JsonResult;
+
+#[handler]
+pub async fn create_promotion(data: JsonBody<PromotionData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = PromotionCurd::insert(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_promotion(id: PathParam<String>, data: JsonBody<PromotionData>) -> JsonResult<Promotion> {
+    let data = data.into_inner();
+    data.validate()?;
+    let promotion = PromotionCurd::update(id.into_inner(), data).await?;
+    Ok(Json(promotion))
+}
+#[handler]
+pub async fn delete_promotion(id: PathParam<String>) -> JsonResult<()> {
+    PromotionCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_promotions() -> JsonResult<Vec<Promotion>> {
+    let promotions = PromotionCurd::query_all().await?;
+    Ok(Json(promotions))
+}
+#[handler]
+pub async fn create_coupon(data: JsonBody<CouponData>) -> JsonResult<String> {
+    let data = data.into_inner();
+    data.validate()?;
+    let id = PromotionCurd::insert_coupon(data).await?;
+    Ok(Json(id))
+}
+#[handler]
+pub async fn update_coupon(id: PathParam<String>, data: JsonBody<CouponData>) -> JsonResult<Coupon> {
+    let data = data.into_inner();
+    data.validate()?;
+    let coupon = PromotionCurd::update_coupon(id.into_inner(), data).await?;
+    Ok(Json(coupon))
+}
+#[handler]
+pub async fn delete_coupon(id: PathParam<String>) -> JsonResult<()> {
+    PromotionCurd::delete_coupon(id.into_inner()).await?;
+    Ok(Json(()))
+}
+/// 查询优惠券，可以用promotion_id参数只查一个活动的
+#[handler]
+pub async fn get_all_coupons(req: &mut Request) -> JsonResult<Vec<Coupon>> {
+    let coupons = PromotionCurd::query_coupons(req.query::<String>("promotion_id")).await?;
+    Ok(Json(coupons))
+}
@@ -0,0 +1,29 @@
+use salvo::{handler, Depot, Request, Writer};
+use salvo::oapi::extract::JsonBody;
+use salvo::prelude::Json;
+use crate::das::report::ReportCurd;
+use crate::dto::report::{CloseDayData, DailyReport};
+use crate::routers::order::current_uid;
+use crate::utils::{parse_date, today};
+use crate::JsonResult;
+
+/// 营业日报表，`date`形如2023-07-01，不填时为当前营业日
+#[handler]
+pub async fn get_daily_report(req: &mut Request) -> JsonResult<DailyReport> {
+    let date = match req.query::<String>("date") {
+        Some(date) => parse_date(&date)?,
+        None => today(),
+    };
+    let report = ReportCurd::daily(date).await?;
+    Ok(Json(report))
+}
+#[handler]
+pub async fn close_day(data: JsonBody<CloseDayData>, depot: &mut Depot) -> JsonResult<DailyReport> {
+    let date = match data.into_inner().date {
+        Some(date) => parse_date(&date)?,
+        None => today(),
+    };
+    let uid = current_uid(depot)?;
+    let report = ReportCurd::close_day(date, uid).await?;
+    Ok(Json(report))
+}
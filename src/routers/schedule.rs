@@ -0,0 +1,33 @@
+use salvo::{handler, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::schedule::ScheduleCurd;
+use crate::dto::schedule::{ScheduleData, ScheduleView};
+use crate::entities::schedule_window::Target;
+use crate::JsonResult;
+
+#[handler]
+pub async fn get_category_schedule(id: PathParam<String>) -> JsonResult<ScheduleView> {
+    let schedule = ScheduleCurd::query(Target::Category, id.into_inner()).await?;
+    Ok(Json(schedule))
+}
+#[handler]
+pub async fn set_category_schedule(id: PathParam<String>, data: JsonBody<ScheduleData>) -> JsonResult<ScheduleView> {
+    let data = data.into_inner();
+    data.validate()?;
+    let schedule = ScheduleCurd::replace(Target::Category, id.into_inner(), data).await?;
+    Ok(Json(schedule))
+}
+#[handler]
+pub async fn get_dish_schedule(id: PathParam<String>) -> JsonResult<ScheduleView> {
+    let schedule = ScheduleCurd::query(Target::Dish, id.into_inner()).await?;
+    Ok(Json(schedule))
+}
+#[handler]
+pub async fn set_dish_schedule(id: PathParam<String>, data: JsonBody<ScheduleData>) -> JsonResult<ScheduleView> {
+    let data = data.into_inner();
+    data.validate()?;
+    let schedule = ScheduleCurd::replace(Target::Dish, id.into_inner(), data).await?;
+    Ok(Json(schedule))
+}
@@ -0,0 +1,46 @@
+use salvo::{handler, Depot, Writer};
+use salvo::oapi::extract::{JsonBody, PathParam};
+use salvo::prelude::Json;
+use validator::Validate;
+use crate::das::stock::StockCurd;
+use crate::dto::stock::{AdjustStockData, SetStockData, StockDetail};
+use crate::entities::prelude::{Stock, StockMovement};
+use crate::routers::order::current_uid;
+use crate::JsonResult;
+
+/// 设置库存，盘点时也用这个接口
+#[handler]
+pub async fn set_stock(data: JsonBody<SetStockData>, depot: &mut Depot) -> JsonResult<Stock> {
+    let data = data.into_inner();
+    data.validate()?;
+    let uid = current_uid(depot)?;
+    let stock = StockCurd::set(data, uid).await?;
+    Ok(Json(stock))
+}
+#[handler]
+pub async fn adjust_stock(id: PathParam<String>, data: JsonBody<AdjustStockData>, depot: &mut Depot) -> JsonResult<Stock> {
+    let uid = current_uid(depot)?;
+    let stock = StockCurd::adjust(id.into_inner(), data.into_inner(), uid).await?;
+    Ok(Json(stock))
+}
+#[handler]
+pub async fn delete_stock(id: PathParam<String>) -> JsonResult<()> {
+    StockCurd::delete_by_id(id.into_inner()).await?;
+    Ok(Json(()))
+}
+#[handler]
+pub async fn get_all_stocks() -> JsonResult<Vec<StockDetail>> {
+    let stocks = StockCurd::query_all().await?;
+    Ok(Json(stocks))
+}
+/// 需要补货的菜品
+#[handler]
+pub async fn get_stock_alerts() -> JsonResult<Vec<StockDetail>> {
+    let stocks = StockCurd::query_alerts().await?;
+    Ok(Json(stocks))
+}
+#[handler]
+pub async fn get_stock_movements(id: PathParam<String>) -> JsonResult<Vec<StockMovement>> {
+    let movements = StockCurd::query_movements(id.into_inner()).await?;
+    Ok(Json(movements))
+}
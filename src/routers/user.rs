@@ -16,6 +16,7 @@ pub async fn create_user(in_data: JsonBody<CreateUserData>) -> JsonResult<UserIn
     Ok(Json(UserInfo {id, username}))
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug, Validate, ToSchema)]
 struct UpdateInData {
     #[validate(length(min = 5, message = "username length must be greater than 5"))]
@@ -1,19 +1,27 @@
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::Path;
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash};
 use argon2::password_hash::rand_core::OsRng;
-use time::{format_description, OffsetDateTime, UtcOffset};
+use salvo::http::StatusError;
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use crate::config::{StoreConfig, CONFIG};
 use crate::error::AppResult;
 
+pub mod money;
+
 /// 检查文件是否存在，不存在则创建文件
-pub fn check_file(path: &PathBuf) ->AppResult<bool>{
+#[allow(dead_code)]
+pub fn check_file(path: &Path) ->AppResult<bool>{
     if path.exists() {
         Ok(true)
     } else {
-        let _ = fs::create_dir_all(path)?;
-        let _ = File::create(path)?;
+        fs::create_dir_all(path)?;
+        File::create(path)?;
         Ok(false)
     }
 }
@@ -34,13 +42,70 @@ pub fn check_file(path: &PathBuf) ->AppResult<bool>{
 /// 2023-07-01 12:34:56.789
 /// ```
 pub fn get_now_time() -> String {
-    let format = format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]").unwrap();
-    let now = OffsetDateTime::now_utc();
-    let local_time = now.to_offset(UtcOffset::from_hms(8, 0, 0).unwrap());
-    local_time.format(&format).unwrap()
+    now_local().format(TIME_FORMAT).unwrap()
+}
+const TIME_FORMAT: &[BorrowedFormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]");
+const DATE_FORMAT: &[BorrowedFormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+/// 门店配置，测试时没有加载配置文件，使用默认配置
+fn store_config() -> StoreConfig {
+    CONFIG.get().map(|config| config.store.clone()).unwrap_or_default()
+}
+/// 门店时区的当前时间
+pub fn now_local() -> OffsetDateTime {
+    let offset = store_config().offset().unwrap_or(UtcOffset::UTC);
+    OffsetDateTime::now_utc().to_offset(offset)
+}
+/// 某个时间所属的营业日，营业日开始前的时间算作前一天
+pub fn business_date(time: PrimitiveDateTime) -> Date {
+    (time - Duration::hours(store_config().day_start_hour as i64)).date()
+}
+/// 解析[get_now_time]格式的时间字符串并返回所属的营业日
+pub fn business_date_of(time: &str) -> AppResult<Date> {
+    let time = PrimitiveDateTime::parse(time, TIME_FORMAT)
+        .map_err(|e| anyhow::anyhow!("invalid time {}: {}", time, e))?;
+    Ok(business_date(time))
+}
+pub fn today() -> Date {
+    let now = now_local();
+    business_date(PrimitiveDateTime::new(now.date(), now.time()))
+}
+/// 营业日的起止时间，左闭右开，可以直接和数据库里的时间字符串比较
+pub fn business_day_range(date: Date) -> (String, String) {
+    let start = PrimitiveDateTime::new(date, Time::MIDNIGHT) + Duration::hours(store_config().day_start_hour as i64);
+    let end = start + Duration::days(1);
+    (start.format(TIME_FORMAT).unwrap(), end.format(TIME_FORMAT).unwrap())
+}
+/// 解析形如2023-07-01的日期
+pub fn parse_date(date: &str) -> AppResult<Date> {
+    Date::parse(date, DATE_FORMAT).map_err(|_| {
+        StatusError::bad_request()
+            .brief(format!("日期{}格式错误，应为形如2023-07-01的日期", date))
+            .into()
+    })
+}
+pub fn format_date(date: Date) -> String {
+    date.format(DATE_FORMAT).unwrap()
+}
+/// 检查排序用的id列表和现有的id完全一致，不多不少也不重复
+pub fn check_order(existing: HashSet<String>, ids: &[String]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(format!("id{}重复", id));
+        }
+        if !existing.contains(id) {
+            return Err(format!("id{}不存在", id));
+        }
+    }
+    if seen.len() != existing.len() {
+        return Err("排序列表必须包含全部的项".to_string());
+    }
+    Ok(())
 }
 pub fn verify_password(password: &str, password_hash: &str) -> AppResult<()> {
-    let hash = PasswordHash::new(&password_hash)
+    let hash = PasswordHash::new(password_hash)
         .map_err(|e| anyhow::anyhow!("invalid password hash: {}", e))?;
     let result = hash.verify_password(&[&Argon2::default()], password);
     match result {
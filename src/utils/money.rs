@@ -0,0 +1,212 @@
+//! 金额类型，内部用整数分保存，避免浮点数相加产生误差
+//! 数据库里存为整数，JSON里序列化为两位小数的字符串，例如"12.50"
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use salvo::oapi::schema::{BasicType, Object, Schema, SchemaFormat};
+use salvo::oapi::{Components, RefOr, ToSchema};
+use sea_orm::DeriveValueType;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use validator::ValidationError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, DeriveValueType)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+    /// 单个金额的上限一百万元，单价乘以数量、多笔相加都不会溢出
+    pub const MAX: Money = Money(100_000_000);
+
+    pub const fn from_fen(fen: i64) -> Self {
+        Money(fen)
+    }
+    pub const fn fen(self) -> i64 {
+        self.0
+    }
+    /// 从元转换，超过两位小数时返回错误，只用于兼容以数字形式传入的金额
+    pub fn from_yuan(yuan: f64) -> Result<Self, String> {
+        let fen = (yuan * 100.0).round();
+        if !yuan.is_finite() || (yuan * 100.0 - fen).abs() > 1e-6 {
+            return Err(format!("金额{}超过两位小数", yuan));
+        }
+        Money(fen as i64).checked()
+    }
+    /// 解析出的金额超过[Money::MAX]时返回错误
+    fn checked(self) -> Result<Self, String> {
+        if self.0.unsigned_abs() > Self::MAX.0.unsigned_abs() {
+            return Err(format!("金额{}超过了{}", self, Self::MAX));
+        }
+        Ok(self)
+    }
+    /// 乘以非整数的数量，例如原料用量乘以单价，结果四舍五入到分
+    pub fn mul_f64(self, factor: f64) -> Self {
+        Money((self.0 as f64 * factor).round() as i64)
+    }
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
+/// 用于validator的custom校验，金额不能为负数
+pub fn non_negative(money: &Money) -> Result<(), ValidationError> {
+    if money.is_negative() {
+        return Err(ValidationError::new("negative_money"));
+    }
+    Ok(())
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let fen = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, fen / 100, fen % 100)
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    /// 解析形如"12"、"12.5"、"-0.35"的金额，最多两位小数
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("金额{}格式错误", s);
+        let text = s.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if integer.is_empty() || !integer.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        if fraction.len() > 2 {
+            return Err(format!("金额{}超过两位小数", s));
+        }
+        let yuan: i64 = integer.parse().map_err(|_| invalid())?;
+        let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+        let fen = yuan.checked_mul(100).and_then(|fen| fen.checked_add(fraction)).ok_or_else(invalid)?;
+        Money(if negative { -fen } else { fen }).checked()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    /// 接受字符串，也兼容旧客户端传的数字
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+        impl Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("最多两位小数的金额，例如\"12.50\"")
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+                v.parse().map_err(E::custom)
+            }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                v.checked_mul(100)
+                    .ok_or_else(|| format!("金额{}太大", v))
+                    .and_then(|fen| Money(fen).checked())
+                    .map_err(E::custom)
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v).map_err(E::custom).and_then(|v| self.visit_i64(v))
+            }
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                Money::from_yuan(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+impl ToSchema for Money {
+    fn to_schema(_components: &mut Components) -> RefOr<Schema> {
+        Object::with_type(BasicType::String)
+            .format(SchemaFormat::Custom("decimal".to_string()))
+            .description("金额，最多两位小数，不超过一百万元")
+            .example("12.50")
+            .into()
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+/// 单价乘以数量
+impl Mul<i32> for Money {
+    type Output = Money;
+    fn mul(self, rhs: i32) -> Money {
+        Money(self.0 * rhs as i64)
+    }
+}
+impl Mul<i64> for Money {
+    type Output = Money;
+    fn mul(self, rhs: i64) -> Money {
+        Money(self.0 * rhs)
+    }
+}
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::money::Money;
+
+    #[test]
+    fn test_money() {
+        let a: Money = "0.1".parse().unwrap();
+        let b: Money = "0.2".parse().unwrap();
+        assert_eq!((a + b).to_string(), "0.30");
+        assert_eq!("-3.05".parse::<Money>().unwrap().to_string(), "-3.05");
+        assert!("1.005".parse::<Money>().is_err());
+        assert!("1.2.3".parse::<Money>().is_err());
+        assert_eq!(serde_json::to_string(&Money::from_fen(1250)).unwrap(), "\"12.50\"");
+        assert_eq!(serde_json::from_str::<Money>("12.5").unwrap(), Money::from_fen(1250));
+        assert_eq!(serde_json::from_str::<Money>("\"7\"").unwrap(), Money::from_fen(700));
+        assert!(serde_json::from_str::<Money>("0.125").is_err());
+        assert_eq!("1000000".parse::<Money>().unwrap(), Money::MAX);
+        assert!("1000000.01".parse::<Money>().is_err());
+        assert!("-92233720368547758".parse::<Money>().is_err());
+        assert!(serde_json::from_str::<Money>("92233720368547758").is_err());
+        assert!(serde_json::from_str::<Money>("1e15").is_err());
+    }
+}